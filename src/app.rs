@@ -1,23 +1,43 @@
-use crate::command::Command;
-use crate::config::Config;
+use crate::command::{Command, HighlightAction, InspectorAction, OutputAction, RecordAction};
+use crate::highlight::Theme;
+use crate::config::{Config, McpServerConfig, ServerDiff};
 use crate::event::Event;
-use crate::mcp::{McpClient, McpClientEvent, ToolInfo};
+use crate::jsonrpc::{Notification, RequestId, Response};
+use crate::mcp::{McpClient, McpClientEvent, McpError, ToolInfo};
+use crate::inspector::Inspector;
 use crate::mode::Mode;
-use crate::state::{Buffer, OutputLog};
+use crate::recording::{RecordEntry, Recorder, Recording, ReplayServer};
+use crate::json_tree::JsonTree;
+use crate::query::Query;
+use crate::results::{Pager, ResultStore};
+use crate::viewport::OutputRows;
+use crate::state::{BlockStatus, Buffer, EntryKind, OutputEntry, OutputLog, OutputView, ViewLine, MAX_LOG_LINES};
 use crate::args::{args_to_json, usage_hint};
+use crate::watch::ConfigWatcher;
 use anyhow::Result;
+use chrono::Local;
 use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::time::Instant;
 use tokio::sync::mpsc;
-use unicode_width::UnicodeWidthStr;
+use ratatui::text::Line;
 
 use crate::completion::{CompletionContext, CommandBufferState};
-use crate::tool_formatter::{format_tool_detailed, format_tool_compact};
+use crate::tool_formatter::{format_result, format_tool_detailed, format_tool_compact, ResultLine};
 
 /// Application state with server and tool selection modes
 #[derive(Debug)]
 pub struct App {
     mode: Mode,
     output: OutputLog,
+    /// Kind filter, grouping and highlighting of the output pane
+    output_view: OutputView,
+    /// Highlight theme, kept while highlighting is toggled off
+    theme: Theme,
+    /// Wrapped, styled rows of the output pane
+    rows: OutputRows,
     input_buffer: Buffer,
     // command_buffer: Buffer,
     status: String,
@@ -35,6 +55,62 @@ pub struct App {
     output_width: u16,
     command_state: CommandBufferState,
     completion_context: CompletionContext,
+    inspector: Inspector,
+    recorder: Option<Recorder>,
+    log_file: Option<File>,
+    startup: VecDeque<String>,
+    awaiting_connection: bool,
+    config_watcher: Option<ConfigWatcher>,
+    /// Server of the current (or last) session, for `:mcp reconnect`
+    session_server: Option<String>,
+    /// Destructive call held back until answered with y/n (production profiles)
+    pending_confirmation: Option<Confirmation>,
+    /// Something visible changed since the last frame
+    redraw: bool,
+    /// Requests sent but not answered yet, oldest first
+    in_flight: Vec<InFlight>,
+    /// Advances on every tick while requests are in flight
+    spinner_frame: usize,
+    /// Request block that `za`/`zc`/`zo` act on; the newest one by default
+    current_block: Option<RequestId>,
+    /// First key of a two-key NORMAL mode command (`z`, `[`, `]`)
+    pending_key: Option<char>,
+    /// Complete result payloads; the output log only holds previews
+    results: ResultStore,
+    /// Full-screen view of a result opened with `:result`
+    pager: Option<Pager>,
+    /// Foldable JSON view of a result opened with `:tree`
+    tree: Option<JsonTree>,
+    /// Text yanked since the last frame, copied to the terminal clipboard
+    clipboard: Option<String>,
+}
+
+/// An outgoing request waiting for its response
+#[derive(Debug, Clone)]
+pub struct InFlight {
+    pub id: RequestId,
+    /// Method, or the tool name for tools/call
+    pub label: String,
+    pub server: Option<String>,
+    pub started: Instant,
+}
+
+/// A request that waits for the user's go-ahead
+#[derive(Debug, Clone)]
+enum Confirmation {
+    ToolCall { tool: String, args: serde_json::Value },
+    Rpc { method: String, params: Option<serde_json::Value> },
+}
+
+impl Confirmation {
+    fn tool(&self) -> &str {
+        match self {
+            Confirmation::ToolCall { tool, .. } => tool,
+            Confirmation::Rpc { params, .. } => {
+                params.as_ref().and_then(|p| p["name"].as_str()).unwrap_or_default()
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -54,20 +130,29 @@ impl App {
         let (mcp_event_tx, mcp_event_rx) = mpsc::channel(100);
         let mcp_client = McpClient::new(mcp_event_tx);
 
-        // Extract server names for completion
-        let server_names: Vec<String> = config.mcp_servers
-            .iter()
-            .map(|s| s.name.clone())
-            .collect();
-
         // Initialize completion context with server names
         let completion_context = CompletionContext::new()
-            .with_list("mcp_servers".to_string(), server_names);
+            .with_list("mcp_servers".to_string(), config.server_names())
+            .with_list("profiles".to_string(), config.profiles.keys().cloned().collect());
+
+        let mut output = config.warnings.iter().fold(
+            OutputLog::with_limit(config.output_lines.unwrap_or(MAX_LOG_LINES))
+                .with_message("MCP Client initialized. \nPress ':' for COMMAND mode. (type :h <ENTER> for help)".to_string()),
+            |log, warning| log.with_message(format!("⚠️  config: {}", warning)),
+        );
+        if config.mcp_servers.is_empty() {
+            output = output.with_message(
+                "No MCP servers configured - add one with :mcp add <name> <url|command args...>".to_string(),
+            );
+        }
+        let (theme, highlight) = highlight_setting(&config);
 
         Self {
             mode: Mode::Normal,
-            output: OutputLog::new()
-                .with_message("MCP Client initialized. \nPress ':' for COMMAND mode. (type :h <ENTER> for help)".to_string()),
+            output,
+            output_view: OutputView { highlight, ..OutputView::default() },
+            theme,
+            rows: OutputRows::new(),
             input_buffer: Buffer::new(),
             command_state: CommandBufferState::new(), // NEW
             completion_context,                       // NEW
@@ -84,7 +169,81 @@ impl App {
             autoscroll: true,
             output_height: 0, // Will be updated by the UI loop
             output_width: 0,
+            inspector: Inspector::new(),
+            recorder: None,
+            log_file: None,
+            startup: VecDeque::new(),
+            awaiting_connection: false,
+            config_watcher: None,
+            session_server: None,
+            pending_confirmation: None,
+            redraw: true,
+            in_flight: Vec::new(),
+            spinner_frame: 0,
+            current_block: None,
+            pending_key: None,
+            results: ResultStore::new(),
+            pager: None,
+            tree: None,
+            clipboard: None,
+        }
+    }
+
+    /// Reloads the config whenever its file changes on disk
+    pub fn with_config_watch(mut self) -> Self {
+        if let Err(e) = self.start_config_watch() {
+            self.output = self.output.with_message(format!("⚠️  config: not watching for changes: {}", e));
+        }
+        self
+    }
+
+    /// Watches `config.path` unless already watching. A config that does
+    /// not exist yet is watched once its directory does (after a save).
+    fn start_config_watch(&mut self) -> notify::Result<()> {
+        let Some(path) = &self.config.path else {
+            return Ok(());
+        };
+        if self.config_watcher.is_none() && path.parent().is_none_or(|dir| dir.as_os_str().is_empty() || dir.is_dir()) {
+            self.config_watcher = Some(ConfigWatcher::new(path)?);
         }
+        Ok(())
+    }
+
+    /// Appends protocol and debug messages to `file`
+    pub fn with_log_file(mut self, file: File) -> Self {
+        self.log_file = Some(file);
+        self
+    }
+
+    pub fn with_mouse(mut self, enabled: bool) -> Self {
+        self.mouse_enabled = enabled;
+        self
+    }
+
+    /// Queues commands for `run_startup_commands`
+    pub fn with_startup_commands(mut self, commands: Vec<String>) -> Self {
+        self.startup = commands.into();
+        self
+    }
+
+    /// Runs queued startup commands in order. After an `mcp connect` the
+    /// rest wait until the server's tools are loaded (or connecting fails).
+    pub async fn run_startup_commands(mut self) -> Result<Self> {
+        while !self.awaiting_connection {
+            let Some(text) = self.startup.pop_front() else {
+                break;
+            };
+            let connects = matches!(
+                Command::parse(&text),
+                Ok(Command::McpConnect(Some(name))) if self.config.server(&name).is_some()
+            );
+            self.output = self.output.with_entry(OutputEntry::new(EntryKind::Command, format!(":{}", text)));
+            self.record(RecordEntry::command(text.trim()));
+            self = self.execute_command(&text).await?;
+            self.completion_context = self.completion_context.with_history_entry(text);
+            self.awaiting_connection = connects && !self.startup.is_empty();
+        }
+        Ok(self)
     }
 
     // ═══════════════════════════════════════════════════════════════
@@ -144,19 +303,31 @@ impl App {
     }
 
     /// Returns number of *visual* lines after wrapping the stored logical lines
-    pub fn visual_lines_count(&self) -> usize {
-        let wrap = self.wrap_width().max(1);
-        self.output.lines().iter().map(|s| {
-            let w = UnicodeWidthStr::width(s.as_str());
-            // If string is empty -> one visual line
-            if w == 0 {
-                1usize
-            } else {
-                // ceil division
-                (w + wrap - 1) / wrap
-            }
-        }).sum()
-}    
+    pub fn visual_lines_count(&mut self) -> usize {
+        self.rows.update(&self.output, &self.output_view, self.wrap_width().max(1));
+        self.rows.len()
+    }
+
+    /// Brings the wrapped rows up to date and resolves the scroll offset:
+    /// pinned to the bottom while autoscrolling, clamped otherwise. Output
+    /// changes only mark the bottom; this runs once per frame or scroll.
+    pub fn sync_scroll(&mut self) {
+        if self.autoscroll {
+            self.scroll_offset = self.max_scroll_offset();
+        } else {
+            self.clamp_scroll_offset();
+        }
+    }
+
+    /// Visible rows of the output area, after [`Self::sync_scroll`]
+    pub fn output_window(&self) -> &[Line<'static>] {
+        self.rows.window(self.scroll_offset, self.view_height())
+    }
+
+    /// Rows of the current block's header, for highlighting
+    pub fn current_block_rows(&self) -> Option<std::ops::Range<usize>> {
+        self.current_block.as_ref().and_then(|id| self.rows.header_rows(id))
+    }
 
     // ═══════════════════════════════════════════════════════════════
     // Scrolling methods
@@ -197,8 +368,8 @@ impl App {
         self.clamp_scroll_offset();
     }
 
-    /// Scrolls to the bottom of the output buffer if autoscroll is enabled.
-    /// This is the primary method for auto-scrolling.
+    // Scrolls to the bottom of the output buffer if autoscroll is enabled.
+    // This is the primary method for auto-scrolling.
     // fn update_scroll_after_output_change(&mut self) {
     //     if self.autoscroll {
     //         let max = self.max_scroll_offset();
@@ -219,11 +390,11 @@ impl App {
         self.output_height.saturating_sub(2) as usize
     }
 
-    pub fn content_visual_len(&self) -> usize {
+    pub fn content_visual_len(&mut self) -> usize {
         self.visual_lines_count()
     }
 
-    pub fn max_scroll_offset(&self) -> usize {
+    pub fn max_scroll_offset(&mut self) -> usize {
         self.content_visual_len().saturating_sub(self.view_height())
     }
 
@@ -236,11 +407,13 @@ impl App {
 
     // Move scroll
     pub fn scroll_up(&mut self) {
+            self.sync_scroll();
             self.disable_autoscroll();
             self.scroll_offset = self.scroll_offset.saturating_sub(1);
     }
 
     pub fn scroll_down(&mut self) {
+        self.sync_scroll();
         self.disable_autoscroll();
         let max = self.max_scroll_offset();
         if self.scroll_offset < max {
@@ -251,9 +424,10 @@ impl App {
         }
     }
 
+    /// Pins the view to the bottom; the offset is resolved lazily by
+    /// [`Self::sync_scroll`], so appending many lines stays cheap
     pub fn scroll_to_bottom(&mut self) {
         self.enable_autoscroll();
-        self.scroll_offset = self.max_scroll_offset();
     }
 
     /// Jumps to the bottom and re-enables autoscroll.
@@ -270,8 +444,17 @@ impl App {
         self.mode
     }
 
-    pub fn output(&self) -> &[String] {
-        self.output.lines()
+    pub fn output(&self) -> &[OutputEntry] {
+        self.output.entries()
+    }
+
+    pub fn current_block(&self) -> Option<&RequestId> {
+        self.current_block.as_ref()
+    }
+
+    /// Output as displayed: filtered and grouped per the output view
+    pub fn output_lines(&self) -> Vec<ViewLine<'_>> {
+        self.output.view(&self.output_view)
     }
 
     pub const fn scroll_offset(&self) -> usize {
@@ -313,6 +496,84 @@ impl App {
     pub const fn mouse_enabled(&self) -> bool {
         self.mouse_enabled
     }
+    pub fn inspector(&self) -> &Inspector {
+        &self.inspector
+    }
+
+    pub fn results(&self) -> &ResultStore {
+        &self.results
+    }
+
+    pub fn pager(&self) -> Option<&Pager> {
+        self.pager.as_ref()
+    }
+
+    /// Rows available to the pager, set by the renderer
+    pub fn set_pager_height(&mut self, height: usize) {
+        if let Some(pager) = self.pager.take() {
+            self.pager = Some(pager.with_height(height));
+        }
+    }
+
+    /// Theme of the output highlighting; None when it is off
+    pub const fn highlight(&self) -> Option<Theme> {
+        self.output_view.highlight
+    }
+
+    pub fn tree(&self) -> Option<&JsonTree> {
+        self.tree.as_ref()
+    }
+
+    /// Rows available to the tree view, set by the renderer
+    pub fn set_tree_height(&mut self, height: usize) {
+        if let Some(tree) = self.tree.take() {
+            self.tree = Some(tree.with_height(height));
+        }
+    }
+
+    /// Text yanked since the last call, for the terminal clipboard
+    pub fn take_clipboard(&mut self) -> Option<String> {
+        self.clipboard.take()
+    }
+
+    /// Name of the active config profile
+    pub fn profile(&self) -> Option<&str> {
+        self.config.active_profile.as_deref()
+    }
+
+    pub fn is_production(&self) -> bool {
+        self.config.is_production()
+    }
+
+    pub fn in_flight(&self) -> &[InFlight] {
+        &self.in_flight
+    }
+
+    pub const fn spinner_frame(&self) -> usize {
+        self.spinner_frame
+    }
+
+    pub const fn is_confirming(&self) -> bool {
+        self.pending_confirmation.is_some()
+    }
+
+    pub const fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Appends to the active recording; a write failure stops recording
+    fn record(&mut self, entry: RecordEntry) {
+        let Some(recorder) = &mut self.recorder else {
+            return;
+        };
+        if let Err(e) = recorder.write(&entry) {
+            self.recorder = None;
+            self.output = std::mem::take(&mut self.output)
+                .with_message(format!("❌ Recording stopped: {}", e));
+            self.status = "Recording stopped".into();
+        }
+    }
+
     // NEW: Accessor for completion state
     pub fn completion_popup(&self) -> Option<&crate::completion::CompletionResult> {
         self.command_state.completion.as_ref()
@@ -325,39 +586,83 @@ impl App {
 
     pub async fn handle_event(mut self, event: Event) -> Result<Self> {
         match event {
-            Event::Key(key) => self.handle_key(key.code, key.modifiers).await,
+            Event::Key(key) => {
+                self.redraw = true;
+                self.handle_key(key.code, key.modifiers).await
+            }
+            Event::Resize => {
+                self.redraw = true;
+                Ok(self)
+            }
+            Event::Mcp(mcp_event) => {
+                self.redraw = true;
+                self.handle_mcp_event(mcp_event).await?.drain_mcp_events().await
+            }
             Event::Tick => {
-                if let Ok(mcp_event) = self.mcp_event_rx.try_recv() {
-                    self.handle_mcp_event(mcp_event).await
-                } else {
-                    Ok(self)
+                if self.config_watcher.as_mut().is_some_and(|w| w.poll()) {
+                    self = self.reload_config();
+                    self.redraw = true;
                 }
+                if !self.in_flight.is_empty() {
+                    self.spinner_frame = self.spinner_frame.wrapping_add(1);
+                    self.redraw = true;
+                    self.expire_requests().await;
+                }
+                self.drain_mcp_events().await
             }
         }
     }
 
+    /// Handles every MCP event already queued, so a burst (a long tool
+    /// result arrives line by line) lands in a single frame
+    async fn drain_mcp_events(mut self) -> Result<Self> {
+        while let Ok(mcp_event) = self.mcp_event_rx.try_recv() {
+            self.redraw = true;
+            self = self.handle_mcp_event(mcp_event).await?;
+        }
+        Ok(self)
+    }
+
+    /// Whether the screen needs redrawing; resets the flag
+    pub fn take_redraw(&mut self) -> bool {
+        std::mem::take(&mut self.redraw)
+    }
+
     async fn handle_mcp_event(mut self, event: McpClientEvent) -> Result<Self> {
-        match event {
+        self.log_event(&event);
+        let settles_connection = matches!(
+            event,
+            McpClientEvent::ToolsListed(_) | McpClientEvent::Error(_) | McpClientEvent::Disconnected
+        );
 
-            McpClientEvent::LargeResponse { total_lines, chunk } => {
-                self.output = self.output.with_message(chunk);
-                self.status = format!("Receiving large response ({} lines)...", total_lines);
-                self.scroll_to_bottom();
-            }
+        match event {
             McpClientEvent::Connected => {
                 self.status = "MCP client connected".into();
             }
+            McpClientEvent::Initialized(result) => {
+                let name = result["serverInfo"]["name"].as_str().unwrap_or("server");
+                self.status = format!("Session initialized with {}", name);
+            }
             McpClientEvent::Disconnected => {
                 self.status = "MCP client disconnected".into();
-                // Clear tools on disconnect
+                // Clear tools on disconnect; no responses will arrive
                 self.available_tools.clear();
+                self.in_flight.clear();
+                self.output = self.output.cancel_running();
             }
             McpClientEvent::Message(msg) => {
-                self.output = self.output.with_message(msg);
+                let entry = self.server_entry(EntryKind::System, msg);
+                self.output = self.output.with_entry(entry);
                 self.scroll_to_bottom();
             }
             McpClientEvent::Error(err) => {
-                self.output = self.output.with_message(format!("❌ [MCP Error] {}", err));
+                let entry = self.server_entry(EntryKind::Error, format!("❌ [MCP Error] {}", err));
+                self.output = self.output.with_entry(entry);
+                self.scroll_to_bottom();
+            }
+            McpClientEvent::Notification(notification) => {
+                let entry = self.server_entry(EntryKind::Notification, describe_notification(&notification));
+                self.output = self.output.with_entry(entry);
                 self.scroll_to_bottom();
             }
             McpClientEvent::ToolsListed(tools) => {
@@ -394,14 +699,145 @@ impl App {
                 self.status = format!("Loaded {} tools", tools.len());
             }
             McpClientEvent::Debug(msg) => {
-                self.output = self.output.with_message(format!("🔍 {}", msg));
+                let entry = self.server_entry(EntryKind::Debug, format!("🔍 {}", msg));
+                self.output = self.output.with_entry(entry);
+                self.scroll_to_bottom();
+            }
+            McpClientEvent::Traffic(record) => {
+                self.record(RecordEntry::from(&record));
+                self.inspector = self.inspector.with_record(record);
+            }
+            McpClientEvent::Response(response) => {
+                if let Some(id) = &response.id {
+                    let failed = response.error.is_some()
+                        || response.result.as_ref().is_some_and(|r| r["isError"] == true);
+                    self.finish_request(id, if failed { BlockStatus::Failed } else { BlockStatus::Done });
+                }
+                self = self.show_response(response);
+            }
+            McpClientEvent::RequestFailed { id, method, error } => {
+                self.finish_request(&id, BlockStatus::Failed);
+                let entry = self.server_entry(EntryKind::Error, format!("❌ {} #{}: {}", method, id, error));
+                self.output = self.output.with_entry(entry.with_request(id));
+                self.status = format!("Error: {}", error);
                 self.scroll_to_bottom();
             }
+            McpClientEvent::RpcResponse { method, elapsed, response } => {
+                if let Some(id) = &response.id {
+                    let status = if response.error.is_some() { BlockStatus::Failed } else { BlockStatus::Done };
+                    self.finish_request(id, status);
+                }
+                let request = response.id.clone();
+                let id = response.id.as_ref().map_or_else(|| "null".to_string(), |id| id.to_string());
+                let header = format!("← {} #{} ({} ms)", method, id, elapsed.as_millis());
+                let (kind, lines) = match response.into_result() {
+                    Ok(result) => {
+                        self.status = format!("{}: ok in {} ms", method, elapsed.as_millis());
+                        let number = self.results.next_number();
+                        let header = format!("{} - result {}", header, number);
+                        let lines = std::iter::once(ResultLine::Heading(header)).chain(format_result(&result, number)).collect();
+                        self.store_result(request.clone(), &method, result);
+                        (EntryKind::Result, lines)
+                    }
+                    Err(error) => {
+                        let lines = error_lines(format!("❌ {}", header), error);
+                        self.status = lines[0].trim_start_matches("❌ ").replacen(&header, &method, 1);
+                        (EntryKind::Error, lines.into_iter().map(ResultLine::Text).collect())
+                    }
+                };
+                self = self.push_response_lines(kind, request, lines);
+            }
+        }
+
+        if settles_connection && self.awaiting_connection {
+            self.awaiting_connection = false;
+            return self.run_startup_commands().await;
         }
         Ok(self)
     }
 
-    async fn handle_key(self, code: KeyCode, mods: KeyModifiers) -> Result<Self> {
+    /// An entry attributed to the server of the current session
+    fn server_entry(&self, kind: EntryKind, text: impl Into<String>) -> OutputEntry {
+        OutputEntry::new(kind, text).with_server(self.session_server.clone())
+    }
+
+    /// Renders a response nobody waited for: a preview of the result (kept
+    /// whole for `:result`), or the error
+    fn show_response(mut self, response: Response) -> Self {
+        let request = response.id.clone();
+        let id = response.id.as_ref().map_or_else(|| "null".to_string(), |id| id.to_string());
+        match response.into_result() {
+            Ok(result) => {
+                let number = self.results.next_number();
+                let lines = format_result(&result, number);
+                self.store_result(request.clone(), "tools/call", result);
+                self.push_response_lines(EntryKind::Result, request, lines)
+            }
+            Err(error) => {
+                let lines = error_lines(format!("❌ [MCP Error] RPC error (id={})", id), error);
+                self.push_response_lines(EntryKind::Error, request, lines.into_iter().map(ResultLine::Text).collect())
+            }
+        }
+    }
+
+    /// Keeps a complete result; it is labelled with its request's block
+    /// title when there is one
+    fn store_result(&mut self, request: Option<RequestId>, fallback: &str, value: serde_json::Value) {
+        let label = request
+            .as_ref()
+            .and_then(|id| self.output.block(id))
+            .map_or_else(|| fallback.to_string(), |block| block.title.clone());
+        self.results = std::mem::take(&mut self.results)
+            .with_result(request, label, self.session_server.clone(), value);
+    }
+
+    fn push_response_lines(mut self, kind: EntryKind, request: Option<RequestId>, lines: Vec<ResultLine>) -> Self {
+        for line in lines {
+            let entry = match line {
+                ResultLine::Heading(text) => self.server_entry(kind, text).as_heading(),
+                ResultLine::Text(text) => self.server_entry(kind, text),
+            };
+            self.output = self.output.with_entry(match &request {
+                Some(id) => entry.with_request(id.clone()),
+                None => entry,
+            });
+        }
+        self.scroll_to_bottom();
+        self
+    }
+
+    /// Writes one event to the `--log-file`; a write failure closes the log
+    fn log_event(&mut self, event: &McpClientEvent) {
+        let Some(file) = &mut self.log_file else {
+            return;
+        };
+        let line = match event {
+            McpClientEvent::Connected => "INFO  connected".to_string(),
+            McpClientEvent::Disconnected => "INFO  disconnected".to_string(),
+            McpClientEvent::Initialized(result) => format!("INFO  initialized: {}", result),
+            McpClientEvent::Message(msg) => format!("INFO  {}", msg),
+            McpClientEvent::Debug(msg) => format!("DEBUG {}", msg),
+            McpClientEvent::Error(err) => format!("ERROR {}", err),
+            McpClientEvent::RequestFailed { id, method, error } => format!("ERROR {} #{}: {}", method, id, error),
+            McpClientEvent::Notification(notification) => format!("INFO  {}", describe_notification(notification)),
+            McpClientEvent::ToolsListed(tools) => format!("INFO  {} tools listed", tools.len()),
+            McpClientEvent::Traffic(record) => format!("{}     {}", record.direction.arrow(), record.body),
+            // Already logged as traffic
+            McpClientEvent::Response(_) | McpClientEvent::RpcResponse { .. } => return,
+        };
+        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+        if writeln!(file, "{} {}", timestamp, line).is_err() {
+            self.log_file = None;
+            self.output = std::mem::take(&mut self.output).with_message("❌ Log file write failed - logging stopped".to_string());
+        }
+    }
+
+    async fn handle_key(mut self, code: KeyCode, mods: KeyModifiers) -> Result<Self> {
+        // A pending y/n confirmation swallows the next key
+        if self.pending_confirmation.is_some() {
+            return Ok(self.handle_confirmation_key(code).await);
+        }
+
         // Tool selection mode has highest priority
         if self.tool_selection.is_some() {
             return self.handle_tool_selection_key(code).await;
@@ -416,6 +852,19 @@ impl App {
             return self.handle_ctrl_key(code).await;
         }
 
+        // The tree, the pager, then the inspector pane, take over navigation keys in NORMAL mode
+        if self.tree.is_some() && self.mode == Mode::Normal {
+            self.handle_tree_key(code);
+            return Ok(self);
+        }
+        if self.pager.is_some() && self.mode == Mode::Normal {
+            self.handle_pager_key(code);
+            return Ok(self);
+        }
+        if self.inspector.is_visible() && self.mode == Mode::Normal {
+            return self.handle_inspector_key(code).await;
+        }
+
         match self.mode {
             Mode::Normal => self.handle_normal_key(code).await,
             Mode::Insert => self.handle_insert_key(code).await,
@@ -459,7 +908,7 @@ impl App {
                 self.status = format!("Calling tool '{}'...", tool.name);
                 
                 // For now, call with empty arguments
-                self.mcp_client.call_tool(tool.name.clone(), serde_json::json!({})).await;
+                self.call_tool(tool.name.clone(), serde_json::json!({}));
             }
             KeyCode::Char(c) if c.is_ascii_digit() => {
                 let idx = c.to_digit(10).unwrap() as usize;
@@ -468,7 +917,7 @@ impl App {
                     self.tool_selection = None;
 
                     self.status = format!("Calling tool '{}'...", tool.name);
-                    self.mcp_client.call_tool(tool.name.clone(), serde_json::json!({})).await;
+                    self.call_tool(tool.name.clone(), serde_json::json!({}));
                 }
             }
             _ => {}
@@ -477,6 +926,63 @@ impl App {
         Ok(self)
     }
 
+    // ═══════════════════════════════════════════════════════════════════
+    // Confirmation of destructive calls (production profiles)
+    // ═══════════════════════════════════════════════════════════════════
+
+    /// Whether a call of `tool` must be confirmed first: the profile is a
+    /// production one and the tool is not annotated as safe (unknown tools
+    /// count as destructive)
+    fn needs_confirmation(&self, tool: &str) -> bool {
+        self.config.is_production()
+            && self.available_tools.iter().find(|t| t.name == tool).is_none_or(ToolInfo::is_destructive)
+    }
+
+    /// Calls a tool, asking first if [`Self::needs_confirmation`]
+    fn call_tool(&mut self, tool: String, args: serde_json::Value) {
+        if self.needs_confirmation(&tool) {
+            self.ask_confirmation(Confirmation::ToolCall { tool, args });
+        } else {
+            let id = self.mcp_client.call_tool(tool.clone(), args.clone());
+            self.track_request(id, tool, &args);
+        }
+    }
+
+    fn ask_confirmation(&mut self, confirmation: Confirmation) {
+        let profile = self.config.active_profile.clone().unwrap_or_default();
+        self.output = std::mem::take(&mut self.output)
+            .with_message(format!(
+                "⚠️  '{}' may modify data and profile '{}' is production - run it? (y/N)",
+                confirmation.tool(),
+                profile
+            ));
+        self.status = format!("Run '{}' on {}? (y/N)", confirmation.tool(), profile);
+        self.pending_confirmation = Some(confirmation);
+        self.scroll_to_bottom();
+    }
+
+    /// `y` sends the held-back request, any other key drops it
+    async fn handle_confirmation_key(mut self, code: KeyCode) -> Self {
+        let Some(confirmation) = self.pending_confirmation.take() else {
+            return self;
+        };
+        if !matches!(code, KeyCode::Char('y' | 'Y')) {
+            self.output = self.output.with_message(format!("🚫 Cancelled '{}'", confirmation.tool()));
+            self.status = "Cancelled".into();
+            self.scroll_to_bottom();
+            return self;
+        }
+        match confirmation {
+            Confirmation::ToolCall { tool, args } => {
+                self.status = format!("Calling tool '{}'...", tool);
+                let id = self.mcp_client.call_tool(tool.clone(), args.clone());
+                self.track_request(id, tool, &args);
+                self
+            }
+            Confirmation::Rpc { method, params } => self.dispatch_rpc(method, params),
+        }
+    }
+
     // ═══════════════════════════════════════════════════════════════════
     // Server selection mode
     // ═══════════════════════════════════════════════════════════════
@@ -509,29 +1015,198 @@ impl App {
             KeyCode::Enter => {
                 let server_name = servers[selected].clone();
                 self.server_selection = None;
-
-                if let Some(server) = self.config.mcp_servers.iter().find(|s| s.name == server_name) {
-                    self.status = format!("Connecting to {}...", server.name);
-                    self.mcp_client.connect(server.url.clone(), server.name.clone()).await;
-                } else {
-                    self.status = format!("Server '{}' not found", server_name);
-                }
+                self.connect_server(&server_name).await;
             }
             KeyCode::Char(c) if c.is_ascii_digit() => {
                 let idx = c.to_digit(10).unwrap() as usize;
                 if idx > 0 && idx <= servers.len() {
                     let server_name = servers[idx - 1].clone();
                     self.server_selection = None;
+                    self.connect_server(&server_name).await;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(self)
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // Result pager
+    // ═══════════════════════════════════════════════════════════════
 
-                    if let Some(server) = self.config.mcp_servers.iter().find(|s| s.name == server_name) {
-                        self.status = format!("Connecting to {}...", server.name);
-                        self.mcp_client.connect(server.url.clone(), server.name.clone()).await;
+    fn handle_pager_key(&mut self, code: KeyCode) {
+        let Some(pager) = self.pager.take() else {
+            return;
+        };
+        self.pager = match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.status = "Pager closed".into();
+                None
+            }
+            KeyCode::Up | KeyCode::Char('k') => Some(pager.scroll(-1)),
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Enter => Some(pager.scroll(1)),
+            KeyCode::PageUp | KeyCode::Char('b') => Some(pager.page(-1)),
+            KeyCode::PageDown | KeyCode::Char(' ') => Some(pager.page(1)),
+            KeyCode::Home | KeyCode::Char('g') => Some(pager.top()),
+            KeyCode::End | KeyCode::Char('G') => Some(pager.bottom()),
+            KeyCode::Left | KeyCode::Char('h') => Some(pager.shift(-8)),
+            KeyCode::Right | KeyCode::Char('l') => Some(pager.shift(8)),
+            KeyCode::Char('t') => match pager.result() {
+                Some(number) => {
+                    self.open_tree(Some(number));
+                    None
+                }
+                None => Some(pager),
+            },
+            _ => Some(pager),
+        };
+    }
+
+    /// `:result [n]`: opens a stored result (the last one by default)
+    fn open_result(&mut self, number: Option<usize>) {
+        match self.results.find(number) {
+            Some(result) => {
+                let lines = result.lines();
+                self.status = format!("Result {}: {} lines", result.number, lines.len());
+                self.pager = Some(Pager::new(result.title(), lines).with_result(result.number));
+                self.tree = None;
+            }
+            None => self.status = self.missing_result(number),
+        }
+    }
+
+    fn missing_result(&self, number: Option<usize>) -> String {
+        match number {
+            Some(n) => format!("Error: no result {} (results 1-{} so far)", n, self.results.next_number() - 1),
+            None => "No results yet".into(),
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // JSON tree
+    // ═══════════════════════════════════════════════════════════════
+
+    fn handle_tree_key(&mut self, code: KeyCode) {
+        let Some(tree) = self.tree.take() else {
+            return;
+        };
+        self.tree = match code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.status = "Tree closed".into();
+                None
+            }
+            KeyCode::Up | KeyCode::Char('k') => Some(tree.move_by(-1)),
+            KeyCode::Down | KeyCode::Char('j') => Some(tree.move_by(1)),
+            KeyCode::PageUp | KeyCode::Char('b') => Some(tree.page(-1)),
+            KeyCode::PageDown => Some(tree.page(1)),
+            KeyCode::Home | KeyCode::Char('g') => Some(tree.top()),
+            KeyCode::End | KeyCode::Char('G') => Some(tree.bottom()),
+            KeyCode::Left | KeyCode::Char('h') => Some(tree.collapse()),
+            KeyCode::Right | KeyCode::Char('l') => Some(tree.expand()),
+            KeyCode::Enter | KeyCode::Char(' ') => Some(tree.toggle()),
+            KeyCode::Char('E') => Some(tree.expand_all()),
+            KeyCode::Char('C') => Some(tree.collapse_all()),
+            KeyCode::Char('y') => {
+                let value = tree.current_value().map(|v| serde_json::to_string_pretty(v).unwrap_or_default());
+                if let Some(value) = value {
+                    self.status = format!("Yanked value at {} ({} bytes)", tree.current_path(), value.len());
+                    self.clipboard = Some(value);
+                }
+                Some(tree)
+            }
+            KeyCode::Char('Y') => {
+                let path = tree.current_path();
+                self.status = format!("Yanked path {}", path);
+                self.clipboard = Some(path);
+                Some(tree)
+            }
+            _ => Some(tree),
+        };
+    }
+
+    /// `:tree [n]`: opens a stored result (the last one by default) as a tree
+    fn open_tree(&mut self, number: Option<usize>) {
+        match self.results.find(number) {
+            Some(result) => {
+                let tree = JsonTree::new(result.title(), result.json());
+                let summary = tree.current().map_or_else(String::new, |row| row.summary.clone());
+                self.status = format!("Result {} as a tree: {}", result.number, summary);
+                self.tree = Some(tree);
+                self.pager = None;
+            }
+            None => self.status = self.missing_result(number),
+        }
+    }
+
+    /// `:query [n] <expr>`: prints what a jq/JSONPath expression selects
+    /// from a stored result (the last one by default)
+    fn run_query(&mut self, number: Option<usize>, expr: &str) {
+        let Some(result) = self.results.find(number) else {
+            self.status = self.missing_result(number);
+            return;
+        };
+        let source = result.number;
+        let outcome = Query::parse(expr).and_then(|query| query.run(&result.json()));
+
+        let mut output = std::mem::take(&mut self.output);
+        match outcome {
+            Ok(matches) => {
+                let noun = if matches.len() == 1 { "match" } else { "matches" };
+                output = output.with_entry(OutputEntry::new(
+                    EntryKind::Result,
+                    format!("🔎 Query on result {}: {} {}", source, matches.len(), noun),
+                ));
+                for value in &matches {
+                    let text = serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string());
+                    for line in text.lines() {
+                        output = output.with_entry(OutputEntry::new(EntryKind::Result, line));
                     }
                 }
+                self.status = format!("Query on result {}: {} {}", source, matches.len(), noun);
+            }
+            Err(e) => {
+                output = output.with_entry(OutputEntry::new(EntryKind::Error, format!("❌ Query error: {}", e)));
+                self.status = format!("Error: {}", e);
             }
-            _ => {}
         }
+        self.output = output;
+        self.scroll_to_bottom();
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // Inspector pane
+    // ═══════════════════════════════════════════════════════════════
 
+    async fn handle_inspector_key(mut self, code: KeyCode) -> Result<Self> {
+        match code {
+            KeyCode::Esc => {
+                self.inspector = self.inspector.hide();
+                self.status = "Inspector closed".into();
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.inspector = self.inspector.select_prev();
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.inspector = self.inspector.select_next();
+            }
+            KeyCode::Home | KeyCode::Char('g') => {
+                self.inspector = self.inspector.select_first();
+            }
+            KeyCode::End | KeyCode::Char('G') => {
+                self.inspector = self.inspector.select_last();
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                self.inspector = self.inspector.toggle_expanded();
+            }
+            KeyCode::PageUp if self.inspector.is_expanded() => {
+                self.inspector = self.inspector.scroll_detail_up(10);
+            }
+            KeyCode::PageDown if self.inspector.is_expanded() => {
+                self.inspector = self.inspector.scroll_detail_down(10);
+            }
+            _ => return self.handle_normal_key(code).await,
+        }
         Ok(self)
     }
 
@@ -540,7 +1215,23 @@ impl App {
     // ═══════════════════════════════════════════════════════════════
 
     async fn handle_normal_key(mut self, code: KeyCode) -> Result<Self> {
+        if let Some(first) = self.pending_key.take() {
+            match (first, code) {
+                ('z', KeyCode::Char('a')) => self.fold_current(None),
+                ('z', KeyCode::Char('c')) => self.fold_current(Some(true)),
+                ('z', KeyCode::Char('o')) => self.fold_current(Some(false)),
+                ('[', KeyCode::Char('[')) => self.jump_block(false),
+                (']', KeyCode::Char(']')) => self.jump_block(true),
+                _ => self.status = "Ready".into(),
+            }
+            return Ok(self);
+        }
+
         match code {
+            KeyCode::Char(first @ ('z' | '[' | ']')) => {
+                self.pending_key = Some(first);
+                self.status = first.to_string();
+            }
             KeyCode::Char('i') => {
                 self.mode = Mode::Insert;
                 self.status = "Entered INSERT mode".into();
@@ -562,13 +1253,15 @@ impl App {
                 self.status = "Scrolled down".into();
             }
             KeyCode::PageUp => {
+                self.sync_scroll();
                 self.disable_autoscroll();
-                self.scroll_offset = self.scroll_offset.saturating_sub(self.view_height() as usize);
+                self.scroll_offset = self.scroll_offset.saturating_sub(self.view_height());
             }
             KeyCode::PageDown => {
+                self.sync_scroll();
                 self.disable_autoscroll();
                 let max = self.max_scroll_offset();
-                self.scroll_offset = (self.scroll_offset + self.view_height() as usize).min(max);
+                self.scroll_offset = (self.scroll_offset + self.view_height()).min(max);
             }
             KeyCode::End => {
                 self.jump_to_bottom();
@@ -578,6 +1271,50 @@ impl App {
         Ok(self)
     }
 
+    // ═══════════════════════════════════════════════════════════════
+    // Request blocks: folding and jumping
+    // ═══════════════════════════════════════════════════════════════
+
+    /// `za`/`zc`/`zo`: toggles, folds or unfolds the current block
+    fn fold_current(&mut self, folded: Option<bool>) {
+        let Some(id) = self.current_block.clone().filter(|id| self.output.block(id).is_some()) else {
+            self.status = "No request block".into();
+            return;
+        };
+        self.output = std::mem::take(&mut self.output).with_folded(&id, folded);
+        let folded = self.output.block(&id).is_some_and(|block| block.folded);
+        self.status = format!("Block #{} {}", id, if folded { "folded" } else { "unfolded" });
+        if self.autoscroll {
+            self.scroll_to_bottom();
+        } else {
+            self.clamp_scroll_offset();
+        }
+    }
+
+    /// `]]`/`[[`: makes the next or previous block current and scrolls its
+    /// header to the top of the output area
+    fn jump_block(&mut self, forward: bool) {
+        self.sync_scroll();
+        let headers: Vec<(RequestId, usize)> =
+            self.rows.headers().iter().map(|(id, rows)| (id.clone(), rows.start)).collect();
+
+        let current = self.current_block.as_ref().and_then(|id| headers.iter().position(|(r, _)| r == id));
+        let target = match (current, forward) {
+            (Some(i), true) => i + 1,
+            (Some(i), false) => i.wrapping_sub(1),
+            (None, true) => 0,
+            (None, false) => headers.len().wrapping_sub(1),
+        };
+        let Some((id, offset)) = headers.get(target).cloned() else {
+            self.status = if forward { "No next block" } else { "No previous block" }.into();
+            return;
+        };
+        self.status = format!("Block #{}", id);
+        self.current_block = Some(id);
+        self.disable_autoscroll();
+        self.set_scroll_offset(offset);
+    }
+
     // ═══════════════════════════════════════════════════════════════
     // Mode: INSERT
     // ═══════════════════════════════════════════════════════════════
@@ -590,14 +1327,20 @@ impl App {
             }
             KeyCode::Enter => {
                 let input = self.input_buffer.content().to_string();
-                if !input.is_empty() {
-                    self.output = self
-                        .output
-                        .with_message(format!("→ {}", input))
-                        .with_message(format!("← Echo: {}", input));
-                    self.scroll_to_bottom();
+                if !input.trim().is_empty() {
                     self.input_buffer = Buffer::new();
-                    self.status = format!("Sent: {}", input);
+                    self.record(RecordEntry::command(format!("rpc {}", input.trim())));
+                    match Command::parse_rpc(&input) {
+                        Ok(Command::Rpc { method, params }) => {
+                            return Ok(self.send_rpc(method, params));
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            self.output = self.output.with_message(format!("❌ {}", e));
+                            self.status = format!("Error: {}", e);
+                            self.scroll_to_bottom();
+                        }
+                    }
                 }
             }
             KeyCode::Char(c) => {
@@ -651,21 +1394,30 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
                 return Ok(self);
             }
 
-            // Execute command
+            // Execute command (back in NORMAL mode first, so a command
+            // can reopen the command line, e.g. :mcp edit <name>)
             let cmd_text = self.command_state.content.clone();
+            if !cmd_text.trim_start().starts_with("record") {
+                self.record(RecordEntry::command(cmd_text.trim()));
+            }
+            self.mode = Mode::Normal;
+            self.command_state = CommandBufferState::new();
+            if !cmd_text.trim().is_empty() {
+                self.output = self.output.with_entry(OutputEntry::new(EntryKind::Command, format!(":{}", cmd_text)));
+            }
             let mut app = self.execute_command(&cmd_text).await?;
             
             // Add to history
             app.completion_context = app.completion_context
                 .with_history_entry(cmd_text);
-            
-            app.mode = Mode::Normal;
-            app.command_state = CommandBufferState::new();
             Ok(app)
         }
 
-        // Up/Down navigation
-        KeyCode::Up | KeyCode::Char('k') => {
+        // Up/Down navigation (j/k only inside the completion popup so they
+        // can still be typed)
+        KeyCode::Up | KeyCode::Char('k')
+            if code == KeyCode::Up || self.command_state.completion.is_some() =>
+        {
             if self.command_state.completion.is_some() {
                 // Navigate completion popup
                 if let Some(comp) = self.command_state.completion.take() {
@@ -686,7 +1438,9 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
             Ok(self)
         }
 
-        KeyCode::Down | KeyCode::Char('j') => {
+        KeyCode::Down | KeyCode::Char('j')
+            if code == KeyCode::Down || self.command_state.completion.is_some() =>
+        {
             if self.command_state.completion.is_some() {
                 // Navigate completion popup
                 if let Some(comp) = self.command_state.completion.take() {
@@ -746,6 +1500,94 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
     }
     }
 
+    /// Sends a raw JSON-RPC request; the response arrives as RpcResponse
+    fn send_rpc(mut self, method: String, params: Option<String>) -> Self {
+        let params = match params.as_deref().map(serde_json::from_str::<serde_json::Value>) {
+            None => None,
+            Some(Ok(params)) => Some(params),
+            Some(Err(e)) => {
+                self.output = self.output.with_message(format!("❌ Invalid params JSON: {}", e));
+                self.status = "Invalid params JSON".into();
+                self.scroll_to_bottom();
+                return self;
+            }
+        };
+
+        let tool = params.as_ref().and_then(|p| p["name"].as_str()).unwrap_or_default();
+        if method == "tools/call" && self.needs_confirmation(tool) {
+            self.ask_confirmation(Confirmation::Rpc { method, params });
+            return self;
+        }
+        self.dispatch_rpc(method, params)
+    }
+
+    fn dispatch_rpc(mut self, method: String, params: Option<serde_json::Value>) -> Self {
+        let shown = params.as_ref().map_or_else(String::new, |p| format!(" {}", p));
+        let (label, args) = match (method.as_str(), params.as_ref().and_then(|p| p["name"].as_str())) {
+            ("tools/call", Some(tool)) => (tool.to_string(), params.as_ref().map_or(serde_json::Value::Null, |p| p["arguments"].clone())),
+            _ => (method.clone(), params.clone().unwrap_or_default()),
+        };
+        let id = self.mcp_client.send_request(method.clone(), params);
+        self.output = self.output.with_message(format!("→ {} #{}{}", method, id, shown));
+        self.status = format!("Sent {} (id={})", method, id);
+        self.track_request(id, label, &args);
+        self.scroll_to_bottom();
+        self
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // In-flight requests
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Tracks a sent request and opens the output block its results go to
+    fn track_request(&mut self, id: RequestId, label: String, args: &serde_json::Value) {
+        let title = match args {
+            serde_json::Value::Null => label.clone(),
+            serde_json::Value::Object(map) if map.is_empty() => label.clone(),
+            args => format!("{} {}", label, args),
+        };
+        self.output = std::mem::take(&mut self.output).with_block(id.clone(), title);
+        self.current_block = Some(id.clone());
+        self.in_flight.push(InFlight {
+            id,
+            label,
+            server: self.session_server.clone(),
+            started: Instant::now(),
+        });
+    }
+
+    /// Forgets a request once its response (or failure) arrived and settles
+    /// its block; ids the app did not send itself (initialize, auto-loaded
+    /// tools) are ignored
+    fn finish_request(&mut self, id: &RequestId, status: BlockStatus) {
+        self.in_flight.retain(|request| request.id != *id);
+        self.output = std::mem::take(&mut self.output).finish_block(id, status);
+    }
+
+    /// Resolves requests past their server's timeout as errors and tells
+    /// the server to stop working on them
+    async fn expire_requests(&mut self) {
+        let (expired, waiting): (Vec<InFlight>, Vec<InFlight>) = std::mem::take(&mut self.in_flight)
+            .into_iter()
+            .partition(|request| request.started.elapsed() >= self.config.request_timeout(request.server.as_deref()));
+        self.in_flight = waiting;
+
+        for request in expired {
+            let timeout = self.config.request_timeout(request.server.as_deref());
+            let reason = format!("timed out after {} s", timeout.as_secs());
+            let entry = self.server_entry(
+                EntryKind::Error,
+                format!("⏱️  {} #{} {} - cancelled", request.label, request.id, reason),
+            );
+            self.output = std::mem::take(&mut self.output)
+                .with_entry(entry.with_request(request.id.clone()))
+                .finish_block(&request.id, BlockStatus::TimedOut);
+            self.status = format!("Error: {} {}", request.label, reason);
+            self.mcp_client.cancel(request.id, reason).await;
+            self.scroll_to_bottom();
+        }
+    }
+
     // ═══════════════════════════════════════════════════════════════
     // Control key handlers
     // ═══════════════════════════════════════════════════════════════
@@ -760,7 +1602,7 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
                 self.status = "Input cleared".into();
             }
             KeyCode::Char('l') => {
-                self.output = OutputLog::new();
+                self.output = std::mem::take(&mut self.output).clear();
                 self.scroll_to_bottom();
                 self.status = "Output cleared".into();
             }
@@ -769,6 +1611,209 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
         Ok(self)
             }
 
+    // ═══════════════════════════════════════════════════════════════
+    // Server list changes
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Validates the new server list and saves it to the config file; the
+    /// running config only changes if both succeed
+    fn update_servers(mut self, servers: Vec<McpServerConfig>, done: String) -> Self {
+        let config = match self.config.with_servers(servers) {
+            Ok(config) => config,
+            Err(errors) => {
+                for error in &errors {
+                    self.output = self.output.with_message(format!("❌ {}", error));
+                }
+                self.status = "Error: server not changed".into();
+                self.scroll_to_bottom();
+                return self;
+            }
+        };
+
+        let saved = match &config.path {
+            Some(path) => match config.save(path) {
+                Ok(()) => format!(" (saved to {})", path.display()),
+                Err(e) => {
+                    self.output = self.output.with_message(format!("❌ {}", e));
+                    self.status = "Error: config not saved".into();
+                    self.scroll_to_bottom();
+                    return self;
+                }
+            },
+            None => " (not saved: no config file)".to_string(),
+        };
+
+        self.config = config;
+        self.refresh_servers();
+        if let Err(e) = self.start_config_watch() {
+            self.output = self.output.with_message(format!("⚠️  config: not watching for changes: {}", e));
+        }
+        self.output = self.output.with_message(format!("{}{}", done, saved));
+        self.status = done.trim_start_matches(|c: char| !c.is_alphanumeric()).to_string();
+        self.scroll_to_bottom();
+        self
+    }
+
+    /// Warns that a saved stdio server cannot be connected from here; the
+    /// entry is only kept for other clients reading the same config
+    fn stdio_note(mut self, name: &str) -> Self {
+        if self.config.mcp_servers.iter().any(|s| s.name == name && s.url.is_empty()) {
+            self.output = self.output.with_message(format!(
+                "⚠️  '{}' is a stdio server - it is saved for other clients, but :mcp connect only supports HTTP/SSE",
+                name
+            ));
+            self.scroll_to_bottom();
+        }
+        self
+    }
+
+    /// Connects to a configured server by name
+    async fn connect_server(&mut self, name: &str) {
+        match self.config.server(name) {
+            Some(server) => {
+                self.status = format!("Connecting to {}...", server.name);
+                self.session_server = Some(server.name.clone());
+                self.in_flight.clear();
+                self.output = std::mem::take(&mut self.output).cancel_running();
+                self.mcp_client.connect(&server).await;
+            }
+            None => self.status = format!("Server '{}' not found in config", name),
+        }
+    }
+
+    /// Lists the servers and opens the picker with `selected` highlighted
+    fn show_server_picker(mut self, selected: usize) -> Self {
+        let servers = self.config.server_names();
+        self.output = self.output.with_message("🔌 Select MCP server:".to_string());
+        for (i, server) in self.config.servers().iter().enumerate() {
+            let prefix = if i == selected { "→" } else { " " };
+            self.output = self.output.with_message(
+                format!("  {} [{}] {}: {}", prefix, i + 1, server.name, server.endpoint())
+            );
+        }
+        self.output = self.output
+            .with_message("".to_string())
+            .with_message("Use ↑↓ or j/k to navigate, Enter to connect, Esc to cancel".to_string());
+
+        self.server_selection = Some(ServerSelection { servers, selected });
+        self.status = "Select server with ↑↓ or number keys".into();
+        self
+    }
+
+    /// Re-reads the config file after a change on disk. An invalid file is
+    /// reported and the running config kept; otherwise the server diff is
+    /// applied and changed sessions are offered a reconnect.
+    fn reload_config(mut self) -> Self {
+        let Some(path) = self.config.path.clone() else {
+            return self;
+        };
+        let config = match Config::from_file(&path) {
+            Ok(config) => config,
+            // Removed (or mid-rename): keep going with what we have
+            Err(crate::config::ConfigError::Read { .. }) if !path.exists() => return self,
+            Err(e) => {
+                self.output = self.output.with_message("❌ Config reload failed - keeping the running config:".to_string());
+                for line in e.to_string().lines() {
+                    self.output = self.output.with_message(format!("  {}", line));
+                }
+                self.status = "Config reload failed".into();
+                self.scroll_to_bottom();
+                return self;
+            }
+        };
+
+        let config = match config.with_profile(self.config.active_profile.as_deref()) {
+            Ok(config) => config,
+            Err(e) => {
+                self.output = self
+                    .output
+                    .with_message("❌ Config reload failed - keeping the running config:".to_string())
+                    .with_message(format!("  {}", e));
+                self.status = "Config reload failed".into();
+                self.scroll_to_bottom();
+                return self;
+            }
+        };
+
+        for warning in config.warnings.iter().filter(|w| !self.config.warnings.contains(w)) {
+            self.output = self.output.with_message(format!("⚠️  config: {}", warning));
+        }
+        let diff = ServerDiff::between(&self.config, &config);
+        // An empty diff is our own save or an edit that did not touch the
+        // servers; other settings still apply
+        if !diff.is_empty() {
+            self.output = self.output.with_message(format!("🔄 Config reloaded: {}", diff.summary()));
+            self.status = format!("Config reloaded: {}", diff.summary());
+        }
+        self.switch_config(config, &diff)
+    }
+
+    /// Switches to the active profile given by name
+    fn switch_profile(mut self, name: &str) -> Self {
+        let config = match self.config.clone().with_profile(Some(name)) {
+            Ok(config) => config,
+            Err(e) => {
+                self.output = self.output.with_message(format!("❌ {}", e));
+                self.status = format!("Error: {}", e);
+                self.scroll_to_bottom();
+                return self;
+            }
+        };
+        let diff = ServerDiff::between(&self.config, &config);
+        let production = if config.is_production() { " (production)" } else { "" };
+        let changes = if diff.is_empty() { String::new() } else { format!(": {}", diff.summary()) };
+        self.output = self.output.with_message(format!("🌐 Profile '{}'{}{}", name, production, changes));
+        self.status = format!("Profile: {}", name);
+        self.switch_config(config, &diff)
+    }
+
+    /// Replaces the running config and tells the current session what the
+    /// server diff means for it
+    fn switch_config(mut self, config: Config, diff: &ServerDiff) -> Self {
+        self.output = self.output.limited(config.output_lines.unwrap_or(MAX_LOG_LINES));
+        if (config.highlight, config.highlight_theme) != (self.config.highlight, self.config.highlight_theme) {
+            (self.theme, self.output_view.highlight) = highlight_setting(&config);
+        }
+        self.config = config;
+        if let Some(session) = &self.session_server {
+            if diff.changed.contains(session) {
+                self.output = self.output.with_message(format!(
+                    "   '{}' changed - :mcp reconnect to apply the new settings",
+                    session
+                ));
+            } else if diff.removed.contains(session) {
+                self.output = self.output.with_message(format!(
+                    "   '{}' was removed - the current session stays open",
+                    session
+                ));
+            }
+        }
+        self.refresh_servers();
+        if let Some(selected) = self.server_selection.as_ref().map(|s| s.selected) {
+            self = self.show_server_picker(selected);
+        }
+        self.scroll_to_bottom();
+        self
+    }
+
+    /// Pushes the current server list into completion and an open picker
+    fn refresh_servers(&mut self) {
+        let names = self.config.server_names();
+        self.completion_context = std::mem::take(&mut self.completion_context)
+            .with_list("mcp_servers".to_string(), names.clone())
+            .with_list("profiles".to_string(), self.config.profiles.keys().cloned().collect());
+        if let Some(selection) = &mut self.server_selection {
+            let current = selection.servers.get(selection.selected).cloned();
+            selection.selected = current
+                .and_then(|name| names.iter().position(|n| *n == name))
+                .unwrap_or(0);
+            selection.servers = names;
+            if selection.servers.is_empty() {
+                self.server_selection = None;
+            }
+        }
+    }
+
     // ═══════════════════════════════════════════════════════════════
     // Command execution
     // ═══════════════════════════════════════════════════════════════
@@ -780,7 +1825,7 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
                 self.status = "Quitting...".into();
             }
             Ok(Command::Clear) => {
-                self.output = OutputLog::new();
+                self.output = std::mem::take(&mut self.output).clear();
                 self.scroll_to_bottom();
                 self.status = "Output cleared".into();
             }
@@ -795,14 +1840,36 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
                     .with_message("  :clear                   - Clear output".to_string())
                     .with_message("  :echo <text>             - Echo text to output".to_string())
                     .with_message("  :mouse on/off            - Enable/disable mouse capture".to_string())
+                    .with_message("  :profile [name]          - Show profiles or switch to one".to_string())
                     .with_message("".to_string())
                     .with_message("  MCP Commands:".to_string())
                     .with_message("  :mcp list                - List configured MCP servers".to_string())
                     .with_message("  :mcp cn, :mcp connect    - Connect to MCP server (interactive)".to_string())
+                    .with_message("  :mcp reconnect           - Reconnect to the current server (e.g. after a config change)".to_string())
                     .with_message("  :mcp status              - Show connection and tools status".to_string())
+                    .with_message("  :mcp pending             - List requests still waiting for a response".to_string())
                     .with_message("  :mcp tools               - List tools (compact view)".to_string())
                     .with_message("  :mcp tool <name>         - Show detailed tool description".to_string())
                     .with_message("  :mcp run [tool_name]     - Run MCP tool (interactive or direct)".to_string())
+                    .with_message("  :mcp logout              - Forget cached OAuth tokens for the server".to_string())
+                    .with_message("  :mcp add <name> <url|cmd> - Add a server (URL, or stdio command + args saved for other clients)".to_string())
+                    .with_message("  :mcp edit <name> [...]   - Change a server's endpoint (no args: prefill it)".to_string())
+                    .with_message("  :mcp remove <name>       - Remove a server and save".to_string())
+                    .with_message("".to_string())
+                    .with_message("  :rpc <method> [json]     - Send a raw JSON-RPC request (also: type it in INSERT mode)".to_string())
+                    .with_message("  :record start <file>     - Record JSON-RPC traffic and commands to JSONL".to_string())
+                    .with_message("  :record stop             - Stop recording".to_string())
+                    .with_message("  :replay <file>           - Connect to a fake server replaying a recording".to_string())
+                    .with_message("  :inspector               - Toggle JSON-RPC traffic inspector".to_string())
+                    .with_message("  :inspector filter [text] - Filter traffic by method or id".to_string())
+                    .with_message("  :inspector clear         - Clear recorded traffic".to_string())
+                    .with_message("  :output filter [kinds]   - Show only some kinds (command,result,error,debug,notification,system)".to_string())
+                    .with_message("  :output group            - Toggle grouping output by kind".to_string())
+                    .with_message("  :output export <file>    - Save output with kinds, timestamps and servers as JSONL".to_string())
+                    .with_message("  :result [n]              - Open result n (default: the last) in a full-screen pager".to_string())
+                    .with_message("  :tree [n]                - Browse result n as a foldable JSON tree (y/Y yank value/path)".to_string())
+                    .with_message("  :query [n] <expr>        - Print what a jq-style or JSONPath expression selects from result n".to_string())
+                    .with_message("  :highlight [on|off|dark|light] - Toggle syntax highlighting of results or pick its theme".to_string())
                     .with_message("".to_string())
                     .with_message("  :h, :help                - Show this help".to_string());
                 self.scroll_to_bottom();
@@ -811,48 +1878,36 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
             Ok(Command::McpConnect(server_name)) => {
                 if let Some(name) = server_name {
                     // Direct connection by name
-                    if let Some(server) = self.config.mcp_servers.iter().find(|s| s.name == name) {
-                        self.status = format!("Connecting to {}...", server.name);
-                        self.mcp_client.connect(server.url.clone(), server.name.clone()).await;
-                    } else {
-                        self.status = format!("Server '{}' not found in config.json", name);
-                    }
+                    self.connect_server(&name).await;
                 } else {
                     // Interactive selection
-                    if self.config.mcp_servers.is_empty() {
-                        self.output = self.output.with_message("No MCP servers configured in config.json".to_string());
+                    if self.config.servers().is_empty() {
+                        self.output = self.output.with_message(
+                            "No MCP servers configured - add one with :mcp add <name> <url|command args...>".to_string()
+                        );
                     } else {
-                        let servers: Vec<String> = self.config.mcp_servers.iter().map(|s| s.name.clone()).collect();
-                        
-                        self.output = self.output.with_message("🔌 Select MCP server:".to_string());
-                    for (i, server) in self.config.mcp_servers.iter().enumerate() {
-                            let prefix = if i == 0 { "→" } else { " " };
-                            self.output = self.output.with_message(
-                                format!("  {} [{}] {}: {}", prefix, i + 1, server.name, server.url)
-                            );
-                    }
-                        self.output = self.output
-                            .with_message("".to_string())
-                            .with_message("Use ↑↓ or j/k to navigate, Enter to connect, Esc to cancel".to_string());
-
-                        self.server_selection = Some(ServerSelection {
-                            servers,
-                            selected: 0,
-                        });
-                        self.status = "Select server with ↑↓ or number keys".into();
+                        self = self.show_server_picker(0);
                     }
                     self.scroll_to_bottom();
                 }
             }
+            Ok(Command::McpReconnect) => match self.session_server.clone() {
+                Some(name) => self.connect_server(&name).await,
+                None => self.status = "Not connected to any server".into(),
+            },
             Ok(Command::McpList) => {
-                self.output = self.output.with_message("📋 Configured MCP servers:".to_string());
-                if self.config.mcp_servers.is_empty() {
+                let servers = self.config.servers();
+                self.output = self.output.with_message(match &self.config.active_profile {
+                    Some(profile) => format!("📋 Configured MCP servers (profile {}):", profile),
+                    None => "📋 Configured MCP servers:".to_string(),
+                });
+                if servers.is_empty() {
                     self.output = self.output.with_message("  (none)".to_string());
                 } else {
-                for server in &self.config.mcp_servers {
+                for server in &servers {
                     self.output = self
                         .output
-                            .with_message(format!("  • {}: {}", server.name, server.url));
+                            .with_message(format!("  • {}: {}", server.name, server.endpoint()));
                     }
                 }
                 self.scroll_to_bottom();
@@ -914,6 +1969,27 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
             self.scroll_to_bottom();
         }
 
+        Ok(Command::McpPending) => {
+            if self.in_flight.is_empty() {
+                self.output = self.output.with_message("No requests in flight".to_string());
+            } else {
+                self.output = self.output.with_message(format!("⏳ {} request(s) in flight:", self.in_flight.len()));
+                for request in &self.in_flight {
+                    let timeout = self.config.request_timeout(request.server.as_deref());
+                    self.output = self.output.with_message(format!(
+                        "  {:<5} {:<24} {:<16} {:>6.1} s (timeout {} s)",
+                        format!("#{}", request.id),
+                        request.label,
+                        request.server.as_deref().unwrap_or("-"),
+                        request.started.elapsed().as_secs_f64(),
+                        timeout.as_secs(),
+                    ));
+                }
+            }
+            self.status = format!("{} request(s) in flight", self.in_flight.len());
+            self.scroll_to_bottom();
+        }
+
         Ok(Command::McpStatus) => {
             self.output = self.output.with_message("📊 MCP Client Status:".to_string());
             self.output = self.output.with_message(
@@ -939,10 +2015,78 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
                     );
                 }
             }
+            match self.mcp_client.tls_details().await {
+                Some(details) => {
+                    self.output = self.output.with_message("  • TLS:".to_string());
+                    for line in details.summary_lines() {
+                        self.output = self.output.with_message(format!("    - {}", line));
+                    }
+                }
+                None => {
+                    self.output = self.output.with_message(
+                        "  • TLS: not in use (plain HTTP or not connected)".to_string()
+                    );
+                }
+            }
             self.scroll_to_bottom();
             self.status = "Status displayed".into();
         }
 
+        Ok(Command::McpLogout) => {
+            if self.mcp_client.logout() {
+                self.output = self.output.with_message(
+                    "🔐 Cached OAuth tokens removed - next connect will re-authorize".to_string()
+                );
+                self.status = "Logged out".into();
+            } else {
+                self.status = "Not connected to any server".into();
+            }
+            self.scroll_to_bottom();
+        }
+
+        Ok(Command::McpAdd(name, endpoint)) => {
+            let mut servers = self.config.mcp_servers.clone();
+            servers.push(McpServerConfig::from_endpoint(&name, &endpoint));
+            self = self.update_servers(servers, format!("➕ Added server '{}'", name)).stdio_note(&name);
+        }
+
+        Ok(Command::McpRemove(name)) => {
+            if self.config.mcp_servers.iter().any(|s| s.name == name) {
+                let mut servers = self.config.mcp_servers.clone();
+                servers.retain(|s| s.name != name);
+                self = self.update_servers(servers, format!("➖ Removed server '{}'", name));
+            } else {
+                self.status = format!("Server '{}' not found in config", name);
+            }
+        }
+
+        // Edits apply to the servers as written in the file, not the profile view
+        Ok(Command::McpEdit(name, endpoint)) => match self.config.mcp_servers.iter().find(|s| s.name == name) {
+            None => self.status = format!("Server '{}' not found in config", name),
+            Some(server) if endpoint.is_empty() => {
+                // Reopen the command line with the current endpoint to edit
+                let current = match &server.command {
+                    Some(command) if server.url.is_empty() => {
+                        std::iter::once(command).chain(&server.args).cloned().collect::<Vec<_>>().join(" ")
+                    }
+                    _ => server.url.clone(),
+                };
+                self.mode = Mode::Command;
+                self.command_state = CommandBufferState::new()
+                    .set_text(format!("mcp edit {} {}", name, current));
+                self.status = format!("Editing '{}' - Enter to save, Esc to cancel", name);
+            }
+            Some(_) => {
+                let servers = self
+                    .config
+                    .mcp_servers
+                    .iter()
+                    .map(|s| if s.name == name { s.clone().with_endpoint(&endpoint) } else { s.clone() })
+                    .collect();
+                self = self.update_servers(servers, format!("✏️  Updated server '{}'", name)).stdio_note(&name);
+            }
+        },
+
         Ok(Command::McpRun(tool_name, args)) => {
             if self.available_tools.is_empty() {
                 self.output = self.output.with_message(
@@ -958,7 +2102,7 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
                             self.output = self.output.with_message(
                                 format!("🔧 Calling '{}' with: {}", tool.name, json_args)
                             );
-                            self.mcp_client.call_tool(tool.name.clone(), json_args).await;
+                            self.call_tool(tool.name.clone(), json_args);
                         }
                         Err(e) => {
                             self.output = self.output.with_message(
@@ -967,7 +2111,7 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
                             self.output = self.output.with_message("".to_string());
                             
                             // Show usage hint
-                            let usage = crate::args::usage_hint(&tool.name, &tool.input_schema);
+                            let usage = usage_hint(&tool.name, &tool.input_schema);
                             self.output = self.output.with_message(
                                 format!("Usage: {}", usage)
                             );
@@ -1001,6 +2145,200 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
             self.scroll_to_bottom();
         }
 
+        Ok(Command::Inspector(action)) => {
+            match action {
+                InspectorAction::Toggle => {
+                    self.inspector = self.inspector.toggle_visible();
+                    self.status = if self.inspector.is_visible() {
+                        format!("Inspector: {} messages", self.inspector.len())
+                    } else {
+                        "Inspector closed".into()
+                    };
+                }
+                InspectorAction::Filter(filter) => {
+                    self.inspector = self.inspector.with_filter(filter);
+                    if !self.inspector.is_visible() {
+                        self.inspector = self.inspector.toggle_visible();
+                    }
+                    self.status = match self.inspector.filter() {
+                        Some(f) => format!("Inspector filter '{}': {} matches", f, self.inspector.filtered().len()),
+                        None => "Inspector filter cleared".into(),
+                    };
+                }
+                InspectorAction::Clear => {
+                    self.inspector = self.inspector.clear();
+                    self.status = "Inspector traffic cleared".into();
+                }
+            }
+        }
+
+        Ok(Command::Output(action)) => {
+            match action {
+                OutputAction::Filter(kinds) => {
+                    self.status = if kinds.is_empty() {
+                        "Output filter cleared".into()
+                    } else {
+                        let names: Vec<&str> = kinds.iter().map(|k| k.name()).collect();
+                        format!("Output: showing {}", names.join(", "))
+                    };
+                    self.output_view.kinds = kinds;
+                }
+                OutputAction::Group => {
+                    self.output_view.grouped = !self.output_view.grouped;
+                    self.status = if self.output_view.grouped {
+                        "Output grouped by kind".into()
+                    } else {
+                        "Output in arrival order".into()
+                    };
+                }
+                OutputAction::Export(path) => match std::fs::write(&path, self.output.to_jsonl()) {
+                    Ok(()) => {
+                        let count = self.output.entries().len();
+                        self.output = self.output.with_message(format!(
+                            "💾 Exported {} output entries to {}",
+                            count, path
+                        ));
+                        self.status = format!("Output exported to {}", path);
+                    }
+                    Err(e) => {
+                        self.output = self.output.with_message(format!("❌ Cannot write {}: {}", path, e));
+                        self.status = "Export failed".into();
+                    }
+                },
+            }
+            self.scroll_to_bottom();
+        }
+
+        Ok(Command::Record(action)) => {
+            match action {
+                RecordAction::Start(path) => match Recorder::start(&path) {
+                    Ok(recorder) => {
+                        self.recorder = Some(recorder);
+                        self.output = self.output.with_message(
+                            format!("⏺️  Recording session to {}", path)
+                        );
+                        self.status = format!("Recording to {}", path);
+                    }
+                    Err(e) => {
+                        self.output = self.output.with_message(format!("❌ {}", e));
+                        self.status = "Recording not started".into();
+                    }
+                },
+                RecordAction::Stop => match self.recorder.take() {
+                    Some(recorder) => {
+                        self.output = self.output.with_message(format!(
+                            "⏹️  Recording saved: {} ({} entries)",
+                            recorder.path().display(),
+                            recorder.entries()
+                        ));
+                        self.status = "Recording stopped".into();
+                    }
+                    None => self.status = "Not recording".into(),
+                },
+                RecordAction::Status => {
+                    self.status = match &self.recorder {
+                        Some(recorder) => format!(
+                            "Recording to {} ({} entries)",
+                            recorder.path().display(),
+                            recorder.entries()
+                        ),
+                        None => "Not recording - use :record start <file>".into(),
+                    };
+                }
+            }
+            self.scroll_to_bottom();
+        }
+
+        Ok(Command::Result(number)) => self.open_result(number),
+
+        Ok(Command::Tree(number)) => self.open_tree(number),
+
+        Ok(Command::Query { result, expr }) => self.run_query(result, &expr),
+
+        Ok(Command::Replay(path)) => {
+            match Recording::load(&path) {
+                Ok(recording) => {
+                    let server = ReplayServer::new(&recording);
+                    let commands: Vec<String> = recording
+                        .commands()
+                        .into_iter()
+                        .filter(|c| !c.starts_with("replay") && !c.starts_with("record"))
+                        .collect();
+
+                    self.output = self.output.with_message(format!(
+                        "📼 Replaying {}: {} messages, {} recorded requests",
+                        path,
+                        recording.message_count(),
+                        server.exchange_count()
+                    ));
+                    if !commands.is_empty() {
+                        self.output = self.output.with_message("  Recorded commands (↑ in COMMAND mode to re-run):".to_string());
+                        for command in &commands {
+                            self.output = self.output.with_message(format!("    :{}", command));
+                            self.completion_context = self.completion_context
+                                .with_history_entry(command.clone());
+                        }
+                    }
+
+                    self.available_tools.clear();
+                    self.status = format!("Replaying {}", path);
+                    let name = std::path::Path::new(&path)
+                        .file_name()
+                        .map_or_else(|| path.clone(), |n| n.to_string_lossy().into_owned());
+                    self.mcp_client.connect_replay(&name, server).await;
+                }
+                Err(e) => {
+                    self.output = self.output.with_message(format!("❌ Cannot replay: {}", e));
+                    self.status = "Replay failed".into();
+                }
+            }
+            self.scroll_to_bottom();
+        }
+
+        Ok(Command::Rpc { method, params }) => {
+            return Ok(self.send_rpc(method, params));
+        }
+
+        Ok(Command::Profile(None)) => {
+            if self.config.profiles.is_empty() {
+                self.output = self.output.with_message(
+                    "No profiles configured - add them under [profiles.<name>] in the config".to_string()
+                );
+            } else {
+                self.output = self.output.with_message("🌐 Profiles:".to_string());
+                for (name, profile) in &self.config.profiles {
+                    let marker = if self.config.active_profile.as_ref() == Some(name) { "→" } else { " " };
+                    let production = if profile.production { " (production)" } else { "" };
+                    self.output = self.output.with_message(format!("  {} {}{}", marker, name, production));
+                }
+            }
+            self.status = match self.profile() {
+                Some(name) => format!("Profile: {}", name),
+                None => "No profile active".into(),
+            };
+            self.scroll_to_bottom();
+        }
+
+        Ok(Command::Profile(Some(name))) => {
+            self = self.switch_profile(&name);
+        }
+
+        Ok(Command::Highlight(action)) => {
+            self.output_view.highlight = match action {
+                HighlightAction::Toggle if self.output_view.highlight.is_some() => None,
+                HighlightAction::Toggle | HighlightAction::On => Some(self.theme),
+                HighlightAction::Off => None,
+                HighlightAction::Theme(theme) => {
+                    self.theme = theme;
+                    Some(theme)
+                }
+            };
+            self.status = match self.output_view.highlight {
+                Some(theme) => format!("Highlighting on ({} theme)", theme.name()),
+                None => "Highlighting off".into(),
+            };
+        }
+
         Ok(Command::Mouse(enabled)) => {
             self.mouse_enabled = enabled;
             let state = if enabled { "enabled" } else { "disabled" };
@@ -1026,10 +2364,10 @@ async fn handle_command_key(mut self, code: KeyCode) -> Result<Self> {
 }
 
 impl Default for App {
+    /// An app without servers; they can be added with `:mcp add`
     fn default() -> Self {
-        let config = Config::from_file("config.json").unwrap();
-        Self::new(config)
-        }
+        Self::new(Config::default())
+    }
 }
 
 impl ServerSelection {
@@ -1051,3 +2389,29 @@ impl ToolSelection {
         self.selected
     }
 }
+
+/// Config → (theme, highlighting). Highlighting is on unless
+/// turned off; the theme follows the terminal unless one is configured.
+fn highlight_setting(config: &Config) -> (Theme, Option<Theme>) {
+    let theme = config.highlight_theme.unwrap_or_else(Theme::detect);
+    (theme, config.highlight.unwrap_or(true).then_some(theme))
+}
+
+/// Pure function: error → `{header}: {error}` and its indented data
+fn error_lines(header: String, error: crate::jsonrpc::ErrorObject) -> Vec<String> {
+    let data = error.data.clone();
+    let mut lines = vec![format!("{}: {}", header, McpError::from(error))];
+    if let Some(data) = data {
+        let pretty = serde_json::to_string_pretty(&data).unwrap_or_default();
+        lines.extend(pretty.lines().map(|line| format!("  {}", line)));
+    }
+    lines
+}
+
+/// Pure function: server notification → output line
+fn describe_notification(notification: &Notification) -> String {
+    match notification.method.as_str() {
+        "notifications/tools/list_changed" => "🔔 Tools list changed - use :mcp tools to refresh".to_string(),
+        method => format!("🔔 Notification: {}", method),
+    }
+}
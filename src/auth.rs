@@ -0,0 +1,1171 @@
+// ============================================================================
+// src/auth.rs - OAuth 2.1 authorization for protected MCP servers
+// ============================================================================
+//
+// Client side of the MCP authorization spec:
+// - Protected resource metadata discovery (RFC 9728)
+// - Authorization server metadata discovery (RFC 8414 / OpenID discovery)
+// - Dynamic client registration (RFC 7591)
+// - Authorization code grant with PKCE (RFC 7636) through a loopback redirect
+// - Token refresh with a per-server token cache on disk
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// How long the loopback listener waits for the browser redirect
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Tokens expiring within this window are treated as already expired
+const EXPIRY_MARGIN_SECS: u64 = 30;
+
+const CALLBACK_PATH: &str = "/callback";
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("{stage} request failed: {source}")]
+    Http {
+        stage: &'static str,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("{stage} failed: HTTP {status}: {body}")]
+    Status {
+        stage: &'static str,
+        status: StatusCode,
+        body: String,
+    },
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Authorization server does not support dynamic client registration")]
+    RegistrationUnsupported,
+    #[error("Authorization server does not support PKCE with S256")]
+    PkceUnsupported,
+    #[error("No refresh token available")]
+    NoRefreshToken,
+    #[error("Redirect listener error: {0}")]
+    Listener(#[from] std::io::Error),
+    #[error("Authorization denied: {0}")]
+    Denied(String),
+    #[error("State mismatch in authorization response")]
+    StateMismatch,
+    #[error("Timed out waiting for authorization in the browser")]
+    Timeout,
+    #[error("Token cache error: {0}")]
+    Cache(String),
+}
+
+// ═══════════════════════════════════════════════════════════════
+// WWW-Authenticate challenge
+// ═══════════════════════════════════════════════════════════════
+
+/// Parameters of a `WWW-Authenticate: Bearer ...` challenge
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BearerChallenge {
+    pub resource_metadata: Option<String>,
+    pub scope: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Pure function: &str → Option<BearerChallenge>
+/// Returns None when the header carries no Bearer challenge
+pub fn parse_www_authenticate(header: &str) -> Option<BearerChallenge> {
+    let mut rest = &header[scheme_end(header, "bearer")?..];
+    let mut challenge = BearerChallenge::default();
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        let Some(eq) = rest.find('=') else { break };
+        let key = rest[..eq].trim().to_ascii_lowercase();
+        // A token without '=' before the next space starts another challenge
+        if key.contains(char::is_whitespace) {
+            break;
+        }
+        rest = &rest[eq + 1..];
+
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let mut end = quoted.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            value.push(escaped);
+                        }
+                    }
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    _ => value.push(c),
+                }
+            }
+            rest = &quoted[end.min(quoted.len())..];
+            value
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let value = rest[..end].trim().to_string();
+            rest = &rest[end..];
+            value
+        };
+
+        match key.as_str() {
+            "resource_metadata" => challenge.resource_metadata = Some(value),
+            "scope" => challenge.scope = Some(value),
+            "error" => challenge.error = Some(value),
+            _ => {}
+        }
+    }
+
+    Some(challenge)
+}
+
+/// Pure function: offset just past the `scheme` auth-scheme token. It must
+/// start the header or follow a comma or whitespace, and be followed by
+/// whitespace or the end; quoted parameter values are skipped.
+fn scheme_end(header: &str, scheme: &str) -> Option<usize> {
+    let bytes = header.as_bytes();
+    let mut quoted = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if quoted => i += 1,
+            b'"' => quoted = !quoted,
+            _ if !quoted && (i == 0 || bytes[i - 1] == b',' || bytes[i - 1].is_ascii_whitespace()) => {
+                let end = i + scheme.len();
+                let word = bytes.get(i..end).is_some_and(|word| word.eq_ignore_ascii_case(scheme.as_bytes()));
+                if word && bytes.get(end).is_none_or(u8::is_ascii_whitespace) {
+                    return Some(end);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// ═══════════════════════════════════════════════════════════════
+// PKCE
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone)]
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+impl Pkce {
+    /// Generates a fresh 32-byte verifier with its S256 challenge
+    pub fn generate() -> Self {
+        Self::from_verifier(random_token(32))
+    }
+
+    /// Pure function: verifier → Pkce
+    pub fn from_verifier(verifier: String) -> Self {
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Self { verifier, challenge }
+    }
+}
+
+fn random_token(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buf);
+    URL_SAFE_NO_PAD.encode(buf)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Metadata documents
+// ═══════════════════════════════════════════════════════════════
+
+/// RFC 9728 protected resource metadata
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProtectedResourceMetadata {
+    #[serde(default)]
+    pub resource: Option<String>,
+    #[serde(default)]
+    pub authorization_servers: Vec<String>,
+    #[serde(default)]
+    pub scopes_supported: Vec<String>,
+}
+
+/// RFC 8414 authorization server metadata
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthorizationServerMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    #[serde(default)]
+    pub registration_endpoint: Option<String>,
+    #[serde(default)]
+    pub code_challenge_methods_supported: Option<Vec<String>>,
+}
+
+impl AuthorizationServerMetadata {
+    /// Default endpoints for servers without a metadata document
+    fn fallback(issuer: &Url) -> Self {
+        let origin = origin_of(issuer);
+        Self {
+            issuer: origin.clone(),
+            authorization_endpoint: format!("{}/authorize", origin),
+            token_endpoint: format!("{}/token", origin),
+            registration_endpoint: Some(format!("{}/register", origin)),
+            code_challenge_methods_supported: None,
+        }
+    }
+}
+
+fn origin_of(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+/// Pure function: base URL × well-known suffix → candidate URLs
+/// Path-aware location first, then the root of the origin
+fn well_known_urls(base: &Url, suffix: &str) -> Vec<String> {
+    let origin = origin_of(base);
+    let path = base.path().trim_end_matches('/');
+    let mut urls = Vec::new();
+    if !path.is_empty() {
+        urls.push(format!("{}/.well-known/{}{}", origin, suffix, path));
+    }
+    urls.push(format!("{}/.well-known/{}", origin, suffix));
+    urls
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Cached state
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenSet {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Unix timestamp (seconds) when the access token expires
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+impl TokenSet {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|at| at <= now_secs() + EXPIRY_MARGIN_SECS)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientCredentials {
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    pub redirect_uri: String,
+}
+
+/// Everything remembered per server between sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthState {
+    pub server_url: String,
+    pub resource: String,
+    pub issuer: String,
+    pub token_endpoint: String,
+    pub client: ClientCredentials,
+    #[serde(default)]
+    pub tokens: Option<TokenSet>,
+}
+
+/// On-disk token cache, one JSON file per server
+#[derive(Debug, Clone)]
+pub struct TokenStore {
+    dir: PathBuf,
+}
+
+impl TokenStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `$XDG_CACHE_HOME/mcp-client/tokens`, falling back to `~/.cache`
+    pub fn default_location() -> Self {
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))
+            .unwrap_or_else(std::env::temp_dir);
+        Self::new(base.join("mcp-client").join("tokens"))
+    }
+
+    fn path(&self, server: &str) -> PathBuf {
+        let file: String = server
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.json", file))
+    }
+
+    pub fn load(&self, server: &str) -> Option<AuthState> {
+        let content = fs::read_to_string(self.path(server)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Writes atomically (temp file + rename), readable by the owner only
+    pub fn save(&self, server: &str, state: &AuthState) -> Result<(), AuthError> {
+        let cache_error = |e: std::io::Error| AuthError::Cache(e.to_string());
+        create_private_dir(&self.dir).map_err(cache_error)?;
+        let path = self.path(server);
+        let tmp = path.with_extension("json.tmp");
+        let content = serde_json::to_string_pretty(state).map_err(|e| AuthError::Cache(e.to_string()))?;
+        create_private_file(&tmp)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(cache_error)?;
+        fs::rename(&tmp, &path).map_err(cache_error)
+    }
+
+    pub fn remove(&self, server: &str) {
+        let _ = fs::remove_file(self.path(server));
+    }
+}
+
+/// Creates `dir` and missing parents, accessible by the owner only (0700)
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+}
+
+/// Opens `path` for writing, truncated and readable by the owner only (0600)
+/// from the moment it exists
+fn create_private_file(path: &Path) -> std::io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(path)?;
+    // The mode only applies to new files; a leftover temp file keeps its own
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(file)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Authorizer
+// ═══════════════════════════════════════════════════════════════
+
+/// Runs the authorization flow for one configured server
+#[derive(Debug, Clone)]
+pub struct Authorizer {
+    client: Client,
+    store: TokenStore,
+    server_name: String,
+    server_url: String,
+}
+
+impl Authorizer {
+    pub fn new(client: Client, store: TokenStore, server_name: String, server_url: String) -> Self {
+        Self {
+            client,
+            store,
+            server_name,
+            server_url,
+        }
+    }
+
+    fn cached_state(&self) -> Option<AuthState> {
+        self.store
+            .load(&self.server_name)
+            .filter(|s| s.server_url == self.server_url)
+    }
+
+    /// Returns a usable access token from the cache, refreshing it if expired
+    pub async fn cached_token(&self) -> Option<String> {
+        let tokens = self.cached_state()?.tokens?;
+        if tokens.is_expired() {
+            self.refresh().await.ok()
+        } else {
+            Some(tokens.access_token)
+        }
+    }
+
+    /// Exchanges the cached refresh token for a new access token
+    pub async fn refresh(&self) -> Result<String, AuthError> {
+        let mut state = self.cached_state().ok_or(AuthError::NoRefreshToken)?;
+        let refresh_token = state
+            .tokens
+            .as_ref()
+            .and_then(|t| t.refresh_token.clone())
+            .ok_or(AuthError::NoRefreshToken)?;
+
+        let mut form = vec![
+            ("grant_type", "refresh_token".to_string()),
+            ("refresh_token", refresh_token.clone()),
+            ("client_id", state.client.client_id.clone()),
+            ("resource", state.resource.clone()),
+        ];
+        if let Some(secret) = &state.client.client_secret {
+            form.push(("client_secret", secret.clone()));
+        }
+
+        let mut tokens = self.request_token(&state.token_endpoint, &form).await?;
+        // Servers may omit the refresh token when it is not rotated
+        if tokens.refresh_token.is_none() {
+            tokens.refresh_token = Some(refresh_token);
+        }
+        let access = tokens.access_token.clone();
+        state.tokens = Some(tokens);
+        self.store.save(&self.server_name, &state)?;
+        Ok(access)
+    }
+
+    /// Full interactive flow: discovery, registration, browser consent, code exchange.
+    /// `open_url` receives the authorization URL the user has to visit.
+    pub async fn authorize<F>(&self, challenge: &BearerChallenge, open_url: F) -> Result<String, AuthError>
+    where
+        F: FnOnce(&str),
+    {
+        let server_url = Url::parse(&self.server_url)
+            .map_err(|_| AuthError::InvalidUrl(self.server_url.clone()))?;
+
+        let resource_meta = self.discover_resource(&server_url, challenge).await;
+        let resource = resource_meta
+            .as_ref()
+            .and_then(|m| m.resource.clone())
+            .unwrap_or_else(|| self.server_url.clone());
+        let issuer = resource_meta
+            .as_ref()
+            .and_then(|m| m.authorization_servers.first().cloned())
+            .unwrap_or_else(|| origin_of(&server_url));
+        let issuer_url = Url::parse(&issuer).map_err(|_| AuthError::InvalidUrl(issuer.clone()))?;
+
+        let metadata = self.discover_authorization_server(&issuer_url).await;
+        if let Some(methods) = &metadata.code_challenge_methods_supported {
+            if !methods.iter().any(|m| m == "S256") {
+                return Err(AuthError::PkceUnsupported);
+            }
+        }
+
+        let scope = challenge.scope.clone().or_else(|| {
+            resource_meta
+                .as_ref()
+                .filter(|m| !m.scopes_supported.is_empty())
+                .map(|m| m.scopes_supported.join(" "))
+        });
+
+        // Reuse the previous registration when its redirect port is still free
+        let previous = self
+            .store
+            .load(&self.server_name)
+            .filter(|s| s.issuer == metadata.issuer);
+        let listener = bind_loopback(previous.as_ref().and_then(|s| redirect_port(&s.client.redirect_uri))).await?;
+        let redirect_uri = format!("http://127.0.0.1:{}{}", listener.local_addr()?.port(), CALLBACK_PATH);
+        let client = match previous {
+            Some(state) if state.client.redirect_uri == redirect_uri => state.client,
+            _ => self.register(&metadata, &redirect_uri).await?,
+        };
+
+        let pkce = Pkce::generate();
+        let state_param = random_token(16);
+
+        let mut auth_url = Url::parse(&metadata.authorization_endpoint)
+            .map_err(|_| AuthError::InvalidUrl(metadata.authorization_endpoint.clone()))?;
+        {
+            let mut query = auth_url.query_pairs_mut();
+            query
+                .append_pair("response_type", "code")
+                .append_pair("client_id", &client.client_id)
+                .append_pair("redirect_uri", &redirect_uri)
+                .append_pair("code_challenge", &pkce.challenge)
+                .append_pair("code_challenge_method", "S256")
+                .append_pair("state", &state_param)
+                .append_pair("resource", &resource);
+            if let Some(scope) = &scope {
+                query.append_pair("scope", scope);
+            }
+        }
+
+        open_url(auth_url.as_str());
+
+        let params = tokio::time::timeout(AUTHORIZATION_TIMEOUT, wait_for_redirect(&listener))
+            .await
+            .map_err(|_| AuthError::Timeout)??;
+
+        if let Some(error) = params.get("error") {
+            let description = params.get("error_description").cloned().unwrap_or_default();
+            return Err(AuthError::Denied(format!("{} {}", error, description).trim().to_string()));
+        }
+        if params.get("state") != Some(&state_param) {
+            return Err(AuthError::StateMismatch);
+        }
+        let code = params
+            .get("code")
+            .cloned()
+            .ok_or_else(|| AuthError::Denied("no authorization code in redirect".into()))?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code".to_string()),
+            ("code", code),
+            ("redirect_uri", redirect_uri.clone()),
+            ("client_id", client.client_id.clone()),
+            ("code_verifier", pkce.verifier),
+            ("resource", resource.clone()),
+        ];
+        if let Some(secret) = &client.client_secret {
+            form.push(("client_secret", secret.clone()));
+        }
+
+        let tokens = self.request_token(&metadata.token_endpoint, &form).await?;
+        let access = tokens.access_token.clone();
+
+        self.store.save(
+            &self.server_name,
+            &AuthState {
+                server_url: self.server_url.clone(),
+                resource,
+                issuer: metadata.issuer,
+                token_endpoint: metadata.token_endpoint,
+                client,
+                tokens: Some(tokens),
+            },
+        )?;
+
+        Ok(access)
+    }
+
+    /// Forgets cached tokens and client registration for this server
+    pub fn logout(&self) {
+        self.store.remove(&self.server_name);
+    }
+
+    async fn fetch_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Option<T> {
+        let response = self.client.get(url).send().await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        response.json().await.ok()
+    }
+
+    async fn discover_resource(
+        &self,
+        server_url: &Url,
+        challenge: &BearerChallenge,
+    ) -> Option<ProtectedResourceMetadata> {
+        let mut candidates: Vec<String> = challenge.resource_metadata.iter().cloned().collect();
+        candidates.extend(well_known_urls(server_url, "oauth-protected-resource"));
+
+        for url in candidates {
+            if let Some(meta) = self.fetch_json(&url).await {
+                return Some(meta);
+            }
+        }
+        None
+    }
+
+    async fn discover_authorization_server(&self, issuer: &Url) -> AuthorizationServerMetadata {
+        let mut candidates = well_known_urls(issuer, "oauth-authorization-server");
+        candidates.extend(well_known_urls(issuer, "openid-configuration"));
+        let path = issuer.path().trim_end_matches('/');
+        if !path.is_empty() {
+            candidates.push(format!("{}{}/.well-known/openid-configuration", origin_of(issuer), path));
+        }
+
+        for url in candidates {
+            if let Some(meta) = self.fetch_json(&url).await {
+                return meta;
+            }
+        }
+        AuthorizationServerMetadata::fallback(issuer)
+    }
+
+    async fn register(
+        &self,
+        metadata: &AuthorizationServerMetadata,
+        redirect_uri: &str,
+    ) -> Result<ClientCredentials, AuthError> {
+        let endpoint = metadata
+            .registration_endpoint
+            .as_ref()
+            .ok_or(AuthError::RegistrationUnsupported)?;
+
+        let body = serde_json::json!({
+            "client_name": "mcp-client",
+            "redirect_uris": [redirect_uri],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "response_types": ["code"],
+            "token_endpoint_auth_method": "none",
+        });
+
+        let response = self
+            .client
+            .post(endpoint)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|source| AuthError::Http { stage: "Client registration", source })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            if status == StatusCode::NOT_FOUND {
+                return Err(AuthError::RegistrationUnsupported);
+            }
+            let body = response.text().await.unwrap_or_default();
+            return Err(AuthError::Status { stage: "Client registration", status, body });
+        }
+
+        #[derive(Deserialize)]
+        struct Registration {
+            client_id: String,
+            #[serde(default)]
+            client_secret: Option<String>,
+        }
+
+        let registration: Registration = response
+            .json()
+            .await
+            .map_err(|source| AuthError::Http { stage: "Client registration", source })?;
+
+        Ok(ClientCredentials {
+            client_id: registration.client_id,
+            client_secret: registration.client_secret,
+            redirect_uri: redirect_uri.to_string(),
+        })
+    }
+
+    async fn request_token(&self, endpoint: &str, form: &[(&str, String)]) -> Result<TokenSet, AuthError> {
+        let response = self
+            .client
+            .post(endpoint)
+            .header("Accept", "application/json")
+            .form(form)
+            .send()
+            .await
+            .map_err(|source| AuthError::Http { stage: "Token request", source })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AuthError::Status { stage: "Token request", status, body });
+        }
+
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|source| AuthError::Http { stage: "Token request", source })?;
+
+        Ok(TokenSet {
+            access_token: token.access_token,
+            refresh_token: token.refresh_token,
+            expires_at: token.expires_in.map(|secs| now_secs() + secs),
+            scope: token.scope,
+        })
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Loopback redirect listener
+// ═══════════════════════════════════════════════════════════════
+
+fn redirect_port(uri: &str) -> Option<u16> {
+    Url::parse(uri).ok()?.port()
+}
+
+async fn bind_loopback(preferred: Option<u16>) -> Result<TcpListener, AuthError> {
+    if let Some(port) = preferred {
+        if let Ok(listener) = TcpListener::bind(("127.0.0.1", port)).await {
+            return Ok(listener);
+        }
+    }
+    Ok(TcpListener::bind(("127.0.0.1", 0)).await?)
+}
+
+/// Accepts connections until the browser hits the callback path,
+/// then returns its query parameters
+async fn wait_for_redirect(listener: &TcpListener) -> Result<HashMap<String, String>, AuthError> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let Some(target) = read_request_target(&mut stream).await else {
+            continue;
+        };
+
+        let url = match Url::parse(&format!("http://127.0.0.1{}", target)) {
+            Ok(url) if url.path() == CALLBACK_PATH => url,
+            _ => {
+                let _ = write_response(&mut stream, "404 Not Found", "Not found").await;
+                continue;
+            }
+        };
+
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        let message = if params.contains_key("code") {
+            "Authorization complete. You can close this window and return to the terminal."
+        } else {
+            "Authorization failed. Check the terminal for details."
+        };
+        let _ = write_response(&mut stream, "200 OK", message).await;
+        return Ok(params);
+    }
+}
+
+async fn read_request_target(stream: &mut TcpStream) -> Option<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") && buf.len() < 16 * 1024 {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let head = String::from_utf8_lossy(&buf);
+    let request_line = head.lines().next()?;
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => Some(target.to_string()),
+        _ => None,
+    }
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, message: &str) -> std::io::Result<()> {
+    let body = format!(
+        "<!DOCTYPE html><html><head><title>mcp-client</title></head><body><p>{}</p></body></html>",
+        message
+    );
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Best-effort attempt to open the system browser; the URL is also
+/// printed to the output so the user can open it manually
+pub fn open_browser(url: &str) {
+    use std::process::{Command, Stdio};
+
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    } else {
+        Command::new("xdg-open")
+    };
+
+    let _ = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_parse_www_authenticate() {
+        let header = r#"Bearer resource_metadata="http://localhost:8080/.well-known/oauth-protected-resource", scope="read write""#;
+        let challenge = parse_www_authenticate(header).unwrap();
+        assert_eq!(
+            challenge.resource_metadata.as_deref(),
+            Some("http://localhost:8080/.well-known/oauth-protected-resource")
+        );
+        assert_eq!(challenge.scope.as_deref(), Some("read write"));
+        assert_eq!(challenge.error, None);
+    }
+
+    #[test]
+    fn test_parse_www_authenticate_unquoted_and_escaped() {
+        let challenge = parse_www_authenticate(r#"bearer error=invalid_token, scope="a \"b\"""#).unwrap();
+        assert_eq!(challenge.error.as_deref(), Some("invalid_token"));
+        assert_eq!(challenge.scope.as_deref(), Some(r#"a "b""#));
+
+        assert_eq!(parse_www_authenticate("Bearer"), Some(BearerChallenge::default()));
+        assert_eq!(parse_www_authenticate(r#"Basic realm="x""#), None);
+    }
+
+    #[test]
+    fn test_parse_www_authenticate_needs_bearer_scheme() {
+        assert_eq!(parse_www_authenticate(r#"Basic realm="bearer-api""#), None);
+        assert_eq!(parse_www_authenticate(r#"Basic realm="x, bearer y""#), None);
+        assert_eq!(parse_www_authenticate("Bearerish realm=x"), None);
+
+        let challenge = parse_www_authenticate(r#"Basic realm="api", Bearer scope="read""#).unwrap();
+        assert_eq!(challenge.scope.as_deref(), Some("read"));
+    }
+
+    #[test]
+    fn test_pkce_s256() {
+        // RFC 7636 Appendix B
+        let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".into());
+        assert_eq!(pkce.challenge, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+
+        let generated = Pkce::generate();
+        assert_eq!(generated.verifier.len(), 43);
+        assert_ne!(generated.verifier, Pkce::generate().verifier);
+    }
+
+    #[test]
+    fn test_well_known_urls() {
+        let url = Url::parse("http://localhost:8080/mcp/sse").unwrap();
+        assert_eq!(
+            well_known_urls(&url, "oauth-protected-resource"),
+            vec![
+                "http://localhost:8080/.well-known/oauth-protected-resource/mcp/sse",
+                "http://localhost:8080/.well-known/oauth-protected-resource",
+            ]
+        );
+        let root = Url::parse("https://auth.example.com").unwrap();
+        assert_eq!(
+            well_known_urls(&root, "oauth-authorization-server"),
+            vec!["https://auth.example.com/.well-known/oauth-authorization-server"]
+        );
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        let mut tokens = TokenSet {
+            access_token: "a".into(),
+            refresh_token: None,
+            expires_at: None,
+            scope: None,
+        };
+        assert!(!tokens.is_expired());
+        tokens.expires_at = Some(now_secs() + 3600);
+        assert!(!tokens.is_expired());
+        tokens.expires_at = Some(now_secs() + 5);
+        assert!(tokens.is_expired());
+    }
+
+    // ───────────────────────────────────────────────────────────
+    // Stand-in authorization server
+    // ───────────────────────────────────────────────────────────
+
+    #[derive(Default)]
+    struct FakeAuthServer {
+        code_challenge: Option<String>,
+        registrations: usize,
+        refreshes: usize,
+    }
+
+    struct Request {
+        method: String,
+        path: String,
+        query: HashMap<String, String>,
+        body: String,
+    }
+
+    async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let header_end = loop {
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        };
+        let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+        let content_length = head
+            .lines()
+            .filter_map(|l| l.split_once(':'))
+            .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        while buf.len() < header_end + content_length {
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                break;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        let mut parts = head.lines().next()?.split_whitespace();
+        let method = parts.next()?.to_string();
+        let url = Url::parse(&format!("http://x{}", parts.next()?)).ok()?;
+        Some(Request {
+            method,
+            path: url.path().to_string(),
+            query: url.query_pairs().into_owned().collect(),
+            body: String::from_utf8_lossy(&buf[header_end..]).to_string(),
+        })
+    }
+
+    fn form(body: &str) -> HashMap<String, String> {
+        Url::parse(&format!("http://x/?{}", body))
+            .map(|u| u.query_pairs().into_owned().collect())
+            .unwrap_or_default()
+    }
+
+    async fn respond(stream: &mut TcpStream, status: &str, headers: &[(&str, String)], body: &str) {
+        let mut response = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n", status, body.len());
+        for (k, v) in headers {
+            response.push_str(&format!("{}: {}\r\n", k, v));
+        }
+        response.push_str("\r\n");
+        response.push_str(body);
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+
+    async fn spawn_auth_server() -> (String, Arc<Mutex<FakeAuthServer>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(FakeAuthServer::default()));
+        let (server_base, server_state) = (base.clone(), state.clone());
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else { break };
+                let base = server_base.clone();
+                let state = server_state.clone();
+                tokio::spawn(async move {
+                    let Some(req) = read_request(&mut stream).await else { return };
+                    let json_header = [("Content-Type", "application/json".to_string())];
+                    match (req.method.as_str(), req.path.as_str()) {
+                        ("GET", "/.well-known/oauth-protected-resource") => {
+                            let body = json!({
+                                "resource": format!("{}/sse", base),
+                                "authorization_servers": [base],
+                                "scopes_supported": ["mcp"],
+                            });
+                            respond(&mut stream, "200 OK", &json_header, &body.to_string()).await;
+                        }
+                        ("GET", "/.well-known/oauth-authorization-server") => {
+                            let body = json!({
+                                "issuer": base,
+                                "authorization_endpoint": format!("{}/authorize", base),
+                                "token_endpoint": format!("{}/token", base),
+                                "registration_endpoint": format!("{}/register", base),
+                                "code_challenge_methods_supported": ["S256"],
+                            });
+                            respond(&mut stream, "200 OK", &json_header, &body.to_string()).await;
+                        }
+                        ("POST", "/register") => {
+                            let request: Value = serde_json::from_str(&req.body).unwrap();
+                            assert!(request["redirect_uris"][0].as_str().unwrap().starts_with("http://127.0.0.1:"));
+                            state.lock().unwrap().registrations += 1;
+                            let body = json!({ "client_id": "client-123" });
+                            respond(&mut stream, "201 Created", &json_header, &body.to_string()).await;
+                        }
+                        ("GET", "/authorize") => {
+                            assert_eq!(req.query["client_id"], "client-123");
+                            assert_eq!(req.query["code_challenge_method"], "S256");
+                            assert_eq!(req.query["scope"], "mcp");
+                            assert_eq!(req.query["resource"], format!("{}/sse", base));
+                            state.lock().unwrap().code_challenge = Some(req.query["code_challenge"].clone());
+                            let location = format!(
+                                "{}?code=auth-code&state={}",
+                                req.query["redirect_uri"], req.query["state"]
+                            );
+                            respond(&mut stream, "302 Found", &[("Location", location)], "").await;
+                        }
+                        ("POST", "/token") => {
+                            let params = form(&req.body);
+                            let body = match params["grant_type"].as_str() {
+                                "authorization_code" => {
+                                    let expected = state.lock().unwrap().code_challenge.clone();
+                                    let actual = Pkce::from_verifier(params["code_verifier"].clone()).challenge;
+                                    assert_eq!(Some(actual), expected, "PKCE verifier mismatch");
+                                    assert_eq!(params["code"], "auth-code");
+                                    // Already expired so the next lookup refreshes
+                                    json!({
+                                        "access_token": "access-1",
+                                        "token_type": "Bearer",
+                                        "expires_in": 0,
+                                        "refresh_token": "refresh-1",
+                                    })
+                                }
+                                "refresh_token" => {
+                                    assert_eq!(params["refresh_token"], "refresh-1");
+                                    state.lock().unwrap().refreshes += 1;
+                                    json!({
+                                        "access_token": "access-2",
+                                        "token_type": "Bearer",
+                                        "expires_in": 3600,
+                                    })
+                                }
+                                other => panic!("unexpected grant type {}", other),
+                            };
+                            respond(&mut stream, "200 OK", &json_header, &body.to_string()).await;
+                        }
+                        _ => respond(&mut stream, "404 Not Found", &[], "").await,
+                    }
+                });
+            }
+        });
+
+        (base, state)
+    }
+
+    /// Token store in a temp dir that goes away with the returned guard
+    fn temp_store() -> (TempDir, TokenStore) {
+        let dir = TempDir::new("auth");
+        let store = TokenStore::new(dir.join("tokens"));
+        (dir, store)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_saved_tokens_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let (_dir, store) = temp_store();
+        // a temp file left over from an earlier run with a wide mode
+        fs::create_dir_all(store.path("srv").parent().unwrap()).unwrap();
+        let tmp = store.path("srv").with_extension("json.tmp");
+        fs::write(&tmp, "{}").unwrap();
+        fs::set_permissions(&tmp, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let state: AuthState = serde_json::from_value(json!({
+            "server_url": "http://s/sse",
+            "resource": "http://s/sse",
+            "issuer": "http://s",
+            "token_endpoint": "http://s/token",
+            "client": { "client_id": "c", "redirect_uri": "http://127.0.0.1:1/callback" },
+        }))
+        .unwrap();
+        store.save("srv", &state).unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&store.path("srv")), 0o600);
+        assert_eq!(store.load("srv").unwrap().client.client_id, "c");
+
+        let (_dir, fresh) = temp_store();
+        fresh.save("srv", &state).unwrap();
+        assert_eq!(mode(&fresh.dir), 0o700);
+        assert_eq!(mode(&fresh.path("srv")), 0o600);
+    }
+
+    /// Simulates the user's browser: follows the authorization redirect
+    /// to the loopback listener
+    fn fake_browser(url: &str) {
+        let url = url.to_string();
+        tokio::spawn(async move {
+            let _ = reqwest::get(url).await;
+        });
+    }
+
+    #[tokio::test]
+    async fn test_full_authorization_flow_and_refresh() {
+        let (base, server) = spawn_auth_server().await;
+        let (_dir, store) = temp_store();
+        let authorizer = Authorizer::new(Client::new(), store.clone(), "test".into(), format!("{}/sse", base));
+
+        assert_eq!(authorizer.cached_token().await, None);
+
+        let challenge = parse_www_authenticate(&format!(
+            r#"Bearer resource_metadata="{}/.well-known/oauth-protected-resource""#,
+            base
+        ))
+        .unwrap();
+        let token = authorizer.authorize(&challenge, fake_browser).await.unwrap();
+        assert_eq!(token, "access-1");
+
+        let cached = store.load("test").unwrap();
+        assert_eq!(cached.client.client_id, "client-123");
+        assert_eq!(cached.tokens.unwrap().refresh_token.as_deref(), Some("refresh-1"));
+
+        // Expired token is refreshed transparently and the refresh token is kept
+        assert_eq!(authorizer.cached_token().await.as_deref(), Some("access-2"));
+        assert_eq!(authorizer.cached_token().await.as_deref(), Some("access-2"));
+        let cached = store.load("test").unwrap().tokens.unwrap();
+        assert_eq!(cached.refresh_token.as_deref(), Some("refresh-1"));
+
+        // A second authorization reuses the registered client
+        authorizer.authorize(&challenge, fake_browser).await.unwrap();
+        {
+            let server = server.lock().unwrap();
+            assert_eq!(server.registrations, 1);
+            assert_eq!(server.refreshes, 1);
+        }
+
+        authorizer.logout();
+        assert!(store.load("test").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cached_token_ignored_for_other_url() {
+        let (_dir, store) = temp_store();
+        store
+            .save(
+                "srv",
+                &AuthState {
+                    server_url: "http://old/sse".into(),
+                    resource: "http://old/sse".into(),
+                    issuer: "http://old".into(),
+                    token_endpoint: "http://old/token".into(),
+                    client: ClientCredentials {
+                        client_id: "c".into(),
+                        client_secret: None,
+                        redirect_uri: "http://127.0.0.1:1/callback".into(),
+                    },
+                    tokens: Some(TokenSet {
+                        access_token: "stale".into(),
+                        refresh_token: None,
+                        expires_at: None,
+                        scope: None,
+                    }),
+                },
+            )
+            .unwrap();
+
+        let same = Authorizer::new(Client::new(), store.clone(), "srv".into(), "http://old/sse".into());
+        assert_eq!(same.cached_token().await.as_deref(), Some("stale"));
+
+        let moved = Authorizer::new(Client::new(), store, "srv".into(), "http://new/sse".into());
+        assert_eq!(moved.cached_token().await, None);
+    }
+}
@@ -0,0 +1,91 @@
+// ============================================================================
+// src/bin/mock_server.rs - Standalone mock MCP server for demos and testing
+// ============================================================================
+
+use mcp_client::mock::{self, Fixture};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: mcp-mock-server [--fixture <file.toml|file.json>] [--port <port>] [--stdio]
+
+  --fixture <path>   Tools, resources and prompts to serve (default: bundled demo)
+  --port <port>      HTTP port on 127.0.0.1 (default: 8080)
+  --stdio            Speak newline-delimited JSON-RPC on stdin/stdout instead of HTTP
+  -h, --help         Show this help";
+
+struct Options {
+    fixture: Option<String>,
+    port: u16,
+    stdio: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options { fixture: None, port: 8080, stdio: false };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fixture" => {
+                options.fixture = Some(args.next().ok_or("--fixture needs a path")?);
+            }
+            "--port" => {
+                let port = args.next().ok_or("--port needs a number")?;
+                options.port = port.parse().map_err(|_| format!("invalid port: {}", port))?;
+            }
+            "--stdio" => options.stdio = true,
+            "-h" | "--help" => return Err(String::new()),
+            other => return Err(format!("unknown argument: {}", other)),
+        }
+    }
+    Ok(options)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("error: {}\n", e);
+            }
+            eprintln!("{}", USAGE);
+            return if e.is_empty() { ExitCode::SUCCESS } else { ExitCode::from(2) };
+        }
+    };
+
+    let fixture = match &options.fixture {
+        Some(path) => match Fixture::load(path) {
+            Ok(fixture) => fixture,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Fixture::demo(),
+    };
+
+    if options.stdio {
+        return match mock::serve_stdio(fixture).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let handle = match mock::bind(fixture, &format!("127.0.0.1:{}", options.port)).await {
+        Ok(handle) => handle,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    eprintln!("🧪 Mock MCP server listening on {}", handle.base_url());
+    eprintln!("   SSE:             {}", handle.sse_url());
+    eprintln!("   Streamable HTTP: {}", handle.http_url());
+
+    tokio::select! {
+        _ = handle.wait() => ExitCode::FAILURE,
+        _ = tokio::signal::ctrl_c() => ExitCode::SUCCESS,
+    }
+}
@@ -0,0 +1,154 @@
+// ============================================================================
+// src/cli.rs - Command-line options of the mcp-client binary
+// ============================================================================
+
+use std::path::PathBuf;
+use thiserror::Error;
+
+pub const USAGE: &str = "\
+Usage: mcp-client [options]
+       mcp-client [options] <list-tools|call|read-resource> --server <name> ...
+
+Options:
+  --config <path>      Config file (default: .mcp-client.{toml,json5,json} in this or a
+                       parent directory, ./config.json, then $XDG_CONFIG_HOME/mcp-client/;
+                       created by :mcp add if it does not exist)
+  --profile <name>     Use a config profile (dev, staging, prod, ...)
+  --connect <server>   Connect to a configured server on start
+  -c <command>         Run a command after start, e.g. -c 'mcp run echo hi'
+                       (repeatable; commands after a connect wait for its tools)
+  --log-file <path>    Append MCP protocol and debug messages to a file
+  --no-mouse           Start with mouse capture disabled
+  --version            Print the version and exit
+  -h, --help           Show this help";
+
+const SUBCOMMANDS: [&str; 3] = ["list-tools", "call", "read-resource"];
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CliError {
+    #[error("{0} needs a value")]
+    MissingValue(String),
+    #[error("unknown option: {0}")]
+    UnknownOption(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliOptions {
+    pub config: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub connect: Option<String>,
+    pub commands: Vec<String>,
+    pub log_file: Option<PathBuf>,
+    pub no_mouse: bool,
+    pub version: bool,
+    pub help: bool,
+    /// Headless subcommand and its own arguments (empty for the TUI)
+    pub subcommand: Vec<String>,
+}
+
+impl CliOptions {
+    /// Pure function: argv (without program name) → options. Global
+    /// options are accepted anywhere; everything from a subcommand name on
+    /// that is not a global option belongs to the subcommand.
+    pub fn parse(args: &[String]) -> Result<Self, CliError> {
+        let mut options = Self::default();
+        let mut args = args.iter().flat_map(|arg| split_equals(arg));
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| CliError::MissingValue(arg.clone()));
+            match arg.as_str() {
+                "--config" => options.config = Some(PathBuf::from(value()?)),
+                "--profile" => options.profile = Some(value()?),
+                "--connect" => options.connect = Some(value()?),
+                "-c" | "--command" => options.commands.push(value()?),
+                "--log-file" => options.log_file = Some(PathBuf::from(value()?)),
+                "--no-mouse" => options.no_mouse = true,
+                "--version" | "-V" => options.version = true,
+                "-h" | "--help" if options.subcommand.is_empty() => options.help = true,
+                _ if !options.subcommand.is_empty() => options.subcommand.push(arg),
+                sub if SUBCOMMANDS.contains(&sub) => options.subcommand.push(arg),
+                _ => return Err(CliError::UnknownOption(arg)),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Commands to run after start: the `--connect` first, then each `-c`
+    pub fn startup_commands(&self) -> Vec<String> {
+        self.connect
+            .iter()
+            .map(|server| format!("mcp connect {}", server))
+            .chain(self.commands.iter().cloned())
+            .collect()
+    }
+}
+
+/// Pure function: `--flag=value` → [`--flag`, `value`]
+fn split_equals(arg: &str) -> Vec<String> {
+    match arg.split_once('=') {
+        Some((flag, value)) if flag.starts_with("--") => vec![flag.to_string(), value.to_string()],
+        _ => vec![arg.to_string()],
+    }
+}
+
+pub fn version() -> String {
+    format!("mcp-client {}", env!("CARGO_PKG_VERSION"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_tui_options() {
+        let options = CliOptions::parse(&argv(&[
+            "--config=dev.json",
+            "--connect",
+            "local",
+            "-c",
+            "mcp tools",
+            "-c",
+            "mcp run echo hi",
+            "--no-mouse",
+            "--log-file",
+            "/tmp/mcp.log",
+        ]))
+        .unwrap();
+        assert_eq!(options.config, Some(PathBuf::from("dev.json")));
+        assert!(options.no_mouse);
+        assert_eq!(options.log_file, Some(PathBuf::from("/tmp/mcp.log")));
+        assert!(options.subcommand.is_empty());
+        assert_eq!(
+            options.startup_commands(),
+            vec!["mcp connect local", "mcp tools", "mcp run echo hi"]
+        );
+    }
+
+    #[test]
+    fn test_subcommand_keeps_its_arguments() {
+        let options = CliOptions::parse(&argv(&[
+            "call", "--server", "s", "--config", "ci.json", "--tool", "t", "-v", "--profile=staging",
+        ]))
+        .unwrap();
+        assert_eq!(options.config, Some(PathBuf::from("ci.json")));
+        assert_eq!(options.profile.as_deref(), Some("staging"));
+        assert_eq!(options.subcommand, argv(&["call", "--server", "s", "--tool", "t", "-v"]));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            CliOptions::parse(&argv(&["--bogus"])),
+            Err(CliError::UnknownOption("--bogus".into()))
+        );
+        assert_eq!(
+            CliOptions::parse(&argv(&["-c"])),
+            Err(CliError::MissingValue("-c".into()))
+        );
+        assert!(CliOptions::parse(&argv(&["--version"])).unwrap().version);
+    }
+}
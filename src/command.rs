@@ -1,6 +1,8 @@
+use crate::highlight::Theme;
+use crate::state::EntryKind;
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Error, PartialEq, Eq)]
 pub enum CommandError {
     #[error("Unknown command: {0}")]
     Unknown(String),
@@ -22,7 +24,52 @@ pub enum Command {
     McpTool(String), // NEW: Show detailed tool description
     McpRun(Option<String>, Vec<String>), // (tool_name, args)
     McpStatus,
+    McpLogout,
+    McpAdd(String, Vec<String>),  // (name, url or command + args)
+    McpRemove(String),
+    McpEdit(String, Vec<String>), // empty endpoint: prefill the command line
+    McpReconnect,
+    McpPending,
+    Inspector(InspectorAction),
+    Rpc { method: String, params: Option<String> }, // raw JSON-RPC request
+    Record(RecordAction),
+    Replay(String), // recording file
     Mouse(bool),
+    Profile(Option<String>), // None: list profiles
+    Output(OutputAction),
+    Result(Option<usize>), // None: the last result
+    Tree(Option<usize>),   // None: the last result
+    Query { result: Option<usize>, expr: String }, // result None: the last one
+    Highlight(HighlightAction),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HighlightAction {
+    Toggle,
+    On,
+    Off,
+    Theme(Theme),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputAction {
+    Filter(Vec<EntryKind>), // empty: show everything
+    Group,                  // toggle grouping by kind
+    Export(String),         // JSONL file
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordAction {
+    Start(String),
+    Stop,
+    Status,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InspectorAction {
+    Toggle,
+    Filter(Option<String>),
+    Clear,
 }
 
 impl Command {
@@ -36,6 +83,14 @@ impl Command {
 
         let parts: Vec<&str> = trimmed.split_whitespace().collect();
 
+        // params are taken verbatim so whitespace inside JSON strings survives
+        if parts.first() == Some(&"rpc") {
+            return Self::parse_rpc(trimmed["rpc".len()..].trim());
+        }
+        if parts.first() == Some(&"query") {
+            return Self::parse_query(trimmed["query".len()..].trim());
+        }
+
         match parts.as_slice() {
             ["q"] | ["quit"] => Ok(Command::Quit),
             ["clear"] => Ok(Command::Clear),
@@ -66,13 +121,130 @@ impl Command {
             ["mcp", "run", tool_name, args @ ..] => {
                 Ok(Command::McpRun(Some(tool_name.to_string()), args.iter().map(|s| s.to_string()).collect()))
             }
+            ["mcp", "reconnect"] => Ok(Command::McpReconnect),
             ["mcp", "status"] => Ok(Command::McpStatus),
+            ["mcp", "pending"] => Ok(Command::McpPending),
+            ["mcp", "logout"] => Ok(Command::McpLogout),
+            ["mcp", "add", name, endpoint @ ..] if !endpoint.is_empty() => Ok(Command::McpAdd(
+                name.to_string(),
+                endpoint.iter().map(|s| s.to_string()).collect(),
+            )),
+            ["mcp", "add", ..] => Err(CommandError::InvalidSyntax(
+                "mcp add requires <name> <url|command [args...]>".into(),
+            )),
+            ["mcp", "remove", name] | ["mcp", "rm", name] => Ok(Command::McpRemove(name.to_string())),
+            ["mcp", "edit", name, endpoint @ ..] => Ok(Command::McpEdit(
+                name.to_string(),
+                endpoint.iter().map(|s| s.to_string()).collect(),
+            )),
+            ["inspector"] => Ok(Command::Inspector(InspectorAction::Toggle)),
+            ["inspector", "clear"] => Ok(Command::Inspector(InspectorAction::Clear)),
+            ["inspector", "filter"] => Ok(Command::Inspector(InspectorAction::Filter(None))),
+            ["inspector", "filter", rest @ ..] => {
+                Ok(Command::Inspector(InspectorAction::Filter(Some(rest.join(" ")))))
+            }
+            ["record"] => Ok(Command::Record(RecordAction::Status)),
+            ["record", "start", path] => Ok(Command::Record(RecordAction::Start(path.to_string()))),
+            ["record", "start"] => Err(CommandError::InvalidSyntax(
+                "record start requires a file".into(),
+            )),
+            ["record", "stop"] => Ok(Command::Record(RecordAction::Stop)),
+            ["replay", path] => Ok(Command::Replay(path.to_string())),
+            ["profile"] => Ok(Command::Profile(None)),
+            ["profile", name] => Ok(Command::Profile(Some(name.to_string()))),
+            ["output", "filter", kinds @ ..] => kinds
+                .iter()
+                .flat_map(|k| k.split(','))
+                .filter(|k| !k.is_empty())
+                .map(|k| {
+                    EntryKind::parse(k).ok_or_else(|| {
+                        CommandError::InvalidSyntax(format!(
+                            "unknown output kind '{}' (command, result, error, debug, notification, system)",
+                            k
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(|kinds| Command::Output(OutputAction::Filter(kinds))),
+            ["output", "group"] => Ok(Command::Output(OutputAction::Group)),
+            ["output", "export", path] => Ok(Command::Output(OutputAction::Export(path.to_string()))),
+            ["output", ..] => Err(CommandError::InvalidSyntax(
+                "output requires filter [kinds], group or export <file>".into(),
+            )),
+            ["result"] => Ok(Command::Result(None)),
+            ["result", n] => result_number(n).map(|n| Command::Result(Some(n))),
+            ["tree"] => Ok(Command::Tree(None)),
+            ["tree", n] => result_number(n).map(|n| Command::Tree(Some(n))),
+            ["highlight"] => Ok(Command::Highlight(HighlightAction::Toggle)),
+            ["highlight", "on"] => Ok(Command::Highlight(HighlightAction::On)),
+            ["highlight", "off"] => Ok(Command::Highlight(HighlightAction::Off)),
+            ["highlight", theme] => Theme::parse(theme)
+                .map(|theme| Command::Highlight(HighlightAction::Theme(theme)))
+                .ok_or_else(|| CommandError::InvalidSyntax(format!("highlight takes on, off, dark or light, got '{}'", theme))),
             ["mouse", "on"] => Ok(Command::Mouse(true)),
             ["mouse", "off"] => Ok(Command::Mouse(false)),
             [cmd, ..] => Err(CommandError::Unknown(cmd.to_string())),
             [] => unreachable!(), // Already handled empty case
         }
     }
+
+    /// Pure parser for raw requests typed in INSERT mode or after `:rpc`.
+    /// Accepts `<method> [json-params]` or a JSON object with `method` and
+    /// optional `params` (any `id` is replaced by the client's own).
+    pub fn parse_rpc(input: &str) -> Result<Self, CommandError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(CommandError::InvalidSyntax(
+                "rpc requires a method, e.g. rpc tools/list {}".into(),
+            ));
+        }
+
+        if input.starts_with('{') {
+            let value: serde_json::Value = serde_json::from_str(input)
+                .map_err(|e| CommandError::InvalidSyntax(format!("invalid JSON: {}", e)))?;
+            let method = value
+                .get("method")
+                .and_then(|m| m.as_str())
+                .ok_or_else(|| CommandError::InvalidSyntax("request object needs a \"method\" string".into()))?;
+            return Ok(Command::Rpc {
+                method: method.to_string(),
+                params: value.get("params").map(|p| p.to_string()),
+            });
+        }
+
+        let (method, params) = match input.split_once(char::is_whitespace) {
+            Some((method, params)) => (method, Some(params.trim().to_string())),
+            None => (input, None),
+        };
+        Ok(Command::Rpc {
+            method: method.to_string(),
+            params,
+        })
+    }
+
+    /// Pure parser for `:query [n] <expr>`; the expression is kept verbatim
+    /// and only parsed when it runs
+    fn parse_query(input: &str) -> Result<Self, CommandError> {
+        let (first, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        match result_number(first) {
+            Ok(n) if rest.trim().is_empty() => Err(CommandError::InvalidSyntax(format!(
+                "query {} requires an expression, e.g. query {} .components[].ref",
+                n, n
+            ))),
+            Ok(n) => Ok(Command::Query { result: Some(n), expr: rest.trim().to_string() }),
+            Err(_) if input.is_empty() => Err(CommandError::InvalidSyntax(
+                "query requires an expression, e.g. query .components[].ref".into(),
+            )),
+            Err(_) => Ok(Command::Query { result: None, expr: input.to_string() }),
+        }
+    }
+}
+
+/// Pure parser: `12` or `#12` → result number
+fn result_number(n: &str) -> Result<usize, CommandError> {
+    n.trim_start_matches('#')
+        .parse()
+        .map_err(|_| CommandError::InvalidSyntax(format!("result number expected, got '{}'", n)))
 }
 
 // ═══════════════════════════════════════════════════════════════
@@ -110,6 +282,7 @@ mod tests {
             Ok(Command::McpConnect(Some("pcbvi-mcp-server".into())))
         );
         assert_eq!(Command::parse("mcp connect"), Ok(Command::McpConnect(None)));
+        assert_eq!(Command::parse("mcp reconnect"), Ok(Command::McpReconnect));
     }
 
     #[test]
@@ -127,16 +300,166 @@ mod tests {
 
     #[test]
     fn test_mcp_run_command() {
-        assert_eq!(Command::parse("mcp run"), Ok(Command::McpRun(None)));
+        assert_eq!(Command::parse("mcp run"), Ok(Command::McpRun(None, vec![])));
         assert_eq!(
             Command::parse("mcp run get_view_state"),
-            Ok(Command::McpRun(Some("get_view_state".into())))
+            Ok(Command::McpRun(Some("get_view_state".into()), vec![]))
+        );
+        assert_eq!(
+            Command::parse("mcp run search_components U* 5"),
+            Ok(Command::McpRun(
+                Some("search_components".into()),
+                vec!["U*".into(), "5".into()]
+            ))
         );
     }
 
     #[test]
     fn test_mcp_status_command() {
         assert_eq!(Command::parse("mcp status"), Ok(Command::McpStatus));
+        assert_eq!(Command::parse("mcp pending"), Ok(Command::McpPending));
+    }
+
+    #[test]
+    fn test_mcp_logout_command() {
+        assert_eq!(Command::parse("mcp logout"), Ok(Command::McpLogout));
+    }
+
+    #[test]
+    fn test_mcp_server_edit_commands() {
+        assert_eq!(
+            Command::parse("mcp add local http://localhost:8080/sse"),
+            Ok(Command::McpAdd("local".into(), vec!["http://localhost:8080/sse".into()]))
+        );
+        assert_eq!(
+            Command::parse("mcp add fs npx -y server-fs"),
+            Ok(Command::McpAdd("fs".into(), vec!["npx".into(), "-y".into(), "server-fs".into()]))
+        );
+        assert!(matches!(Command::parse("mcp add local"), Err(CommandError::InvalidSyntax(_))));
+        assert_eq!(Command::parse("mcp remove fs"), Ok(Command::McpRemove("fs".into())));
+        assert_eq!(Command::parse("mcp rm fs"), Ok(Command::McpRemove("fs".into())));
+        assert_eq!(Command::parse("mcp edit fs"), Ok(Command::McpEdit("fs".into(), vec![])));
+        assert_eq!(
+            Command::parse("mcp edit fs http://x/sse"),
+            Ok(Command::McpEdit("fs".into(), vec!["http://x/sse".into()]))
+        );
+    }
+
+    #[test]
+    fn test_inspector_commands() {
+        assert_eq!(
+            Command::parse("inspector"),
+            Ok(Command::Inspector(InspectorAction::Toggle))
+        );
+        assert_eq!(
+            Command::parse("inspector filter tools/call"),
+            Ok(Command::Inspector(InspectorAction::Filter(Some("tools/call".into()))))
+        );
+        assert_eq!(
+            Command::parse("inspector filter"),
+            Ok(Command::Inspector(InspectorAction::Filter(None)))
+        );
+        assert_eq!(
+            Command::parse("inspector clear"),
+            Ok(Command::Inspector(InspectorAction::Clear))
+        );
+    }
+
+    #[test]
+    fn test_rpc_command() {
+        assert_eq!(
+            Command::parse("rpc tools/list"),
+            Ok(Command::Rpc { method: "tools/list".into(), params: None })
+        );
+        assert_eq!(
+            Command::parse("rpc resources/read {\"uri\": \"file:///a  b\"}"),
+            Ok(Command::Rpc {
+                method: "resources/read".into(),
+                params: Some("{\"uri\": \"file:///a  b\"}".into()),
+            })
+        );
+        assert!(matches!(Command::parse("rpc"), Err(CommandError::InvalidSyntax(_))));
+    }
+
+    #[test]
+    fn test_rpc_json_object() {
+        assert_eq!(
+            Command::parse_rpc(r#"{"jsonrpc":"2.0","id":9,"method":"ping"}"#),
+            Ok(Command::Rpc { method: "ping".into(), params: None })
+        );
+        assert_eq!(
+            Command::parse_rpc(r#"{"method":"prompts/get","params":{"name":"x"}}"#),
+            Ok(Command::Rpc { method: "prompts/get".into(), params: Some(r#"{"name":"x"}"#.into()) })
+        );
+        assert!(Command::parse_rpc(r#"{"params":{}}"#).is_err());
+        assert!(Command::parse_rpc("{oops").is_err());
+    }
+
+    #[test]
+    fn test_record_commands() {
+        assert_eq!(
+            Command::parse("record start session.jsonl"),
+            Ok(Command::Record(RecordAction::Start("session.jsonl".into())))
+        );
+        assert_eq!(Command::parse("record stop"), Ok(Command::Record(RecordAction::Stop)));
+        assert_eq!(Command::parse("record"), Ok(Command::Record(RecordAction::Status)));
+        assert!(matches!(Command::parse("record start"), Err(CommandError::InvalidSyntax(_))));
+        assert_eq!(
+            Command::parse("replay bug-42.jsonl"),
+            Ok(Command::Replay("bug-42.jsonl".into()))
+        );
+    }
+
+    #[test]
+    fn test_profile_command() {
+        assert_eq!(Command::parse("profile"), Ok(Command::Profile(None)));
+        assert_eq!(Command::parse("profile prod"), Ok(Command::Profile(Some("prod".into()))));
+    }
+
+    #[test]
+    fn test_output_commands() {
+        assert_eq!(
+            Command::parse("output filter errors,result"),
+            Ok(Command::Output(OutputAction::Filter(vec![EntryKind::Error, EntryKind::Result])))
+        );
+        assert_eq!(Command::parse("output filter"), Ok(Command::Output(OutputAction::Filter(vec![]))));
+        assert_eq!(Command::parse("output group"), Ok(Command::Output(OutputAction::Group)));
+        assert_eq!(
+            Command::parse("output export out.jsonl"),
+            Ok(Command::Output(OutputAction::Export("out.jsonl".into())))
+        );
+        assert!(matches!(Command::parse("output filter bogus"), Err(CommandError::InvalidSyntax(_))));
+    }
+
+    #[test]
+    fn test_result_command() {
+        assert_eq!(Command::parse("result"), Ok(Command::Result(None)));
+        assert_eq!(Command::parse("result 12"), Ok(Command::Result(Some(12))));
+        assert!(matches!(Command::parse("result last"), Err(CommandError::InvalidSyntax(_))));
+        assert_eq!(Command::parse("tree #3"), Ok(Command::Tree(Some(3))));
+        assert_eq!(Command::parse("tree"), Ok(Command::Tree(None)));
+    }
+
+    #[test]
+    fn test_query_command() {
+        assert_eq!(
+            Command::parse(r#"query 3 .components[] | select(.ref|startswith("U"))"#),
+            Ok(Command::Query { result: Some(3), expr: r#".components[] | select(.ref|startswith("U"))"#.into() })
+        );
+        assert_eq!(
+            Command::parse("query  $..ref"),
+            Ok(Command::Query { result: None, expr: "$..ref".into() })
+        );
+        assert!(matches!(Command::parse("query 3"), Err(CommandError::InvalidSyntax(_))));
+        assert!(matches!(Command::parse("query"), Err(CommandError::InvalidSyntax(_))));
+    }
+
+    #[test]
+    fn test_highlight_command() {
+        assert_eq!(Command::parse("highlight"), Ok(Command::Highlight(HighlightAction::Toggle)));
+        assert_eq!(Command::parse("highlight off"), Ok(Command::Highlight(HighlightAction::Off)));
+        assert_eq!(Command::parse("highlight light"), Ok(Command::Highlight(HighlightAction::Theme(Theme::Light))));
+        assert!(matches!(Command::parse("highlight neon"), Err(CommandError::InvalidSyntax(_))));
     }
 
     #[test]
@@ -255,6 +255,126 @@ impl CompletionContext {
             ],
         });
 
+        commands.insert("inspector".to_string(), CommandTemplate {
+            name: "inspector".to_string(),
+            description: "Toggle JSON-RPC traffic inspector".to_string(),
+            args: vec![
+                ArgTemplate {
+                    name: "action".to_string(),
+                    required: false,
+                    completion_list: Some("inspector_actions".to_string()),
+                }
+            ],
+        });
+
+        commands.insert("output".to_string(), CommandTemplate {
+            name: "output".to_string(),
+            description: "Filter, group or export the output".to_string(),
+            args: vec![
+                ArgTemplate {
+                    name: "action".to_string(),
+                    required: true,
+                    completion_list: Some("output_actions".to_string()),
+                }
+            ],
+        });
+
+        commands.insert("result".to_string(), CommandTemplate {
+            name: "result".to_string(),
+            description: "Open a complete result in the pager".to_string(),
+            args: vec![
+                ArgTemplate {
+                    name: "n".to_string(),
+                    required: false,
+                    completion_list: None,
+                }
+            ],
+        });
+
+        commands.insert("tree".to_string(), CommandTemplate {
+            name: "tree".to_string(),
+            description: "Browse a JSON result as a foldable tree".to_string(),
+            args: vec![
+                ArgTemplate {
+                    name: "n".to_string(),
+                    required: false,
+                    completion_list: None,
+                }
+            ],
+        });
+
+        commands.insert("query".to_string(), CommandTemplate {
+            name: "query".to_string(),
+            description: "Select parts of a result with a jq or JSONPath expression".to_string(),
+            args: vec![
+                ArgTemplate {
+                    name: "expr".to_string(),
+                    required: true,
+                    completion_list: None,
+                }
+            ],
+        });
+
+        commands.insert("highlight".to_string(), CommandTemplate {
+            name: "highlight".to_string(),
+            description: "Toggle syntax highlighting or pick its theme".to_string(),
+            args: vec![
+                ArgTemplate {
+                    name: "mode".to_string(),
+                    required: false,
+                    completion_list: Some("highlight_modes".to_string()),
+                }
+            ],
+        });
+
+        commands.insert("rpc".to_string(), CommandTemplate {
+            name: "rpc".to_string(),
+            description: "Send a raw JSON-RPC request".to_string(),
+            args: vec![
+                ArgTemplate {
+                    name: "method".to_string(),
+                    required: true,
+                    completion_list: Some("rpc_methods".to_string()),
+                }
+            ],
+        });
+
+        commands.insert("record".to_string(), CommandTemplate {
+            name: "record".to_string(),
+            description: "Record session to a JSONL file".to_string(),
+            args: vec![
+                ArgTemplate {
+                    name: "action".to_string(),
+                    required: false,
+                    completion_list: Some("record_actions".to_string()),
+                }
+            ],
+        });
+
+        commands.insert("replay".to_string(), CommandTemplate {
+            name: "replay".to_string(),
+            description: "Replay a recorded session".to_string(),
+            args: vec![
+                ArgTemplate {
+                    name: "file".to_string(),
+                    required: true,
+                    completion_list: None,
+                }
+            ],
+        });
+
+        commands.insert("profile".to_string(), CommandTemplate {
+            name: "profile".to_string(),
+            description: "Show or switch the config profile".to_string(),
+            args: vec![
+                ArgTemplate {
+                    name: "name".to_string(),
+                    required: false,
+                    completion_list: Some("profiles".to_string()),
+                }
+            ],
+        });
+
         let mut lists = HashMap::new();
         
         // Static completion lists
@@ -263,14 +383,56 @@ impl CompletionContext {
             "off".to_string(),
         ]);
         
+        lists.insert("rpc_methods".to_string(), vec![
+            "ping".to_string(),
+            "tools/list".to_string(),
+            "tools/call".to_string(),
+            "resources/list".to_string(),
+            "resources/templates/list".to_string(),
+            "resources/read".to_string(),
+            "prompts/list".to_string(),
+            "prompts/get".to_string(),
+            "completion/complete".to_string(),
+            "logging/setLevel".to_string(),
+        ]);
+
+        lists.insert("record_actions".to_string(), vec![
+            "start".to_string(),
+            "stop".to_string(),
+        ]);
+
+        lists.insert("inspector_actions".to_string(), vec![
+            "filter".to_string(),
+            "clear".to_string(),
+        ]);
+
+        lists.insert("output_actions".to_string(), vec![
+            "filter".to_string(),
+            "group".to_string(),
+            "export".to_string(),
+        ]);
+
+        lists.insert("highlight_modes".to_string(), vec![
+            "on".to_string(),
+            "off".to_string(),
+            "dark".to_string(),
+            "light".to_string(),
+        ]);
+
         lists.insert("mcp_subcommands".to_string(), vec![
             "list".to_string(),
             "connect".to_string(),
             "cn".to_string(),
+            "reconnect".to_string(),
             "tools".to_string(),
             "tool".to_string(),
             "run".to_string(),
             "status".to_string(),
+            "pending".to_string(),
+            "logout".to_string(),
+            "add".to_string(),
+            "remove".to_string(),
+            "edit".to_string(),
         ]);
 
         Self {
@@ -416,10 +578,10 @@ impl CompletionContext {
             }
             
             // After "mcp connect" or "mcp cn" - server name
-            ["connect"] | ["cn"] if ends_with_space => {
+            ["connect" | "cn" | "remove" | "rm" | "edit"] if ends_with_space => {
                 self.complete_from_list("mcp_servers", "")
             }
-            ["connect", prefix] | ["cn", prefix] if !ends_with_space => {
+            ["connect" | "cn" | "remove" | "rm" | "edit", prefix] if !ends_with_space => {
                 self.complete_from_list("mcp_servers", prefix)
             }
             
@@ -603,6 +765,9 @@ mod tests {
         let result = ctx.complete("mcp connect ");
         assert_eq!(result.len(), 2);
         assert!(result.candidates.iter().any(|c| c.text == "server1"));
+
+        assert_eq!(ctx.complete("mcp remove ").len(), 2);
+        assert_eq!(ctx.complete("mcp edit server2").len(), 1);
     }
 
     #[test]
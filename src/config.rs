@@ -1,21 +1,1257 @@
-use serde::Deserialize;
-use std::fs;
+use crate::highlight::Theme;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
 
-#[derive(Debug, Deserialize, Clone)]
+/// Project-local config names, searched in the working directory and its parents
+pub const PROJECT_CONFIGS: [&str; 3] = [".mcp-client.toml", ".mcp-client.json5", ".mcp-client.json"];
+/// Legacy location: `config.json` in the working directory
+pub const LEGACY_CONFIG: &str = "config.json";
+/// Config names under `$XDG_CONFIG_HOME/mcp-client`
+pub const USER_CONFIGS: [&str; 3] = ["config.toml", "config.json5", "config.json"];
+/// How long a request may wait for its response unless `request_timeout` says otherwise
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+const TOP_LEVEL_KEYS: [&str; 6] = ["mcp_servers", "mcpServers", "profiles", "request_timeout", "output_lines", "highlight"];
+const SERVER_KEYS: [&str; 8] = ["name", "url", "command", "args", "env", "headers", "tls", "request_timeout"];
+const PROFILE_KEYS: [&str; 4] = ["enabled", "headers", "servers", "production"];
+const OVERRIDE_KEYS: [&str; 2] = ["url", "headers"];
+const TLS_KEYS: [&str; 4] = ["ca_files", "client_cert", "client_key", "danger_accept_invalid_certs"];
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
 pub struct McpServerConfig {
+    #[serde(default)]
     pub name: String,
+    /// SSE endpoint; empty for stdio servers
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub url: String,
+    /// Local stdio server (as imported from `mcpServers`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Extra HTTP headers sent with every request to this server
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+    /// Seconds before a request to this server times out (overrides the default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout: Option<u64>,
+}
+
+impl McpServerConfig {
+    /// Pure function: server → what it connects to, for listings
+    pub fn endpoint(&self) -> String {
+        match &self.command {
+            Some(command) if self.url.is_empty() => {
+                let mut line = format!("stdio: {}", command);
+                for arg in &self.args {
+                    line.push(' ');
+                    line.push_str(arg);
+                }
+                line
+            }
+            _ => self.url.clone(),
+        }
+    }
+
+    /// Pure function: `:mcp add` words → server. An `http(s)://` first word
+    /// is the URL, anything else a command followed by its arguments.
+    pub fn from_endpoint(name: &str, words: &[String]) -> Self {
+        Self { name: name.to_string(), ..Self::default() }.with_endpoint(words)
+    }
+
+    /// Pure function: replaces url/command/args, keeping env and TLS settings
+    pub fn with_endpoint(mut self, words: &[String]) -> Self {
+        match words.split_first() {
+            Some((url, [])) if url.contains("://") => {
+                self.url = url.clone();
+                self.command = None;
+                self.args = Vec::new();
+            }
+            Some((command, args)) => {
+                self.url = String::new();
+                self.command = Some(command.clone());
+                self.args = args.to_vec();
+            }
+            None => {}
+        }
+        self
+    }
+}
+
+/// Per-server TLS settings
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct TlsConfig {
+    /// Extra PEM files with CA certificates trusted in addition to the system roots
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ca_files: Vec<PathBuf>,
+    /// PEM client certificate (chain) for mutual TLS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    /// PKCS#8 PEM private key matching `client_cert`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+    /// DANGER: disables certificate verification entirely
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub danger_accept_invalid_certs: bool,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Named environment (dev, staging, prod, ...) layered over `mcp_servers`
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct Profile {
+    /// Servers available in this profile (all when absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<Vec<String>>,
+    /// Headers added to every server's requests
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Per-server URL and header overrides, keyed by server name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub servers: BTreeMap<String, ServerOverride>,
+    /// Destructive tools need a confirmation before they run
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub production: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct ServerOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Profile {
+    pub fn enables(&self, server: &str) -> bool {
+        self.enabled.as_ref().is_none_or(|names| names.iter().any(|n| n == server))
+    }
+
+    /// Pure function: base server → server as seen in this profile.
+    /// Headers merge in order server < profile < per-server override.
+    pub fn apply(&self, server: &McpServerConfig) -> McpServerConfig {
+        let mut server = server.clone();
+        server.headers.extend(self.headers.clone());
+        if let Some(over) = self.servers.get(&server.name) {
+            if let Some(url) = &over.url {
+                server.url = url.clone();
+                server.command = None;
+                server.args = Vec::new();
+            }
+            server.headers.extend(over.headers.clone());
+        }
+        server
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Config {
+    /// Servers as written in the file; see [`Config::servers`] for the
+    /// list with the active profile applied
     pub mcp_servers: Vec<McpServerConfig>,
+    pub profiles: BTreeMap<String, Profile>,
+    /// Selected with `--profile` or `:profile`
+    pub active_profile: Option<String>,
+    /// Default request timeout in seconds (see [`DEFAULT_REQUEST_TIMEOUT`])
+    pub request_timeout: Option<u64>,
+    /// Most lines kept in the output log (default `state::MAX_LOG_LINES`)
+    pub output_lines: Option<usize>,
+    /// Syntax highlighting of results (default on)
+    pub highlight: Option<bool>,
+    /// Highlight colors; None picks them from the terminal
+    pub highlight_theme: Option<Theme>,
+    /// Non-fatal findings from loading (unknown keys)
+    pub warnings: Vec<String>,
+    /// File this config belongs to; `:mcp add/remove/edit` save back here
+    pub path: Option<PathBuf>,
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// ERRORS
+// ═══════════════════════════════════════════════════════════════════
+
+/// One validation problem, located by its path in the document
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("cannot read {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("{path}: syntax error: {reason}")]
+    Syntax { path: PathBuf, reason: String },
+    #[error("{path}: invalid config:{}", .errors.iter().map(|e| format!("\n  {}", e)).collect::<String>())]
+    Invalid { path: PathBuf, errors: Vec<FieldError> },
+    #[error("cannot write {path}: {source}")]
+    Write { path: PathBuf, source: io::Error },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Json5,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Pure function: path → format by extension (JSON by default)
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("toml") => ConfigFormat::Toml,
+            Some("json5") => ConfigFormat::Json5,
+            _ => ConfigFormat::Json,
+        }
+    }
 }
 
+// ═══════════════════════════════════════════════════════════════════
+// LOADING AND VALIDATION
+// ═══════════════════════════════════════════════════════════════════
+
 impl Config {
-    pub fn from_file(path: &str) -> Result<Self, anyhow::Error> {
-        let content = fs::read_to_string(path)?;
-        let config: Config = serde_json::from_str(&content)?;
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|source| ConfigError::Read { path: path.to_path_buf(), source })?;
+        let mut config = Self::parse(&content, path)?;
+        config.path = Some(path.to_path_buf());
         Ok(config)
     }
+
+    /// Like `from_file`, but a missing file gives an empty config that
+    /// will be created on the first save
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        if path.exists() {
+            Self::from_file(path)
+        } else {
+            Ok(Self { path: Some(path.to_path_buf()), ..Self::default() })
+        }
+    }
+
+    /// Parses and validates config text; the format follows the path's
+    /// extension, which is also used in error messages
+    pub fn parse(text: &str, path: &Path) -> Result<Self, ConfigError> {
+        Self::from_value(decode(text, path)?)
+            .map_err(|errors| ConfigError::Invalid { path: path.to_path_buf(), errors })
+    }
+
+    /// Validates a decoded document. Servers come from `mcp_servers` (a list)
+    /// and/or `mcpServers` (Claude Desktop style map keyed by name).
+    pub fn from_value(value: Value) -> Result<Self, Vec<FieldError>> {
+        let Value::Object(root) = value else {
+            return Err(vec![FieldError::new("(root)", "expected a table of settings")]);
+        };
+
+        let mut errors = Vec::new();
+        let mut warnings = unknown_keys("", &root, &TOP_LEVEL_KEYS);
+        let mut entries: Vec<(String, Value)> = Vec::new();
+
+        match root.get("mcp_servers") {
+            None => {}
+            Some(Value::Array(items)) => entries.extend(
+                items.iter().enumerate().map(|(i, v)| (format!("mcp_servers[{}]", i), v.clone())),
+            ),
+            Some(_) => errors.push(FieldError::new("mcp_servers", "expected a list of servers")),
+        }
+        match root.get("mcpServers") {
+            None => {}
+            Some(Value::Object(map)) => {
+                for (name, server) in map {
+                    let mut server = server.clone();
+                    if let Value::Object(obj) = &mut server {
+                        obj.entry("name").or_insert_with(|| Value::String(name.clone()));
+                    }
+                    entries.push((format!("mcpServers.{}", name), server));
+                }
+            }
+            Some(_) => errors.push(FieldError::new("mcpServers", "expected a map of servers")),
+        }
+
+        // Profiles are checked against every declared name, valid or not,
+        // so one broken server does not cascade into profile errors
+        let declared: Vec<String> = entries
+            .iter()
+            .filter_map(|(_, entry)| Some(entry.get("name")?.as_str()?.trim().to_string()))
+            .collect();
+
+        let mut servers = Vec::new();
+        let mut seen: HashMap<String, String> = HashMap::new();
+        for (path, entry) in entries {
+            let Some(server) = validate_server(&path, entry, &mut errors, &mut warnings) else {
+                continue;
+            };
+            if let Some(first) = seen.get(&server.name) {
+                errors.push(FieldError::new(
+                    format!("{}.name", path),
+                    format!("duplicate server name \"{}\" (first defined at {})", server.name, first),
+                ));
+                continue;
+            }
+            seen.insert(server.name.clone(), path);
+            servers.push(server);
+        }
+
+        let mut profiles = BTreeMap::new();
+        match root.get("profiles") {
+            None => {}
+            Some(Value::Object(map)) => {
+                let names: Vec<&str> = declared.iter().map(String::as_str).collect();
+                for (name, entry) in map {
+                    let path = format!("profiles.{}", name);
+                    if let Some(profile) = validate_profile(&path, entry, &names, &mut errors, &mut warnings) {
+                        profiles.insert(name.clone(), profile);
+                    }
+                }
+            }
+            Some(_) => errors.push(FieldError::new("profiles", "expected a table of profiles")),
+        }
+
+        let request_timeout = root.get("request_timeout").and_then(|v| check_timeout("request_timeout", v, &mut errors));
+        let output_lines = root.get("output_lines").and_then(|v| match v.as_u64() {
+            Some(lines) if lines > 0 => Some(lines as usize),
+            _ => {
+                errors.push(FieldError::new("output_lines", "expected a positive number of lines"));
+                None
+            }
+        });
+        let (highlight, highlight_theme) = match root.get("highlight") {
+            None => (None, None),
+            Some(Value::Bool(enabled)) => (Some(*enabled), None),
+            Some(Value::String(s)) if s == "auto" => (Some(true), None),
+            Some(Value::String(s)) if s == "off" => (Some(false), None),
+            Some(value) => match value.as_str().and_then(Theme::parse) {
+                Some(theme) => (Some(true), Some(theme)),
+                None => {
+                    errors.push(FieldError::new("highlight", "expected true, false, \"auto\", \"dark\", \"light\" or \"off\""));
+                    (None, None)
+                }
+            },
+        };
+
+        if errors.is_empty() {
+            Ok(Config {
+                mcp_servers: servers,
+                profiles,
+                active_profile: None,
+                request_timeout,
+                output_lines,
+                highlight,
+                highlight_theme,
+                warnings,
+                path: None,
+            })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Pure function: config × new server list → validated config with the
+    /// same profiles, path and warnings (duplicate names, bad URLs, servers
+    /// still named by a profile, ... are rejected)
+    pub fn with_servers(&self, servers: Vec<McpServerConfig>) -> Result<Self, Vec<FieldError>> {
+        let value = serde_json::json!({ "mcp_servers": servers, "profiles": self.profiles });
+        let config = Self::from_value(value)?;
+        Ok(Self {
+            active_profile: self.active_profile.clone(),
+            request_timeout: self.request_timeout,
+            output_lines: self.output_lines,
+            highlight: self.highlight,
+            highlight_theme: self.highlight_theme,
+            warnings: self.warnings.clone(),
+            path: self.path.clone(),
+            ..config
+        })
+    }
+
+    /// Pure function: selects a profile (None: plain `mcp_servers`)
+    pub fn with_profile(mut self, name: Option<&str>) -> Result<Self, String> {
+        if let Some(name) = name {
+            if !self.profiles.contains_key(name) {
+                let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                return Err(if known.is_empty() {
+                    format!("unknown profile '{}' (no profiles configured)", name)
+                } else {
+                    format!("unknown profile '{}' (available: {})", name, known.join(", "))
+                });
+            }
+        }
+        self.active_profile = name.map(str::to_string);
+        Ok(self)
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.active_profile.as_ref().and_then(|name| self.profiles.get(name))
+    }
+
+    /// Whether destructive tools need confirmation
+    pub fn is_production(&self) -> bool {
+        self.profile().is_some_and(|p| p.production)
+    }
+
+    /// Servers with the active profile applied: disabled ones left out,
+    /// URLs and headers overridden
+    pub fn servers(&self) -> Vec<McpServerConfig> {
+        match self.profile() {
+            None => self.mcp_servers.clone(),
+            Some(profile) => self
+                .mcp_servers
+                .iter()
+                .filter(|s| profile.enables(&s.name))
+                .map(|s| profile.apply(s))
+                .collect(),
+        }
+    }
+
+    /// A server with the active profile applied
+    pub fn server(&self, name: &str) -> Option<McpServerConfig> {
+        self.servers().into_iter().find(|s| s.name == name)
+    }
+
+    pub fn server_names(&self) -> Vec<String> {
+        self.servers().into_iter().map(|s| s.name).collect()
+    }
+
+    /// How long requests to `server` may take: the server's own
+    /// `request_timeout`, else the top-level one, else the default
+    pub fn request_timeout(&self, server: Option<&str>) -> Duration {
+        server
+            .and_then(|name| self.mcp_servers.iter().find(|s| s.name == name))
+            .and_then(|s| s.request_timeout)
+            .or(self.request_timeout)
+            .map_or(DEFAULT_REQUEST_TIMEOUT, Duration::from_secs)
+    }
+}
+
+/// Server names that differ between two configs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ServerDiff {
+    /// Pure function: old × new → added, removed and changed servers
+    /// Servers are compared with each config's active profile applied.
+    pub fn between(old: &Config, new: &Config) -> Self {
+        let (old, new) = (old.servers(), new.servers());
+        let find = |list: &[McpServerConfig], name: &str| list.iter().position(|s| s.name == name);
+        let mut diff = Self::default();
+        for server in &new {
+            match find(&old, &server.name) {
+                None => diff.added.push(server.name.clone()),
+                Some(i) if old[i] != *server => diff.changed.push(server.name.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = old
+            .iter()
+            .filter(|s| find(&new, &s.name).is_none())
+            .map(|s| s.name.clone())
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// `+a, -b, ~c` for status lines
+    pub fn summary(&self) -> String {
+        let added = self.added.iter().map(|n| format!("+{}", n));
+        let removed = self.removed.iter().map(|n| format!("-{}", n));
+        let changed = self.changed.iter().map(|n| format!("~{}", n));
+        added.chain(removed).chain(changed).collect::<Vec<_>>().join(", ")
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// SAVING
+// ═══════════════════════════════════════════════════════════════════
+
+impl Config {
+    /// Writes the server list to `path`, atomically (temp file + rename).
+    /// Other top-level keys already in the file are kept; a file that only
+    /// uses `mcpServers` keeps that shape. Comments are not preserved.
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        let mut root = match fs::read_to_string(path) {
+            Ok(text) => match decode(&text, path)? {
+                Value::Object(root) => root,
+                _ => Map::new(),
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Map::new(),
+            Err(source) => return Err(ConfigError::Read { path: path.to_path_buf(), source }),
+        };
+
+        let servers = self.mcp_servers.iter().map(|s| serde_json::to_value(s).unwrap_or_default());
+        if root.contains_key("mcpServers") && !root.contains_key("mcp_servers") {
+            let map: Map<String, Value> = self
+                .mcp_servers
+                .iter()
+                .zip(servers)
+                .map(|(server, mut value)| {
+                    if let Value::Object(obj) = &mut value {
+                        obj.remove("name");
+                    }
+                    (server.name.clone(), value)
+                })
+                .collect();
+            root.insert("mcpServers".into(), Value::Object(map));
+        } else {
+            root.remove("mcpServers");
+            root.insert("mcp_servers".into(), Value::Array(servers.collect()));
+        }
+
+        let write_error = |source: io::Error| ConfigError::Write { path: path.to_path_buf(), source };
+        let text = encode(&Value::Object(root), ConfigFormat::from_path(path)).map_err(write_error)?;
+        write_atomic(path, &text).map_err(write_error)
+    }
+}
+
+/// Pure function: config text → document, in the format of `path`
+fn decode(text: &str, path: &Path) -> Result<Value, ConfigError> {
+    let syntax = |reason: String| ConfigError::Syntax { path: path.to_path_buf(), reason };
+    match ConfigFormat::from_path(path) {
+        ConfigFormat::Json => serde_json::from_str(text).map_err(|e| syntax(e.to_string())),
+        ConfigFormat::Json5 => json5::from_str(text).map_err(|e| syntax(e.to_string())),
+        ConfigFormat::Toml => {
+            let table: toml::Table = toml::from_str(text).map_err(|e| syntax(e.to_string()))?;
+            serde_json::to_value(table).map_err(|e| syntax(e.to_string()))
+        }
+    }
+}
+
+/// Pure function: document → text (JSON5 files are written as plain JSON)
+fn encode(value: &Value, format: ConfigFormat) -> io::Result<String> {
+    let text = match format {
+        ConfigFormat::Toml => toml::to_string_pretty(value).map_err(io::Error::other)?,
+        ConfigFormat::Json | ConfigFormat::Json5 => serde_json::to_string_pretty(value)?,
+    };
+    Ok(if text.ends_with('\n') { text } else { text + "\n" })
+}
+
+/// Writes a sibling temp file and renames it over `path`, so readers never
+/// see a half-written config. A symlinked config is replaced at its target
+/// and an existing file's permissions carry over (it may hold credentials
+/// in `headers`).
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let path = &fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let permissions = fs::metadata(path).ok().map(|meta| meta.permissions());
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let name = path.file_name().map_or_else(|| "config".into(), |n| n.to_string_lossy().into_owned());
+    let tmp = dir.join(format!(".{}.{}.tmp", name, std::process::id()));
+
+    let result = File::create(&tmp)
+        .and_then(|mut file| {
+            if let Some(permissions) = permissions {
+                file.set_permissions(permissions)?;
+            }
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Pure function: object × known keys → warnings for the rest
+fn unknown_keys(path: &str, obj: &serde_json::Map<String, Value>, known: &[&str]) -> Vec<String> {
+    obj.keys()
+        .filter(|k| !known.contains(&k.as_str()))
+        .map(|k| {
+            let field = if path.is_empty() { k.clone() } else { format!("{}.{}", path, k) };
+            format!("{}: unknown key (ignored)", field)
+        })
+        .collect()
+}
+
+fn validate_server(
+    path: &str,
+    entry: Value,
+    errors: &mut Vec<FieldError>,
+    warnings: &mut Vec<String>,
+) -> Option<McpServerConfig> {
+    let Value::Object(obj) = &entry else {
+        errors.push(FieldError::new(path, "expected a table with at least `name` and `url`"));
+        return None;
+    };
+    warnings.extend(unknown_keys(path, obj, &SERVER_KEYS));
+    if let Some(Value::Object(tls)) = obj.get("tls") {
+        warnings.extend(unknown_keys(&format!("{}.tls", path), tls, &TLS_KEYS));
+    }
+
+    // Type checks per field, so errors name the field rather than the entry
+    let before = errors.len();
+    let field = |key: &str| format!("{}.{}", path, key);
+    for key in ["name", "url", "command"] {
+        if obj.get(key).is_some_and(|v| !v.is_string()) {
+            errors.push(FieldError::new(field(key), "expected a string"));
+        }
+    }
+    if obj.get("args").is_some_and(|v| !v.as_array().is_some_and(|a| a.iter().all(Value::is_string))) {
+        errors.push(FieldError::new(field("args"), "expected a list of strings"));
+    }
+    if obj.get("env").is_some_and(|v| !v.as_object().is_some_and(|m| m.values().all(Value::is_string))) {
+        errors.push(FieldError::new(field("env"), "expected a map of strings"));
+    }
+    if let Some(headers) = obj.get("headers") {
+        check_headers(&field("headers"), headers, errors);
+    }
+    if let Some(timeout) = obj.get("request_timeout") {
+        check_timeout(&field("request_timeout"), timeout, errors);
+    }
+    if errors.len() > before {
+        return None;
+    }
+
+    let mut server: McpServerConfig = match serde_json::from_value(entry) {
+        Ok(server) => server,
+        Err(e) => {
+            errors.push(FieldError::new(path, e.to_string()));
+            return None;
+        }
+    };
+    server.name = server.name.trim().to_string();
+    server.url = server.url.trim().to_string();
+
+    if server.name.is_empty() {
+        errors.push(FieldError::new(field("name"), "must not be empty"));
+    }
+    match (server.url.is_empty(), &server.command) {
+        (true, None) => errors.push(FieldError::new(path, "either `url` or `command` is required")),
+        (false, Some(_)) => errors.push(FieldError::new(path, "`url` and `command` are mutually exclusive")),
+        (false, None) => check_url(&field("url"), &server.url, errors),
+        (true, Some(command)) if command.trim().is_empty() => {
+            errors.push(FieldError::new(field("command"), "must not be empty"));
+        }
+        (true, Some(_)) => {}
+    }
+
+    (errors.len() == before).then_some(server)
+}
+
+/// Timeouts are whole seconds, at least one
+fn check_timeout(field: &str, value: &Value, errors: &mut Vec<FieldError>) -> Option<u64> {
+    match value.as_u64() {
+        Some(secs) if secs > 0 => Some(secs),
+        _ => {
+            errors.push(FieldError::new(field, "expected a positive number of seconds"));
+            None
+        }
+    }
+}
+
+fn check_url(field: &str, url: &str, errors: &mut Vec<FieldError>) {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+        Ok(parsed) => errors.push(FieldError::new(
+            field,
+            format!("unsupported scheme \"{}\" (expected http or https)", parsed.scheme()),
+        )),
+        Err(e) => errors.push(FieldError::new(field, format!("invalid URL \"{}\": {}", url, e))),
+    }
+}
+
+/// Header maps must hold valid HTTP header names and values
+fn check_headers(field: &str, headers: &Value, errors: &mut Vec<FieldError>) {
+    let Some(map) = headers.as_object().filter(|m| m.values().all(Value::is_string)) else {
+        errors.push(FieldError::new(field, "expected a map of strings"));
+        return;
+    };
+    for (name, value) in map {
+        if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+            errors.push(FieldError::new(format!("{}.{}", field, name), "invalid header name"));
+        } else if reqwest::header::HeaderValue::from_str(value.as_str().unwrap_or_default()).is_err() {
+            errors.push(FieldError::new(format!("{}.{}", field, name), "invalid header value"));
+        }
+    }
+}
+
+fn validate_profile(
+    path: &str,
+    entry: &Value,
+    servers: &[&str],
+    errors: &mut Vec<FieldError>,
+    warnings: &mut Vec<String>,
+) -> Option<Profile> {
+    let Value::Object(obj) = entry else {
+        errors.push(FieldError::new(path, "expected a table"));
+        return None;
+    };
+    warnings.extend(unknown_keys(path, obj, &PROFILE_KEYS));
+
+    let before = errors.len();
+    let field = |key: &str| format!("{}.{}", path, key);
+    let unknown_server = |name: &str| format!("unknown server \"{}\"", name);
+    match obj.get("enabled") {
+        None => {}
+        Some(Value::Array(names)) => {
+            for (i, name) in names.iter().enumerate() {
+                match name.as_str() {
+                    Some(name) if servers.contains(&name) => {}
+                    Some(name) => errors.push(FieldError::new(format!("{}[{}]", field("enabled"), i), unknown_server(name))),
+                    None => errors.push(FieldError::new(format!("{}[{}]", field("enabled"), i), "expected a string")),
+                }
+            }
+        }
+        Some(_) => errors.push(FieldError::new(field("enabled"), "expected a list of server names")),
+    }
+    if let Some(headers) = obj.get("headers") {
+        check_headers(&field("headers"), headers, errors);
+    }
+    if obj.get("production").is_some_and(|v| !v.is_boolean()) {
+        errors.push(FieldError::new(field("production"), "expected true or false"));
+    }
+    match obj.get("servers") {
+        None => {}
+        Some(Value::Object(overrides)) => {
+            for (name, over) in overrides {
+                let path = format!("{}.{}", field("servers"), name);
+                if !servers.contains(&name.as_str()) {
+                    errors.push(FieldError::new(&path, unknown_server(name)));
+                    continue;
+                }
+                let Value::Object(over) = over else {
+                    errors.push(FieldError::new(&path, "expected a table with `url` and/or `headers`"));
+                    continue;
+                };
+                warnings.extend(unknown_keys(&path, over, &OVERRIDE_KEYS));
+                match over.get("url") {
+                    None => {}
+                    Some(Value::String(url)) => check_url(&format!("{}.url", path), url, errors),
+                    Some(_) => errors.push(FieldError::new(format!("{}.url", path), "expected a string")),
+                }
+                if let Some(headers) = over.get("headers") {
+                    check_headers(&format!("{}.headers", path), headers, errors);
+                }
+            }
+        }
+        Some(_) => errors.push(FieldError::new(field("servers"), "expected a table of server overrides")),
+    }
+    if errors.len() > before {
+        return None;
+    }
+
+    match serde_json::from_value(entry.clone()) {
+        Ok(profile) => Some(profile),
+        Err(e) => {
+            errors.push(FieldError::new(path, e.to_string()));
+            None
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/mcp-client`, falling back to `~/.config/mcp-client`
+pub fn user_config_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .unwrap_or_else(std::env::temp_dir);
+    base.join("mcp-client")
+}
+
+/// Pure function: working directory × user config dir → candidate files,
+/// highest priority first. A project-local file overrides the user one.
+pub fn config_candidates(cwd: &Path, user_dir: &Path) -> Vec<PathBuf> {
+    cwd.ancestors()
+        .flat_map(|dir| PROJECT_CONFIGS.iter().map(move |name| dir.join(name)))
+        .chain(std::iter::once(cwd.join(LEGACY_CONFIG)))
+        .chain(USER_CONFIGS.iter().map(|name| user_dir.join(name)))
+        .collect()
+}
+
+/// Where a new config is created when none exists: `config.toml` in the
+/// user config directory
+pub fn default_config_path() -> PathBuf {
+    user_config_dir().join(USER_CONFIGS[0])
+}
+
+/// Resolves the config file for the TUI: the explicit path (even if it does
+/// not exist yet), else the first existing candidate, else
+/// [`default_config_path`]
+pub fn resolve(explicit: Option<&Path>) -> Result<PathBuf, anyhow::Error> {
+    if let Some(path) = explicit {
+        return Ok(path.to_path_buf());
+    }
+    let cwd = std::env::current_dir()?;
+    Ok(config_candidates(&cwd, &user_config_dir())
+        .into_iter()
+        .find(|p| p.is_file())
+        .unwrap_or_else(default_config_path))
+}
+
+/// Resolves the config file: an explicit `--config` path must exist,
+/// otherwise the first existing candidate wins
+pub fn locate(explicit: Option<&Path>) -> Result<PathBuf, anyhow::Error> {
+    if let Some(path) = explicit {
+        if !path.is_file() {
+            anyhow::bail!("config file {} does not exist", path.display());
+        }
+        return Ok(path.to_path_buf());
+    }
+
+    let cwd = std::env::current_dir()?;
+    let candidates = config_candidates(&cwd, &user_config_dir());
+    candidates
+        .iter()
+        .find(|p| p.is_file())
+        .cloned()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "no config file found (tried .mcp-client.{{toml,json5,json}}, {} and {}/config.{{toml,json5,json}})",
+                LEGACY_CONFIG,
+                user_config_dir().display()
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn field_errors(value: Value) -> Vec<String> {
+        Config::from_value(value).unwrap_err().iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_candidate_order() {
+        let candidates = config_candidates(Path::new("/work/sub"), Path::new("/home/u/.config/mcp-client"));
+        let names: Vec<String> = candidates.iter().map(|p| p.display().to_string()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "/work/sub/.mcp-client.toml",
+                "/work/sub/.mcp-client.json5",
+                "/work/sub/.mcp-client.json",
+                "/work/.mcp-client.toml",
+                "/work/.mcp-client.json5",
+                "/work/.mcp-client.json",
+                "/.mcp-client.toml",
+                "/.mcp-client.json5",
+                "/.mcp-client.json",
+                "/work/sub/config.json",
+                "/home/u/.config/mcp-client/config.toml",
+                "/home/u/.config/mcp-client/config.json5",
+                "/home/u/.config/mcp-client/config.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explicit_path_must_exist() {
+        let missing = std::env::temp_dir().join(format!("mcp-client-{}.json", uuid::Uuid::new_v4()));
+        assert!(locate(Some(&missing)).is_err());
+
+        fs::write(&missing, r#"{"mcp_servers": []}"#).unwrap();
+        assert_eq!(locate(Some(&missing)).unwrap(), missing);
+        assert!(Config::from_file(&missing).unwrap().mcp_servers.is_empty());
+        fs::remove_file(&missing).unwrap();
+    }
+
+    #[test]
+    fn test_url_is_trimmed() {
+        let config = Config::from_value(json!({
+            "mcp_servers": [{"name": " pcb ", "url": "http://localhost:8080/sse "}]
+        }))
+        .unwrap();
+        assert_eq!(config.mcp_servers[0].name, "pcb");
+        assert_eq!(config.mcp_servers[0].url, "http://localhost:8080/sse");
+    }
+
+    #[test]
+    fn test_toml_and_json5() {
+        let toml = r#"
+            [[mcp_servers]]
+            name = "local"
+            url = "http://localhost:8080/sse"
+
+            [mcp_servers.tls]
+            danger_accept_invalid_certs = true
+        "#;
+        let config = Config::parse(toml, Path::new("c.toml")).unwrap();
+        assert!(config.mcp_servers[0].tls.as_ref().unwrap().danger_accept_invalid_certs);
+
+        let json5 = r#"{
+            // comments and trailing commas are fine
+            mcp_servers: [{ name: 'local', url: 'http://localhost:8080/sse', },],
+        }"#;
+        let config = Config::parse(json5, Path::new("c.json5")).unwrap();
+        assert_eq!(config.mcp_servers[0].name, "local");
+
+        let err = Config::parse("mcp_servers = [", Path::new("c.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::Syntax { .. }));
+    }
+
+    #[test]
+    fn test_claude_desktop_import() {
+        let config = Config::from_value(json!({
+            "mcpServers": {
+                "filesystem": {
+                    "command": "npx",
+                    "args": ["-y", "@modelcontextprotocol/server-filesystem", "/tmp"],
+                    "env": {"DEBUG": "1"}
+                },
+                "remote": {"url": "https://mcp.example.com/sse"}
+            }
+        }))
+        .unwrap();
+        let fs_server = config.mcp_servers.iter().find(|s| s.name == "filesystem").unwrap();
+        assert_eq!(fs_server.command.as_deref(), Some("npx"));
+        assert_eq!(fs_server.env["DEBUG"], "1");
+        assert!(fs_server.endpoint().starts_with("stdio: npx -y"));
+        let remote = config.mcp_servers.iter().find(|s| s.name == "remote").unwrap();
+        assert_eq!(remote.endpoint(), "https://mcp.example.com/sse");
+    }
+
+    #[test]
+    fn test_unknown_keys_warn() {
+        let config = Config::from_value(json!({
+            "theme": "dark",
+            "mcp_servers": [{"name": "a", "url": "http://a/sse", "tsl": {}, "tls": {"ca_file": "x"}}]
+        }))
+        .unwrap();
+        assert_eq!(
+            config.warnings,
+            vec![
+                "theme: unknown key (ignored)",
+                "mcp_servers[0].tsl: unknown key (ignored)",
+                "mcp_servers[0].tls.ca_file: unknown key (ignored)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_names_fail() {
+        let errors = field_errors(json!({
+            "mcp_servers": [{"name": "a", "url": "http://a/sse"}],
+            "mcpServers": {"a": {"url": "http://b/sse"}}
+        }));
+        assert_eq!(
+            errors,
+            vec!["mcpServers.a.name: duplicate server name \"a\" (first defined at mcp_servers[0])"]
+        );
+    }
+
+    #[test]
+    fn test_field_level_errors() {
+        let errors = field_errors(json!({
+            "mcp_servers": [
+                {"name": 5, "url": "http://a/sse"},
+                {"name": "b", "url": "ftp://b"},
+                {"name": "c", "url": "not a url"},
+                {"name": "d"},
+                {"name": "e", "url": "http://e", "command": "x"},
+                {"name": "", "url": "http://f"},
+                {"name": "g", "command": "x", "args": [1]}
+            ]
+        }));
+        assert_eq!(errors.len(), 7);
+        assert_eq!(errors[0], "mcp_servers[0].name: expected a string");
+        assert!(errors[1].starts_with("mcp_servers[1].url: unsupported scheme \"ftp\""));
+        assert!(errors[2].starts_with("mcp_servers[2].url: invalid URL"));
+        assert_eq!(errors[3], "mcp_servers[3]: either `url` or `command` is required");
+        assert_eq!(errors[4], "mcp_servers[4]: `url` and `command` are mutually exclusive");
+        assert_eq!(errors[5], "mcp_servers[5].name: must not be empty");
+        assert_eq!(errors[6], "mcp_servers[6].args: expected a list of strings");
+    }
+
+    #[test]
+    fn test_invalid_error_lists_every_field() {
+        let err = Config::parse(r#"{"mcp_servers": [{"name": "a"}, {"url": 1}]}"#, Path::new("c.json")).unwrap_err();
+        let message = err.to_string();
+        assert!(message.starts_with("c.json: invalid config:"));
+        assert!(message.contains("\n  mcp_servers[0]: either"));
+        assert!(message.contains("\n  mcp_servers[1].url: expected a string"));
+    }
+
+    fn temp_path(ext: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mcp-client-{}.{}", uuid::Uuid::new_v4(), ext))
+    }
+
+    fn words(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_from_endpoint() {
+        let remote = McpServerConfig::from_endpoint("r", &words("https://mcp.example.com/sse"));
+        assert_eq!(remote.url, "https://mcp.example.com/sse");
+        assert_eq!(remote.command, None);
+
+        let local = McpServerConfig::from_endpoint("l", &words("npx -y server-fs /tmp"));
+        assert_eq!(local.command.as_deref(), Some("npx"));
+        assert_eq!(local.args, words("-y server-fs /tmp"));
+
+        // Editing keeps env/TLS but switches the transport
+        let edited = McpServerConfig { env: BTreeMap::from([("A".into(), "1".into())]), ..local }
+            .with_endpoint(&words("http://localhost:8080/sse"));
+        assert_eq!(edited.endpoint(), "http://localhost:8080/sse");
+        assert!(edited.args.is_empty());
+        assert_eq!(edited.env["A"], "1");
+    }
+
+    #[test]
+    fn test_with_servers_validates() {
+        let config = Config { path: Some(PathBuf::from("c.toml")), ..Config::default() };
+        let a = McpServerConfig::from_endpoint("a", &words("http://a/sse"));
+
+        let config = config.with_servers(vec![a.clone()]).unwrap();
+        assert_eq!(config.server_names(), vec!["a"]);
+        assert_eq!(config.path, Some(PathBuf::from("c.toml")));
+
+        let errors = config.with_servers(vec![a.clone(), a]).unwrap_err();
+        assert!(errors[0].message.starts_with("duplicate server name \"a\""));
+        let bad = McpServerConfig::from_endpoint("b", &words("ftp://b"));
+        assert!(config.with_servers(vec![bad]).is_err());
+    }
+
+    #[test]
+    fn test_load_missing_file_is_empty() {
+        let path = temp_path("toml");
+        let config = Config::load(&path).unwrap();
+        assert!(config.mcp_servers.is_empty());
+        assert_eq!(config.path, Some(path));
+    }
+
+    #[test]
+    fn test_save_round_trip_keeps_other_keys() {
+        let path = temp_path("toml");
+        fs::write(&path, "theme = \"dark\"\n\n[[mcp_servers]]\nname = \"old\"\nurl = \"http://old/sse\"\n").unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        let mut servers = config.mcp_servers.clone();
+        servers.push(McpServerConfig::from_endpoint("fs", &words("npx server-fs /tmp")));
+        config.with_servers(servers).unwrap().save(&path).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("theme = \"dark\""));
+        let reloaded = Config::from_file(&path).unwrap();
+        assert_eq!(reloaded.server_names(), vec!["old", "fs"]);
+        assert_eq!(reloaded.server("fs").unwrap().args, words("server-fs /tmp"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_save_keeps_mcp_servers_map() {
+        let path = temp_path("json");
+        fs::write(&path, r#"{"mcpServers": {"fs": {"command": "npx"}}}"#).unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        config.with_servers(Vec::new()).unwrap().save(&path).unwrap();
+        let value: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value, json!({"mcpServers": {}}));
+
+        // A file that no longer parses is not overwritten
+        fs::write(&path, "{oops").unwrap();
+        assert!(matches!(config.save(&path), Err(ConfigError::Syntax { .. })));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{oops");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_keeps_mode_and_symlink() {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let path = temp_path("toml");
+        fs::write(&path, "[[mcp_servers]]\nname = \"a\"\nurl = \"http://a/sse\"\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        let link = temp_path("toml");
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+
+        let config = Config::from_file(&link).unwrap();
+        config.with_servers(Vec::new()).unwrap().save(&link).unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert!(Config::from_file(&path).unwrap().mcp_servers.is_empty());
+        assert_eq!(mode(&path), 0o600);
+        fs::remove_file(&link).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_server_diff() {
+        let old = Config::from_value(json!({"mcp_servers": [
+            {"name": "a", "url": "http://a/sse"},
+            {"name": "b", "url": "http://b/sse"},
+            {"name": "c", "url": "http://c/sse"}
+        ]}))
+        .unwrap();
+        let new = Config::from_value(json!({"mcp_servers": [
+            {"name": "a", "url": "http://a/sse"},
+            {"name": "b", "url": "http://b2/sse"},
+            {"name": "d", "command": "mcp-d"}
+        ]}))
+        .unwrap();
+
+        let diff = ServerDiff::between(&old, &new);
+        assert_eq!(diff.added, vec!["d"]);
+        assert_eq!(diff.removed, vec!["c"]);
+        assert_eq!(diff.changed, vec!["b"]);
+        assert_eq!(diff.summary(), "+d, -c, ~b");
+        assert!(ServerDiff::between(&new, &new).is_empty());
+    }
+
+    fn profiled() -> Config {
+        Config::from_value(json!({
+            "mcp_servers": [
+                {"name": "api", "url": "http://localhost:8080/sse", "headers": {"X-Team": "pcb"}},
+                {"name": "search", "url": "http://localhost:8081/sse"},
+                {"name": "fs", "command": "mcp-fs"}
+            ],
+            "profiles": {
+                "dev": {},
+                "prod": {
+                    "production": true,
+                    "enabled": ["api", "fs"],
+                    "headers": {"X-Env": "prod"},
+                    "servers": {
+                        "api": {"url": "https://api.example.com/sse", "headers": {"X-Env": "prod-api"}}
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_profile_overrides_servers() {
+        let config = profiled();
+        assert_eq!(config.server_names(), vec!["api", "search", "fs"]);
+        assert!(!config.is_production());
+
+        let prod = config.clone().with_profile(Some("prod")).unwrap();
+        assert!(prod.is_production());
+        assert_eq!(prod.server_names(), vec!["api", "fs"]);
+        let api = prod.server("api").unwrap();
+        assert_eq!(api.url, "https://api.example.com/sse");
+        assert_eq!(api.headers["X-Team"], "pcb");
+        assert_eq!(api.headers["X-Env"], "prod-api");
+        assert_eq!(prod.server("fs").unwrap().headers["X-Env"], "prod");
+        // The file's own server list is untouched
+        assert_eq!(prod.mcp_servers[0].url, "http://localhost:8080/sse");
+
+        let diff = ServerDiff::between(&config, &prod);
+        assert_eq!(diff.removed, vec!["search"]);
+        assert_eq!(diff.changed, vec!["api", "fs"]);
+
+        let err = config.with_profile(Some("qa")).unwrap_err();
+        assert_eq!(err, "unknown profile 'qa' (available: dev, prod)");
+    }
+
+    #[test]
+    fn test_profile_validation() {
+        let errors = field_errors(json!({
+            "mcp_servers": [{"name": "a", "url": "http://a/sse", "headers": {"bad header": "x"}}],
+            "profiles": {
+                "p": {
+                    "enabled": ["a", "b"],
+                    "production": "yes",
+                    "servers": {"c": {}, "a": {"url": "ftp://a"}}
+                }
+            }
+        }));
+        assert_eq!(
+            errors,
+            vec![
+                "mcp_servers[0].headers.bad header: invalid header name",
+                "profiles.p.enabled[1]: unknown server \"b\"",
+                "profiles.p.production: expected true or false",
+                "profiles.p.servers.a.url: unsupported scheme \"ftp\" (expected http or https)",
+                "profiles.p.servers.c: unknown server \"c\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_servers_keeps_profiles() {
+        let config = profiled().with_profile(Some("prod")).unwrap();
+        let servers: Vec<McpServerConfig> = config.mcp_servers.iter().filter(|s| s.name != "search").cloned().collect();
+        let updated = config.with_servers(servers).unwrap();
+        assert_eq!(updated.active_profile.as_deref(), Some("prod"));
+        assert_eq!(updated.server("api").unwrap().url, "https://api.example.com/sse");
+
+        // A server a profile still names cannot be removed
+        let errors = config.with_servers(config.mcp_servers[1..].to_vec()).unwrap_err();
+        assert_eq!(errors[0].to_string(), "profiles.prod.enabled[0]: unknown server \"api\"");
+    }
+
+    #[test]
+    fn test_request_timeouts() {
+        let config = Config::from_value(json!({
+            "request_timeout": 20,
+            "mcp_servers": [
+                {"name": "slow", "url": "http://slow/sse", "request_timeout": 300},
+                {"name": "fast", "url": "http://fast/sse"}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(config.request_timeout(Some("slow")), Duration::from_secs(300));
+        assert_eq!(config.request_timeout(Some("fast")), Duration::from_secs(20));
+        assert_eq!(Config::default().request_timeout(None), DEFAULT_REQUEST_TIMEOUT);
+
+        let errors = field_errors(json!({
+            "request_timeout": 0,
+            "mcp_servers": [{"name": "a", "url": "http://a/sse", "request_timeout": "1m"}]
+        }));
+        assert_eq!(
+            errors,
+            vec![
+                "mcp_servers[0].request_timeout: expected a positive number of seconds",
+                "request_timeout: expected a positive number of seconds",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight() {
+        let config = Config::from_value(json!({"highlight": "light", "mcp_servers": []})).unwrap();
+        assert_eq!((config.highlight, config.highlight_theme), (Some(true), Some(Theme::Light)));
+        let config = Config::from_value(json!({"highlight": false, "mcp_servers": []})).unwrap();
+        assert_eq!((config.highlight, config.highlight_theme), (Some(false), None));
+        assert_eq!(
+            field_errors(json!({"highlight": "neon", "mcp_servers": []})),
+            vec!["highlight: expected true, false, \"auto\", \"dark\", \"light\" or \"off\""]
+        );
+    }
+
+    #[test]
+    fn test_output_lines() {
+        let config = Config::from_value(json!({"output_lines": 500000, "mcp_servers": []})).unwrap();
+        assert_eq!(config.output_lines, Some(500_000));
+        assert_eq!(
+            field_errors(json!({"output_lines": -1, "mcp_servers": []})),
+            vec!["output_lines: expected a positive number of lines"]
+        );
+    }
 }
@@ -1,42 +1,55 @@
-use anyhow::Result;
-use crossterm::event::{self, KeyEvent};
+use crate::mcp::McpClientEvent;
+use anyhow::{anyhow, Result};
+use crossterm::event::{self, EventStream, KeyEvent};
+use futures_util::StreamExt;
 use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::{interval, Interval, MissedTickBehavior};
 
 /// Event stream abstraction
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Event {
     Key(KeyEvent),
+    /// Terminal size changed
+    Resize,
+    /// Render timer: periodic housekeeping (config watcher, queued MCP events)
     Tick,
+    Mcp(McpClientEvent),
 }
 
-/// Event loop with configurable tick rate
+/// Waits on terminal input, the MCP event channel and a render timer at
+/// once, so neither input nor server traffic waits for a poll timeout
 pub struct EventLoop {
-    tick_rate: Duration,
+    terminal: EventStream,
+    timer: Interval,
 }
 
 impl EventLoop {
     pub fn new() -> Self {
-        Self {
-            tick_rate: Duration::from_millis(100),
-        }
+        Self::with_tick_rate(Duration::from_millis(100))
     }
 
-    pub fn with_tick_rate(mut self, rate: Duration) -> Self {
-        self.tick_rate = rate;
-        self
+    pub fn with_tick_rate(rate: Duration) -> Self {
+        let mut timer = interval(rate);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        Self { terminal: EventStream::new(), timer }
     }
 
-    /// Pure function: Self → Result<Option<Event>>
-    /// Polls for events with timeout
-    pub fn next(&mut self) -> Result<Option<Event>> {
-        if event::poll(self.tick_rate)? {
-            match event::read()? {
-                event::Event::Key(key) => Ok(Some(Event::Key(key))),
-                event::Event::Resize(_, _) => Ok(Some(Event::Tick)),
-                _ => Ok(None),
+    /// Next event from whichever source is ready first. Terminal events the
+    /// app does not use (mouse, focus, paste) are skipped.
+    pub async fn next(&mut self, mcp: &mut mpsc::Receiver<McpClientEvent>) -> Result<Event> {
+        loop {
+            tokio::select! {
+                input = self.terminal.next() => match input {
+                    Some(Ok(event::Event::Key(key))) => return Ok(Event::Key(key)),
+                    Some(Ok(event::Event::Resize(_, _))) => return Ok(Event::Resize),
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e.into()),
+                    None => return Err(anyhow!("terminal input closed")),
+                },
+                Some(event) = mcp.recv() => return Ok(Event::Mcp(event)),
+                _ = self.timer.tick() => return Ok(Event::Tick),
             }
-        } else {
-            Ok(Some(Event::Tick))
         }
     }
 }
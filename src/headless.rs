@@ -0,0 +1,331 @@
+// ============================================================================
+// src/headless.rs - Non-interactive subcommands (list-tools, call, read-resource)
+// ============================================================================
+
+use crate::args::args_to_json;
+use crate::config::{Config, McpServerConfig};
+use crate::jsonrpc::{ErrorObject, RequestId};
+use crate::mcp::{McpClient, McpClientEvent, ToolInfo};
+use serde_json::{json, Value};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Exit codes of the headless subcommands
+pub mod exit {
+    pub const OK: u8 = 0;
+    /// JSON-RPC error or a tool result with `isError: true`
+    pub const FAILED: u8 = 1;
+    /// Bad command line (unknown server, invalid arguments, ...)
+    pub const USAGE: u8 = 2;
+    /// Config could not be loaded or the server could not be reached
+    pub const UNAVAILABLE: u8 = 3;
+    pub const TIMEOUT: u8 = 4;
+}
+
+pub const USAGE: &str = "\
+Usage: mcp-client <subcommand> --server <name> [options]
+
+Subcommands (JSON on stdout, no TUI):
+  list-tools     --server <name>
+  call           --server <name> --tool <name> [--args '<json object>' | --args '<positional args>']
+  read-resource  --server <name> --uri <uri>
+
+Options:
+  --timeout <secs>   Give up after this many seconds (default: 30)
+  -y, --yes          Call tools that may be destructive in a production profile
+  -v, --verbose      Log protocol progress to stderr
+
+Exit codes: 0 ok, 1 JSON-RPC error or tool error, 2 usage, 3 connection/config, 4 timeout";
+
+#[derive(Debug, Error)]
+pub enum HeadlessError {
+    #[error("{0}")]
+    Usage(String),
+    #[error("server '{0}' not found in config")]
+    UnknownServer(String),
+    #[error("tool '{0}' not found on server")]
+    UnknownTool(String),
+    #[error("tool '{0}' may be destructive and the profile is production; pass --yes to call it")]
+    Unconfirmed(String),
+    #[error("config error: {0}")]
+    Config(String),
+    #[error("connection failed: {0}")]
+    Connect(String),
+    #[error("timed out after {} s", .0.as_secs())]
+    Timeout(Duration),
+    #[error("{} ({})", .0.message, .0.code)]
+    Rpc(ErrorObject),
+}
+
+impl HeadlessError {
+    pub const fn exit_code(&self) -> u8 {
+        match self {
+            HeadlessError::Usage(_)
+            | HeadlessError::UnknownServer(_)
+            | HeadlessError::UnknownTool(_)
+            | HeadlessError::Unconfirmed(_) => exit::USAGE,
+            HeadlessError::Config(_) | HeadlessError::Connect(_) => exit::UNAVAILABLE,
+            HeadlessError::Timeout(_) => exit::TIMEOUT,
+            HeadlessError::Rpc(_) => exit::FAILED,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeadlessAction {
+    ListTools,
+    Call { tool: String, args: Option<String> },
+    ReadResource { uri: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadlessCommand {
+    pub server: String,
+    pub action: HeadlessAction,
+    pub timeout: Duration,
+    pub verbose: bool,
+    /// Skip the production-profile check for destructive tools
+    pub yes: bool,
+}
+
+impl HeadlessCommand {
+    /// Pure function: argv (without program name) → subcommand.
+    /// `Ok(None)` means "no subcommand, start the TUI".
+    pub fn parse(args: &[String]) -> Result<Option<Self>, HeadlessError> {
+        let Some(subcommand) = args.first() else {
+            return Ok(None);
+        };
+        if !matches!(subcommand.as_str(), "list-tools" | "call" | "read-resource") {
+            return Ok(None);
+        }
+
+        let mut server = None;
+        let mut tool = None;
+        let mut call_args = None;
+        let mut uri = None;
+        let mut timeout = DEFAULT_TIMEOUT;
+        let mut verbose = false;
+        let mut yes = false;
+
+        let mut rest = args[1..].iter();
+        while let Some(flag) = rest.next() {
+            let mut value = || {
+                rest.next()
+                    .cloned()
+                    .ok_or_else(|| HeadlessError::Usage(format!("{} needs a value", flag)))
+            };
+            match flag.as_str() {
+                "--server" | "-s" => server = Some(value()?),
+                "--tool" | "-t" => tool = Some(value()?),
+                "--args" | "-a" => call_args = Some(value()?),
+                "--uri" | "-u" => uri = Some(value()?),
+                "--timeout" => {
+                    let secs = value()?;
+                    let secs: u64 = secs
+                        .parse()
+                        .map_err(|_| HeadlessError::Usage(format!("invalid --timeout: {}", secs)))?;
+                    timeout = Duration::from_secs(secs);
+                }
+                "-v" | "--verbose" => verbose = true,
+                "-y" | "--yes" => yes = true,
+                other => return Err(HeadlessError::Usage(format!("unknown option: {}", other))),
+            }
+        }
+
+        let server = server.ok_or_else(|| HeadlessError::Usage("--server is required".into()))?;
+        let action = match subcommand.as_str() {
+            "list-tools" => HeadlessAction::ListTools,
+            "call" => HeadlessAction::Call {
+                tool: tool.ok_or_else(|| HeadlessError::Usage("call needs --tool".into()))?,
+                args: call_args,
+            },
+            _ => HeadlessAction::ReadResource {
+                uri: uri.ok_or_else(|| HeadlessError::Usage("read-resource needs --uri".into()))?,
+            },
+        };
+
+        Ok(Some(Self { server, action, timeout, verbose, yes }))
+    }
+
+    /// Runs the subcommand against the configured server and returns the
+    /// JSON-RPC result
+    pub async fn run(&self, config: &Config) -> Result<Value, HeadlessError> {
+        let server = config
+            .server(&self.server)
+            .ok_or_else(|| HeadlessError::UnknownServer(self.server.clone()))?;
+
+        tokio::time::timeout(self.timeout, self.execute(&server, config.is_production()))
+            .await
+            .map_err(|_| HeadlessError::Timeout(self.timeout))?
+    }
+
+    async fn execute(&self, server: &McpServerConfig, production: bool) -> Result<Value, HeadlessError> {
+        let mut session = Session::open(server, self.verbose).await?;
+
+        match &self.action {
+            HeadlessAction::ListTools => session.request("tools/list", json!({})).await,
+            HeadlessAction::ReadResource { uri } => {
+                session.request("resources/read", json!({ "uri": uri })).await
+            }
+            HeadlessAction::Call { tool, args } => {
+                let args = args.as_deref().map(str::trim).filter(|text| !text.is_empty());
+                let positional = args.is_some_and(|text| !text.starts_with('{'));
+                // Like the TUI's confirmation: unknown tools count as destructive
+                let guarded = production && !self.yes;
+                let definition = if positional || guarded { session.tool(tool).await? } else { None };
+                if guarded && definition.as_ref().is_none_or(ToolInfo::is_destructive) {
+                    return Err(HeadlessError::Unconfirmed(tool.clone()));
+                }
+
+                let arguments = match args {
+                    None => json!({}),
+                    Some(text) if !positional => serde_json::from_str(text)
+                        .map_err(|e| HeadlessError::Usage(format!("--args is not valid JSON: {}", e)))?,
+                    // Positional arguments, converted like `:mcp run`
+                    Some(text) => {
+                        let schema = &definition.ok_or_else(|| HeadlessError::UnknownTool(tool.clone()))?.input_schema;
+                        let words: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+                        args_to_json(&words, schema).map_err(|e| HeadlessError::Usage(e.to_string()))?
+                    }
+                };
+                session
+                    .request("tools/call", json!({ "name": tool, "arguments": arguments }))
+                    .await
+            }
+        }
+    }
+}
+
+/// Pure function: result → exit code (`isError` tool results fail)
+pub fn result_exit_code(result: &Value) -> u8 {
+    if result.get("isError").and_then(|e| e.as_bool()) == Some(true) {
+        exit::FAILED
+    } else {
+        exit::OK
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// SESSION - McpClient driven without the TUI
+// ═══════════════════════════════════════════════════════════════════
+
+struct Session {
+    client: McpClient,
+    events: mpsc::Receiver<McpClientEvent>,
+    verbose: bool,
+}
+
+impl Session {
+    /// Connects and waits for the initialize handshake
+    async fn open(server: &McpServerConfig, verbose: bool) -> Result<Self, HeadlessError> {
+        let (tx, events) = mpsc::channel(100);
+        let mut session = Self {
+            client: McpClient::new(tx).with_auto_load_tools(false),
+            events,
+            verbose,
+        };
+        session.client.connect(server).await;
+
+        loop {
+            match session.next_event().await? {
+                McpClientEvent::Initialized(_) => return Ok(session),
+                McpClientEvent::Error(e) => return Err(HeadlessError::Connect(e)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Sends a request and waits for its correlated response
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value, HeadlessError> {
+        let id: RequestId = self.client.send_request(method.to_string(), Some(params));
+
+        loop {
+            match self.next_event().await? {
+                McpClientEvent::RpcResponse { response, .. } if response.id.as_ref() == Some(&id) => {
+                    return response.into_result().map_err(HeadlessError::Rpc);
+                }
+                McpClientEvent::RequestFailed { id: failed, error, .. } if failed == id => {
+                    return Err(HeadlessError::Connect(error.to_string()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Looks a tool up in `tools/list`
+    async fn tool(&mut self, name: &str) -> Result<Option<ToolInfo>, HeadlessError> {
+        let tools = self.request("tools/list", json!({})).await?;
+        let tool = tools["tools"].as_array().into_iter().flatten().find(|t| t["name"] == name);
+        Ok(tool.map(|t| ToolInfo {
+            name: name.to_string(),
+            description: t["description"].as_str().unwrap_or_default().to_string(),
+            input_schema: t["inputSchema"].clone(),
+            annotations: t["annotations"].clone(),
+        }))
+    }
+
+    async fn next_event(&mut self) -> Result<McpClientEvent, HeadlessError> {
+        let event = self
+            .events
+            .recv()
+            .await
+            .ok_or_else(|| HeadlessError::Connect("client stopped".into()))?;
+
+        match &event {
+            McpClientEvent::Disconnected => {
+                return Err(HeadlessError::Connect("server closed the connection".into()));
+            }
+            McpClientEvent::Debug(msg) | McpClientEvent::Message(msg) if self.verbose => {
+                eprintln!("{}", msg);
+            }
+            McpClientEvent::Error(msg) if self.verbose => eprintln!("❌ {}", msg),
+            _ => {}
+        }
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_no_subcommand_starts_tui() {
+        assert_eq!(HeadlessCommand::parse(&[]).unwrap(), None);
+        assert_eq!(HeadlessCommand::parse(&argv("--no-mouse")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_call() {
+        let cmd = HeadlessCommand::parse(&argv("call --server s1 --tool echo --timeout 5 -v --yes"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cmd.server, "s1");
+        assert_eq!(cmd.action, HeadlessAction::Call { tool: "echo".into(), args: None });
+        assert_eq!(cmd.timeout, Duration::from_secs(5));
+        assert!(cmd.verbose);
+        assert!(cmd.yes);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = HeadlessCommand::parse(&argv("list-tools")).unwrap_err();
+        assert_eq!(err.exit_code(), exit::USAGE);
+        assert!(HeadlessCommand::parse(&argv("call --server s1")).is_err());
+        assert!(HeadlessCommand::parse(&argv("read-resource --server s1 --bogus")).is_err());
+        assert!(HeadlessCommand::parse(&argv("list-tools --server")).is_err());
+    }
+
+    #[test]
+    fn test_result_exit_code() {
+        assert_eq!(result_exit_code(&json!({"content": []})), exit::OK);
+        assert_eq!(result_exit_code(&json!({"content": [], "isError": true})), exit::FAILED);
+    }
+}
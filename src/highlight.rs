@@ -0,0 +1,346 @@
+// ============================================================================
+// src/highlight.rs - Syntax highlighting for JSON and fenced code in results
+// ============================================================================
+
+use crate::jsonrpc::RequestId;
+use crate::state::{EntryKind, OutputEntry};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+
+/// Color set for highlighted output, picked to suit the terminal background
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Theme::Dark),
+            "light" => Some(Theme::Light),
+            _ => None,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+
+    /// Pure function: `$COLORFGBG` ("15;0", "0;15", ...) → theme. Terminals
+    /// that set it put the background color last; 7 and 15 are light.
+    pub fn from_colorfgbg(value: Option<&str>) -> Self {
+        match value.and_then(|v| v.rsplit(';').next()) {
+            Some("7" | "15") => Theme::Light,
+            _ => Theme::Dark,
+        }
+    }
+
+    /// Theme for the current terminal, dark unless it says otherwise
+    pub fn detect() -> Self {
+        Self::from_colorfgbg(std::env::var("COLORFGBG").ok().as_deref())
+    }
+
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Dark => Palette {
+                key: Style::default().fg(Color::Cyan),
+                string: Style::default().fg(Color::Green),
+                number: Style::default().fg(Color::Magenta),
+                boolean: Style::default().fg(Color::Yellow),
+                null: Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                punctuation: Style::default().fg(Color::Gray),
+                fence: Style::default().fg(Color::DarkGray),
+                keyword: Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD),
+                comment: Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            },
+            Theme::Light => Palette {
+                key: Style::default().fg(Color::Blue),
+                string: Style::default().fg(Color::Green),
+                number: Style::default().fg(Color::Magenta),
+                boolean: Style::default().fg(Color::Red),
+                null: Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+                punctuation: Style::default().fg(Color::DarkGray),
+                fence: Style::default().fg(Color::Gray),
+                keyword: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+                comment: Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            },
+        }
+    }
+}
+
+/// Styles for each kind of token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub key: Style,
+    pub string: Style,
+    pub number: Style,
+    pub boolean: Style,
+    pub null: Style,
+    pub punctuation: Style,
+    pub fence: Style,
+    pub keyword: Style,
+    pub comment: Style,
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Line highlighter
+// ═══════════════════════════════════════════════════════════════
+
+/// Highlights result lines one at a time, in display order. Pretty-printed
+/// JSON is recognised line by line; the only state carried between lines
+/// is whether a ``` fence is open, which ends with the result it is in.
+#[derive(Debug, Clone)]
+pub struct Highlighter {
+    palette: Palette,
+    /// Language of the open fence ("" when none was given)
+    fence: Option<String>,
+    /// Request of the previous line; a new one closes any open fence
+    request: Option<RequestId>,
+}
+
+impl Highlighter {
+    pub fn new(theme: Theme) -> Self {
+        Self { palette: theme.palette(), fence: None, request: None }
+    }
+
+    /// Forgets fence state, e.g. at a block or group header
+    pub fn reset(&mut self) {
+        self.fence = None;
+        self.request = None;
+    }
+
+    /// Styled spans for a result line, or None to keep the entry's own style
+    pub fn spans(&mut self, entry: &OutputEntry) -> Option<Vec<Span<'static>>> {
+        if entry.kind != EntryKind::Result || entry.heading {
+            self.reset();
+            return None;
+        }
+        if entry.request != self.request {
+            self.reset();
+            self.request = entry.request.clone();
+        }
+
+        let text = entry.text.as_str();
+        let fence = text.trim_start().strip_prefix("```");
+        match (&self.fence, fence) {
+            (Some(_), Some(_)) => {
+                self.fence = None;
+                Some(vec![Span::styled(text.to_string(), self.palette.fence)])
+            }
+            (None, Some(lang)) => {
+                self.fence = Some(lang.trim().to_lowercase());
+                Some(vec![Span::styled(text.to_string(), self.palette.fence)])
+            }
+            (Some(lang), None) => Some(code_spans(text, lang, &self.palette)),
+            (None, None) => json_spans(text, &self.palette),
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Lexers
+// ═══════════════════════════════════════════════════════════════
+
+/// Pure function: line → JSON token spans, or None when the line is not
+/// made of JSON tokens only (so prose is never colored by accident)
+pub fn json_spans(line: &str, palette: &Palette) -> Option<Vec<Span<'static>>> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let style = match c {
+            c if c.is_whitespace() => {
+                i += chars[i..].iter().take_while(|c| c.is_whitespace()).count();
+                Style::default()
+            }
+            '{' | '}' | '[' | ']' | ':' | ',' => {
+                i += 1;
+                palette.punctuation
+            }
+            '"' => {
+                i += quoted_len(&chars[i..])?;
+                let rest = chars[i..].iter().find(|c| !c.is_whitespace());
+                if rest == Some(&':') { palette.key } else { palette.string }
+            }
+            '-' | '0'..='9' => {
+                i += chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+                    .count();
+                palette.number
+            }
+            _ => {
+                let word: String = chars[i..].iter().take_while(|c| c.is_ascii_alphabetic()).collect();
+                i += word.len();
+                match word.as_str() {
+                    "true" | "false" => palette.boolean,
+                    "null" => palette.null,
+                    _ => return None,
+                }
+            }
+        };
+        spans.push(Span::styled(chars[start..i].iter().collect::<String>(), style));
+    }
+
+    if spans.iter().all(|span| span.content.trim().is_empty()) {
+        return None;
+    }
+    Some(spans)
+}
+
+/// Length of the quoted string at `chars[0]`, quotes included
+fn quoted_len(chars: &[char]) -> Option<usize> {
+    let quote = chars[0];
+    let mut i = 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            c if c == quote => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+const KEYWORDS: [&str; 34] = [
+    "as", "async", "await", "break", "class", "const", "continue", "def", "elif", "else", "enum", "export",
+    "fn", "for", "from", "function", "if", "impl", "import", "in", "let", "match", "mut", "pub", "return",
+    "self", "static", "struct", "trait", "type", "use", "var", "while", "yield",
+];
+
+/// Pure function: line of fenced code × language → spans. JSON fences use
+/// the JSON lexer; anything else gets strings, numbers, comments and
+/// common keywords.
+pub fn code_spans(line: &str, lang: &str, palette: &Palette) -> Vec<Span<'static>> {
+    if matches!(lang, "json" | "jsonc" | "json5") {
+        if let Some(spans) = json_spans(line, palette) {
+            return spans;
+        }
+    }
+    let hash_comments = matches!(lang, "sh" | "bash" | "shell" | "python" | "py" | "toml" | "yaml" | "yml" | "ruby");
+
+    let chars: Vec<char> = line.chars().collect();
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+    let flush = |plain: &mut String, spans: &mut Vec<Span<'static>>| {
+        if !plain.is_empty() {
+            spans.push(Span::raw(std::mem::take(plain)));
+        }
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let comment = (c == '/' && chars.get(i + 1) == Some(&'/')) || (c == '#' && hash_comments);
+        let (len, style) = if comment {
+            (chars.len() - i, palette.comment)
+        } else if c == '"' || c == '\'' {
+            (quoted_len(&chars[i..]).unwrap_or(chars.len() - i), palette.string)
+        } else if c.is_ascii_digit() && !chars[..i].last().is_some_and(|p| p.is_alphanumeric() || *p == '_') {
+            (chars[i..].iter().take_while(|c| c.is_ascii_alphanumeric() || **c == '.' || **c == '_').count(), palette.number)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = chars[i..].iter().take_while(|c| c.is_alphanumeric() || **c == '_').count();
+            let word: String = chars[i..i + len].iter().collect();
+            match word.as_str() {
+                "true" | "false" | "True" | "False" => (len, palette.boolean),
+                "null" | "None" | "nil" => (len, palette.null),
+                w if KEYWORDS.contains(&w) => (len, palette.keyword),
+                _ => {
+                    plain.push_str(&word);
+                    i += len;
+                    continue;
+                }
+            }
+        } else {
+            plain.push(c);
+            i += 1;
+            continue;
+        };
+        flush(&mut plain, &mut spans);
+        spans.push(Span::styled(chars[i..i + len].iter().collect::<String>(), style));
+        i += len;
+    }
+    flush(&mut plain, &mut spans);
+    spans
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(spans: &[Span<'static>]) -> Vec<(String, Style)> {
+        spans.iter().filter(|s| !s.content.trim().is_empty()).map(|s| (s.content.trim().to_string(), s.style)).collect()
+    }
+
+    #[test]
+    fn test_json_line_tokens() {
+        let p = Theme::Dark.palette();
+        let spans = json_spans(r#"  "ref": "U1", "pins": 8, "ok": true, "x": null"#, &p).unwrap();
+        assert_eq!(
+            styled(&spans)[..5],
+            [
+                ("\"ref\"".to_string(), p.key),
+                (":".to_string(), p.punctuation),
+                ("\"U1\"".to_string(), p.string),
+                (",".to_string(), p.punctuation),
+                ("\"pins\"".to_string(), p.key),
+            ]
+        );
+        assert!(styled(&spans).contains(&("8".to_string(), p.number)));
+        assert!(styled(&spans).contains(&("true".to_string(), p.boolean)));
+        assert!(styled(&spans).contains(&("null".to_string(), p.null)));
+
+        // prose is left alone
+        assert_eq!(json_spans("Found components matching U: U1, U2", &p), None);
+        assert_eq!(json_spans("", &p), None);
+    }
+
+    #[test]
+    fn test_fenced_code_in_text_results() {
+        let p = Theme::Light.palette();
+        let mut h = Highlighter::new(Theme::Light);
+        let line = |text: &str| OutputEntry::new(EntryKind::Result, text).with_request(RequestId::Number(1));
+
+        assert_eq!(h.spans(&line("Here is the config:")), None);
+        assert_eq!(styled(&h.spans(&line("```rust")).unwrap()), vec![("```rust".to_string(), p.fence)]);
+        let code = h.spans(&line("let x = \"a\"; // note")).unwrap();
+        assert_eq!(
+            styled(&code),
+            vec![
+                ("let".to_string(), p.keyword),
+                ("x =".to_string(), Style::default()),
+                ("\"a\"".to_string(), p.string),
+                (";".to_string(), Style::default()),
+                ("// note".to_string(), p.comment),
+            ]
+        );
+        assert!(h.spans(&line("```")).is_some());
+        assert_eq!(h.spans(&line("plain again")), None);
+
+        // a fence left open ends at the next heading or with its result
+        h.spans(&line("```"));
+        assert_eq!(h.spans(&line("Result 2").as_heading()), None);
+        assert_eq!(h.spans(&line("plain")), None);
+        h.spans(&line("```"));
+        let other = OutputEntry::new(EntryKind::Result, "after").with_request(RequestId::Number(2));
+        assert_eq!(h.spans(&other), None);
+    }
+
+    #[test]
+    fn test_theme_detection() {
+        assert_eq!(Theme::from_colorfgbg(Some("0;15")), Theme::Light);
+        assert_eq!(Theme::from_colorfgbg(Some("15;default;0")), Theme::Dark);
+        assert_eq!(Theme::from_colorfgbg(None), Theme::Dark);
+    }
+}
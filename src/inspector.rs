@@ -0,0 +1,385 @@
+// ============================================================================
+// src/inspector.rs - Raw JSON-RPC traffic log for the :inspector pane
+// ============================================================================
+
+use crate::jsonrpc::RequestId;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+const MAX_TRAFFIC_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrafficDirection {
+    #[serde(rename = "out")]
+    Outgoing,
+    #[serde(rename = "in")]
+    Incoming,
+}
+
+impl TrafficDirection {
+    pub const fn arrow(&self) -> &'static str {
+        match self {
+            TrafficDirection::Outgoing => "→",
+            TrafficDirection::Incoming => "←",
+        }
+    }
+}
+
+/// One JSON-RPC message as it crossed the wire, emitted by the MCP client
+#[derive(Debug, Clone)]
+pub struct TrafficRecord {
+    pub direction: TrafficDirection,
+    pub timestamp: DateTime<Local>,
+    pub size: usize,
+    pub body: Value,
+}
+
+impl TrafficRecord {
+    pub fn new(direction: TrafficDirection, body: Value, size: usize) -> Self {
+        Self { direction, timestamp: Local::now(), size, body }
+    }
+}
+
+/// A record enriched for display: responses borrow the method of
+/// their request and carry the round-trip latency
+#[derive(Debug, Clone)]
+pub struct TrafficEntry {
+    pub direction: TrafficDirection,
+    pub timestamp: DateTime<Local>,
+    pub method: Option<String>,
+    pub id: Option<RequestId>,
+    pub size: usize,
+    pub latency: Option<Duration>,
+    pub is_error: bool,
+    pub body: Value,
+}
+
+impl TrafficEntry {
+    /// Pure function: TrafficEntry × filter → bool
+    /// Matches a substring of the method or the exact id (quotes optional)
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim();
+        if filter.is_empty() {
+            return true;
+        }
+        let method_match = self.method.as_deref().is_some_and(|m| m.contains(filter));
+        let id_match = self.id.as_ref().is_some_and(|id| match id {
+            RequestId::Number(n) => n.to_string() == filter.trim_start_matches('#'),
+            RequestId::String(s) => s == filter.trim_matches('"'),
+        });
+        method_match || id_match
+    }
+
+    /// Pure function: TrafficEntry → one-line summary
+    pub fn summary(&self) -> String {
+        let id = self.id.as_ref().map_or_else(|| "-".to_string(), |id| format!("#{}", id));
+        let method = self.method.as_deref().unwrap_or("?");
+        let kind = if self.is_error { " ✗" } else { "" };
+        let latency = self
+            .latency
+            .map_or_else(String::new, |d| format!("  {} ms", d.as_millis()));
+        format!(
+            "{} {}  {:<6} {}{}  {}{}",
+            self.direction.arrow(),
+            self.timestamp.format("%H:%M:%S%.3f"),
+            id,
+            method,
+            kind,
+            format_size(self.size),
+            latency
+        )
+    }
+}
+
+/// Bounded traffic history with selection, expansion and filter state
+#[derive(Debug, Clone, Default)]
+pub struct Inspector {
+    entries: Vec<TrafficEntry>,
+    visible: bool,
+    selected: usize,
+    expanded: bool,
+    detail_scroll: u16,
+    filter: Option<String>,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub const fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub const fn is_expanded(&self) -> bool {
+        self.expanded
+    }
+
+    pub const fn detail_scroll(&self) -> u16 {
+        self.detail_scroll
+    }
+
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries passing the current filter, oldest first
+    pub fn filtered(&self) -> Vec<&TrafficEntry> {
+        let filter = self.filter.as_deref().unwrap_or("");
+        self.entries.iter().filter(|e| e.matches(filter)).collect()
+    }
+
+    /// Index into `filtered()`
+    pub fn selected(&self) -> usize {
+        self.selected.min(self.filtered().len().saturating_sub(1))
+    }
+
+    pub fn selected_entry(&self) -> Option<&TrafficEntry> {
+        self.filtered().get(self.selected()).copied()
+    }
+
+    /// Pure transformation: Inspector → TrafficRecord → Inspector
+    pub fn with_record(mut self, record: TrafficRecord) -> Self {
+        let body = record.body;
+        let id = body
+            .get("id")
+            .and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok());
+        let mut method = body.get("method").and_then(|m| m.as_str()).map(str::to_string);
+        let is_response = method.is_none() && (body.get("result").is_some() || body.get("error").is_some());
+        let mut latency = None;
+
+        if is_response {
+            let request = self.entries.iter().rev().find(|e| {
+                e.direction != record.direction && e.method.is_some() && e.id.is_some() && e.id == id
+            });
+            if let Some(request) = request {
+                method = request.method.clone();
+                latency = (record.timestamp - request.timestamp).to_std().ok();
+            }
+        }
+
+        // Follow the newest entry unless the user moved the selection
+        let follow = self.selected + 1 >= self.filtered().len();
+
+        self.entries.push(TrafficEntry {
+            direction: record.direction,
+            timestamp: record.timestamp,
+            method,
+            id,
+            size: record.size,
+            latency,
+            is_error: body.get("error").is_some(),
+            body,
+        });
+
+        if self.entries.len() > MAX_TRAFFIC_ENTRIES {
+            let excess = self.entries.len() - MAX_TRAFFIC_ENTRIES;
+            self.entries.drain(0..excess);
+            self.selected = self.selected.saturating_sub(excess);
+        }
+
+        if follow && !self.expanded {
+            self.selected = self.filtered().len().saturating_sub(1);
+        }
+        self
+    }
+
+    pub fn toggle_visible(mut self) -> Self {
+        self.visible = !self.visible;
+        self
+    }
+
+    pub fn hide(mut self) -> Self {
+        self.visible = false;
+        self
+    }
+
+    pub fn toggle_expanded(mut self) -> Self {
+        self.expanded = !self.expanded;
+        self.detail_scroll = 0;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Option<String>) -> Self {
+        self.filter = filter.filter(|f| !f.trim().is_empty());
+        self.selected = self.filtered().len().saturating_sub(1);
+        self
+    }
+
+    pub fn select_prev(mut self) -> Self {
+        self.selected = self.selected().saturating_sub(1);
+        self.detail_scroll = 0;
+        self
+    }
+
+    pub fn select_next(mut self) -> Self {
+        let last = self.filtered().len().saturating_sub(1);
+        self.selected = (self.selected() + 1).min(last);
+        self.detail_scroll = 0;
+        self
+    }
+
+    pub fn select_first(mut self) -> Self {
+        self.selected = 0;
+        self.detail_scroll = 0;
+        self
+    }
+
+    pub fn select_last(mut self) -> Self {
+        self.selected = self.filtered().len().saturating_sub(1);
+        self.detail_scroll = 0;
+        self
+    }
+
+    pub fn scroll_detail_up(mut self, lines: u16) -> Self {
+        self.detail_scroll = self.detail_scroll.saturating_sub(lines);
+        self
+    }
+
+    pub fn scroll_detail_down(mut self, lines: u16) -> Self {
+        self.detail_scroll = self.detail_scroll.saturating_add(lines);
+        self
+    }
+
+    pub fn clear(mut self) -> Self {
+        self.entries.clear();
+        self.selected = 0;
+        self.expanded = false;
+        self
+    }
+}
+
+/// Pure function: bytes → human readable size
+pub fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(direction: TrafficDirection, body: Value, offset_ms: i64) -> TrafficRecord {
+        let size = body.to_string().len();
+        let base = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().with_timezone(&Local);
+        TrafficRecord {
+            direction,
+            timestamp: base + chrono::Duration::milliseconds(offset_ms),
+            size,
+            body,
+        }
+    }
+
+    fn sample() -> Inspector {
+        Inspector::new()
+            .with_record(record(
+                TrafficDirection::Outgoing,
+                json!({"jsonrpc": "2.0", "id": 5, "method": "tools/call", "params": {}}),
+                0,
+            ))
+            .with_record(record(
+                TrafficDirection::Incoming,
+                json!({"jsonrpc": "2.0", "method": "notifications/progress"}),
+                10,
+            ))
+            .with_record(record(
+                TrafficDirection::Incoming,
+                json!({"jsonrpc": "2.0", "id": 5, "result": {"content": []}}),
+                42,
+            ))
+    }
+
+    #[test]
+    fn test_response_correlation() {
+        let inspector = sample();
+        let response = inspector.filtered()[2];
+        assert_eq!(response.method.as_deref(), Some("tools/call"));
+        assert_eq!(response.id, Some(RequestId::Number(5)));
+        assert_eq!(response.latency, Some(Duration::from_millis(42)));
+        assert!(inspector.filtered()[0].latency.is_none());
+    }
+
+    #[test]
+    fn test_filter_by_method_and_id() {
+        let inspector = sample().with_filter(Some("tools/".into()));
+        assert_eq!(inspector.filtered().len(), 2);
+
+        let inspector = inspector.with_filter(Some("#5".into()));
+        assert_eq!(inspector.filtered().len(), 2);
+
+        let inspector = inspector.with_filter(Some("progress".into()));
+        assert_eq!(inspector.filtered().len(), 1);
+
+        let inspector = inspector.with_filter(None);
+        assert_eq!(inspector.filtered().len(), 3);
+    }
+
+    #[test]
+    fn test_string_id_filter() {
+        let inspector = Inspector::new().with_record(record(
+            TrafficDirection::Outgoing,
+            json!({"jsonrpc": "2.0", "id": "req-a", "method": "ping"}),
+            0,
+        ));
+        assert!(inspector.filtered()[0].matches("req-a"));
+        assert!(inspector.filtered()[0].matches("\"req-a\""));
+        assert!(!inspector.filtered()[0].matches("req-b"));
+    }
+
+    #[test]
+    fn test_selection_follows_newest() {
+        let inspector = sample();
+        assert_eq!(inspector.selected(), 2);
+
+        let inspector = inspector.select_first().with_record(record(
+            TrafficDirection::Outgoing,
+            json!({"jsonrpc": "2.0", "id": 6, "method": "tools/list"}),
+            50,
+        ));
+        assert_eq!(inspector.selected(), 0);
+
+        let inspector = inspector.select_next().select_next().select_next().select_next();
+        assert_eq!(inspector.selected(), 3);
+    }
+
+    #[test]
+    fn test_bounded_history() {
+        let mut inspector = Inspector::new();
+        for i in 0..(MAX_TRAFFIC_ENTRIES + 10) {
+            inspector = inspector.with_record(record(
+                TrafficDirection::Outgoing,
+                json!({"jsonrpc": "2.0", "id": i, "method": "ping"}),
+                0,
+            ));
+        }
+        assert_eq!(inspector.len(), MAX_TRAFFIC_ENTRIES);
+        assert_eq!(inspector.selected(), MAX_TRAFFIC_ENTRIES - 1);
+    }
+
+    #[test]
+    fn test_summary_and_size() {
+        let summary = sample().filtered()[2].summary();
+        assert!(summary.starts_with("←"));
+        assert!(summary.contains("#5"));
+        assert!(summary.contains("tools/call"));
+        assert!(summary.contains("42 ms"));
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(2048), "2.0 KB");
+    }
+}
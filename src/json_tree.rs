@@ -0,0 +1,394 @@
+// ============================================================================
+// src/json_tree.rs - Foldable JSON tree behind :tree
+// ============================================================================
+
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// One step from a value to one of its children
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Pure function: path → JSONPath (`$.components[0]["odd key"]`)
+pub fn json_path(path: &[PathSegment]) -> String {
+    path.iter().fold("$".to_string(), |mut out, segment| {
+        match segment {
+            PathSegment::Key(key) if is_identifier(key) => {
+                out.push('.');
+                out.push_str(key);
+            }
+            PathSegment::Key(key) => {
+                out.push('[');
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(']');
+            }
+            PathSegment::Index(i) => out.push_str(&format!("[{}]", i)),
+        }
+        out
+    })
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Pure function: value × path → the value the path points at
+pub fn lookup<'a>(root: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter().try_fold(root, |value, segment| match segment {
+        PathSegment::Key(key) => value.get(key),
+        PathSegment::Index(i) => value.get(i),
+    })
+}
+
+/// What a row shows; the renderer styles by kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Object,
+    Array,
+    String,
+    Number,
+    Bool,
+    Null,
+}
+
+impl NodeKind {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Object(_) => NodeKind::Object,
+            Value::Array(_) => NodeKind::Array,
+            Value::String(_) => NodeKind::String,
+            Value::Number(_) => NodeKind::Number,
+            Value::Bool(_) => NodeKind::Bool,
+            Value::Null => NodeKind::Null,
+        }
+    }
+
+    pub const fn is_container(self) -> bool {
+        matches!(self, NodeKind::Object | NodeKind::Array)
+    }
+}
+
+/// One visible node
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeRow {
+    pub depth: usize,
+    pub path: Vec<PathSegment>,
+    /// `key` or `[i]`; empty for the root
+    pub label: String,
+    pub kind: NodeKind,
+    /// Leaf value as JSON, or `{3 keys}` / `[12 items]` for containers
+    pub summary: String,
+    pub expanded: bool,
+}
+
+impl TreeRow {
+    /// `▾`/`▸` for containers, blank for leaves
+    pub const fn marker(&self) -> &'static str {
+        match (self.kind.is_container(), self.expanded) {
+            (true, true) => "▾ ",
+            (true, false) => "▸ ",
+            (false, _) => "  ",
+        }
+    }
+}
+
+/// Foldable view of one JSON value with a cursor; only expanded nodes
+/// are flattened into rows
+#[derive(Debug, Clone)]
+pub struct JsonTree {
+    title: String,
+    root: Value,
+    expanded: HashSet<Vec<PathSegment>>,
+    rows: Vec<TreeRow>,
+    cursor: usize,
+    offset: usize,
+    /// Visible rows, set by the renderer
+    height: usize,
+}
+
+impl JsonTree {
+    /// Opens with the root expanded
+    pub fn new(title: String, root: Value) -> Self {
+        let mut tree = Self {
+            title,
+            root,
+            expanded: HashSet::from([Vec::new()]),
+            rows: Vec::new(),
+            cursor: 0,
+            offset: 0,
+            height: 1,
+        };
+        tree.rebuild();
+        tree
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn rows(&self) -> &[TreeRow] {
+        &self.rows
+    }
+
+    pub const fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Rows in the window starting at the current offset
+    pub fn visible(&self) -> &[TreeRow] {
+        let end = (self.offset + self.height).min(self.rows.len());
+        &self.rows[self.offset.min(end)..end]
+    }
+
+    pub fn current(&self) -> Option<&TreeRow> {
+        self.rows.get(self.cursor)
+    }
+
+    /// JSONPath of the node under the cursor
+    pub fn current_path(&self) -> String {
+        self.current().map_or_else(|| "$".to_string(), |row| json_path(&row.path))
+    }
+
+    /// Value under the cursor
+    pub fn current_value(&self) -> Option<&Value> {
+        self.current().and_then(|row| lookup(&self.root, &row.path))
+    }
+
+    pub fn with_height(mut self, height: usize) -> Self {
+        self.height = height.max(1);
+        self.follow_cursor();
+        self
+    }
+
+    /// Pure transformation: moves the cursor by `delta` rows
+    pub fn move_by(mut self, delta: isize) -> Self {
+        let last = self.rows.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(delta).min(last);
+        self.follow_cursor();
+        self
+    }
+
+    pub fn page(self, pages: isize) -> Self {
+        let height = self.height as isize;
+        self.move_by(pages * height)
+    }
+
+    pub fn top(self) -> Self {
+        self.move_by(isize::MIN)
+    }
+
+    pub fn bottom(self) -> Self {
+        self.move_by(isize::MAX)
+    }
+
+    /// `l`: expands a collapsed container, or steps into an expanded one
+    pub fn expand(mut self) -> Self {
+        let Some(row) = self.current().cloned() else {
+            return self;
+        };
+        if !row.kind.is_container() {
+            return self;
+        }
+        if row.expanded {
+            let has_children = self.rows.get(self.cursor + 1).is_some_and(|next| next.depth > row.depth);
+            return if has_children { self.move_by(1) } else { self };
+        }
+        self.expanded.insert(row.path);
+        self.rebuild();
+        self
+    }
+
+    /// `h`: collapses an expanded container, or moves to the parent
+    pub fn collapse(mut self) -> Self {
+        let Some(row) = self.current().cloned() else {
+            return self;
+        };
+        if row.kind.is_container() && row.expanded {
+            self.expanded.remove(&row.path);
+            self.rebuild();
+            return self;
+        }
+        if let Some(parent) = self.rows[..self.cursor].iter().rposition(|r| r.depth < row.depth) {
+            self.cursor = parent;
+            self.follow_cursor();
+        }
+        self
+    }
+
+    pub fn toggle(self) -> Self {
+        match self.current() {
+            Some(row) if row.expanded => self.collapse(),
+            _ => self.expand(),
+        }
+    }
+
+    /// Expands everything below the cursor
+    pub fn expand_all(mut self) -> Self {
+        let Some(row) = self.current().cloned() else {
+            return self;
+        };
+        if let Some(value) = lookup(&self.root, &row.path) {
+            let mut stack = vec![(row.path, value)];
+            while let Some((path, value)) = stack.pop() {
+                let children: Vec<(PathSegment, &Value)> = match value {
+                    Value::Object(map) => map.iter().map(|(k, v)| (PathSegment::Key(k.clone()), v)).collect(),
+                    Value::Array(items) => items.iter().enumerate().map(|(i, v)| (PathSegment::Index(i), v)).collect(),
+                    _ => continue,
+                };
+                for (segment, child) in children {
+                    let mut child_path = path.clone();
+                    child_path.push(segment);
+                    stack.push((child_path, child));
+                }
+                self.expanded.insert(path);
+            }
+        }
+        self.rebuild();
+        self
+    }
+
+    /// Collapses everything but the root
+    pub fn collapse_all(mut self) -> Self {
+        self.expanded = HashSet::from([Vec::new()]);
+        self.cursor = 0;
+        self.rebuild();
+        self
+    }
+
+    /// Re-flattens after a fold change, keeping the cursor on its node
+    fn rebuild(&mut self) {
+        let path = self.current().map(|row| row.path.clone());
+        self.rows.clear();
+        flatten(&self.root, Vec::new(), String::new(), 0, &self.expanded, &mut self.rows);
+        if let Some(path) = path {
+            self.cursor = self.rows.iter().position(|row| row.path == path).unwrap_or(0);
+        }
+        self.follow_cursor();
+    }
+
+    fn follow_cursor(&mut self) {
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + self.height {
+            self.offset = self.cursor + 1 - self.height;
+        }
+        self.offset = self.offset.min(self.rows.len().saturating_sub(self.height));
+    }
+}
+
+fn flatten(
+    value: &Value,
+    path: Vec<PathSegment>,
+    label: String,
+    depth: usize,
+    expanded: &HashSet<Vec<PathSegment>>,
+    rows: &mut Vec<TreeRow>,
+) {
+    let kind = NodeKind::of(value);
+    let summary = match value {
+        Value::Object(map) => format!("{{{} {}}}", map.len(), if map.len() == 1 { "key" } else { "keys" }),
+        Value::Array(items) => format!("[{} {}]", items.len(), if items.len() == 1 { "item" } else { "items" }),
+        leaf => leaf.to_string(),
+    };
+    let is_expanded = kind.is_container() && expanded.contains(&path);
+    rows.push(TreeRow { depth, path: path.clone(), label, kind, summary, expanded: is_expanded });
+    if !is_expanded {
+        return;
+    }
+
+    let children: Vec<(PathSegment, String, &Value)> = match value {
+        Value::Object(map) => map.iter().map(|(k, v)| (PathSegment::Key(k.clone()), k.clone(), v)).collect(),
+        Value::Array(items) => items.iter().enumerate().map(|(i, v)| (PathSegment::Index(i), format!("[{}]", i), v)).collect(),
+        _ => Vec::new(),
+    };
+    for (segment, label, child) in children {
+        let mut child_path = path.clone();
+        child_path.push(segment);
+        flatten(child, child_path, label, depth + 1, expanded, rows);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn board() -> Value {
+        json!({
+            "name": "demo",
+            "components": [
+                {"ref": "U1", "pins": 8},
+                {"ref": "R1", "pins": 2}
+            ],
+            "odd key": null
+        })
+    }
+
+    fn labels(tree: &JsonTree) -> Vec<String> {
+        tree.rows().iter().map(|row| format!("{}{}{} {}", "  ".repeat(row.depth), row.marker(), row.label, row.summary)).collect()
+    }
+
+    #[test]
+    fn test_json_path() {
+        let path = vec![PathSegment::Key("components".into()), PathSegment::Index(0), PathSegment::Key("odd key".into())];
+        assert_eq!(json_path(&path), r#"$.components[0]["odd key"]"#);
+        assert_eq!(lookup(&board(), &path[..2]).unwrap()["ref"], "U1");
+    }
+
+    #[test]
+    fn test_expand_collapse_and_counts() {
+        let tree = JsonTree::new("t".into(), board());
+        assert_eq!(
+            labels(&tree),
+            vec!["▾  {3 keys}", "  ▸ components [2 items]", "    name \"demo\"", "    odd key null"]
+        );
+
+        let tree = tree.move_by(1).expand();
+        assert_eq!(tree.rows()[2].summary, "{2 keys}");
+        assert_eq!(tree.cursor(), 1);
+
+        // l on an expanded node steps into it, h goes back to the parent
+        let tree = tree.expand();
+        assert_eq!(tree.current_path(), "$.components[0]");
+        let tree = tree.collapse();
+        assert_eq!(tree.current_path(), "$.components");
+        let tree = tree.collapse();
+        assert_eq!(tree.rows().len(), 4);
+    }
+
+    #[test]
+    fn test_expand_all_and_yank_targets() {
+        let tree = JsonTree::new("t".into(), board()).expand_all();
+        assert_eq!(tree.rows().len(), 10);
+        let tree = tree.bottom().move_by(-3);
+        assert_eq!(tree.current_path(), "$.components[1].pins");
+        assert_eq!(tree.current_value(), Some(&json!(2)));
+
+        let tree = tree.collapse_all();
+        assert_eq!(tree.rows().len(), 4);
+        assert_eq!(tree.cursor(), 0);
+    }
+
+    #[test]
+    fn test_window_follows_cursor() {
+        let items: Vec<Value> = (0..100).map(Value::from).collect();
+        let tree = JsonTree::new("t".into(), Value::Array(items)).with_height(10);
+        let tree = tree.page(2);
+        assert_eq!(tree.cursor(), 20);
+        assert_eq!(tree.offset(), 11);
+        assert_eq!(tree.visible().last().unwrap().label, "[19]");
+    }
+}
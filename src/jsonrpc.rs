@@ -0,0 +1,312 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+// ═══════════════════════════════════════════════════════════════════
+// JSON-RPC 2.0 MESSAGE MODEL
+// ═══════════════════════════════════════════════════════════════════
+
+pub const VERSION: &str = "2.0";
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// Request id - the spec allows both numbers and strings
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(i64),
+    String(String),
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestId::Number(n) => write!(f, "{}", n),
+            RequestId::String(s) => write!(f, "\"{}\"", s),
+        }
+    }
+}
+
+impl From<i64> for RequestId {
+    fn from(n: i64) -> Self {
+        RequestId::Number(n)
+    }
+}
+
+impl From<&str> for RequestId {
+    fn from(s: &str) -> Self {
+        RequestId::String(s.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    pub id: RequestId,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+impl Request {
+    pub fn new(id: impl Into<RequestId>, method: impl Into<String>, params: Value) -> Self {
+        Self {
+            jsonrpc: VERSION.to_string(),
+            id: id.into(),
+            method: method.into(),
+            params: Some(params),
+        }
+    }
+
+    /// Request with optional params (omitted from the wire when None)
+    pub fn with_params(id: impl Into<RequestId>, method: impl Into<String>, params: Option<Value>) -> Self {
+        Self {
+            jsonrpc: VERSION.to_string(),
+            id: id.into(),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+}
+
+impl Notification {
+    pub fn new(method: impl Into<String>, params: Option<Value>) -> Self {
+        Self {
+            jsonrpc: VERSION.to_string(),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// Error member of a failed response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorObject {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl ErrorObject {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), data: None }
+    }
+}
+
+/// Response carries exactly one of `result` / `error`.
+/// `id` is null when the server could not read the request id.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Option<RequestId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorObject>,
+}
+
+impl Response {
+    pub fn success(id: RequestId, result: Value) -> Self {
+        Self { jsonrpc: VERSION.to_string(), id: Some(id), result: Some(result), error: None }
+    }
+
+    pub fn failure(id: Option<RequestId>, error: ErrorObject) -> Self {
+        Self { jsonrpc: VERSION.to_string(), id, result: None, error: Some(error) }
+    }
+
+    /// Pure function: Response → Result<result, error object>
+    pub fn into_result(self) -> Result<Value, ErrorObject> {
+        match (self.result, self.error) {
+            (_, Some(error)) => Err(error),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Message {
+    Request(Request),
+    Notification(Notification),
+    Response(Response),
+}
+
+impl Message {
+    pub fn method(&self) -> Option<&str> {
+        match self {
+            Message::Request(r) => Some(&r.method),
+            Message::Notification(n) => Some(&n.method),
+            Message::Response(_) => None,
+        }
+    }
+
+    pub fn id(&self) -> Option<&RequestId> {
+        match self {
+            Message::Request(r) => Some(&r.id),
+            Message::Notification(_) => None,
+            Message::Response(r) => r.id.as_ref(),
+        }
+    }
+
+    /// Pure function: Value → Message, classified by its members
+    /// (`method` + `id` = request, `method` alone = notification,
+    /// `result` / `error` = response)
+    pub fn from_value(value: Value) -> Result<Self, ErrorObject> {
+        let Some(obj) = value.as_object() else {
+            return Err(ErrorObject::new(INVALID_REQUEST, "message is not an object"));
+        };
+        if obj.get("jsonrpc").and_then(|v| v.as_str()) != Some(VERSION) {
+            return Err(ErrorObject::new(INVALID_REQUEST, "missing or unsupported \"jsonrpc\" version"));
+        }
+
+        let invalid = |e: serde_json::Error| ErrorObject::new(INVALID_REQUEST, e.to_string());
+        let has_id = obj.get("id").is_some_and(|id| !id.is_null());
+
+        if obj.contains_key("method") {
+            if has_id {
+                serde_json::from_value(value).map(Message::Request).map_err(invalid)
+            } else {
+                serde_json::from_value(value).map(Message::Notification).map_err(invalid)
+            }
+        } else if obj.contains_key("result") || obj.contains_key("error") {
+            if !obj.contains_key("id") {
+                return Err(ErrorObject::new(INVALID_REQUEST, "response without \"id\""));
+            }
+            serde_json::from_value(value).map(Message::Response).map_err(invalid)
+        } else {
+            Err(ErrorObject::new(INVALID_REQUEST, "neither a request nor a response"))
+        }
+    }
+}
+
+/// Pure function: raw text → messages. A batch array yields one entry per
+/// element, each classified independently so one bad element does not
+/// discard the rest.
+pub fn parse(text: &str) -> Result<Vec<Result<Message, ErrorObject>>, ErrorObject> {
+    let value: Value = serde_json::from_str(text)
+        .map_err(|e| ErrorObject::new(PARSE_ERROR, e.to_string()))?;
+    parse_value(value)
+}
+
+/// Pure function: already decoded JSON → messages (see `parse`)
+pub fn parse_value(value: Value) -> Result<Vec<Result<Message, ErrorObject>>, ErrorObject> {
+    match value {
+        Value::Array(items) if items.is_empty() => {
+            Err(ErrorObject::new(INVALID_REQUEST, "empty batch"))
+        }
+        Value::Array(items) => Ok(items.into_iter().map(Message::from_value).collect()),
+        single => Ok(vec![Message::from_value(single)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn single(text: &str) -> Message {
+        let mut messages = parse(text).unwrap();
+        assert_eq!(messages.len(), 1);
+        messages.remove(0).unwrap()
+    }
+
+    #[test]
+    fn test_request_serialization() {
+        let req = Request::new(7, "tools/list", json!({}));
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            json!({"jsonrpc": "2.0", "id": 7, "method": "tools/list", "params": {}})
+        );
+    }
+
+    #[test]
+    fn test_string_and_number_ids() {
+        let msg = single(r#"{"jsonrpc":"2.0","id":"abc-1","result":{}}"#);
+        assert_eq!(msg.id(), Some(&RequestId::from("abc-1")));
+
+        let msg = single(r#"{"jsonrpc":"2.0","id":42,"result":{}}"#);
+        assert_eq!(msg.id(), Some(&RequestId::Number(42)));
+        assert_eq!(RequestId::from("x").to_string(), "\"x\"");
+    }
+
+    #[test]
+    fn test_classification() {
+        assert!(matches!(
+            single(r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#),
+            Message::Request(_)
+        ));
+        assert!(matches!(
+            single(r#"{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}"#),
+            Message::Notification(_)
+        ));
+        assert!(matches!(
+            single(r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"Parse error"}}"#),
+            Message::Response(Response { id: None, .. })
+        ));
+    }
+
+    #[test]
+    fn test_error_response() {
+        let Message::Response(resp) = single(
+            r#"{"jsonrpc":"2.0","id":3,"error":{"code":-32601,"message":"Method not found","data":"tools/nope"}}"#,
+        ) else {
+            panic!("expected response");
+        };
+        let err = resp.into_result().unwrap_err();
+        assert_eq!(err.code, METHOD_NOT_FOUND);
+        assert_eq!(err.data, Some(json!("tools/nope")));
+    }
+
+    #[test]
+    fn test_batch() {
+        let messages = parse(
+            r#"[{"jsonrpc":"2.0","id":1,"result":{}},
+                {"jsonrpc":"2.0","method":"notifications/progress"},
+                {"foo":1}]"#,
+        )
+        .unwrap();
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(messages[0], Ok(Message::Response(_))));
+        assert!(matches!(messages[1], Ok(Message::Notification(_))));
+        assert_eq!(messages[2].as_ref().unwrap_err().code, INVALID_REQUEST);
+    }
+
+    #[test]
+    fn test_batch_serialization() {
+        let batch = vec![
+            Message::Request(Request::new("a", "tools/list", json!({}))),
+            Message::Notification(Notification::new("notifications/initialized", None)),
+        ];
+        assert_eq!(
+            serde_json::to_value(&batch).unwrap(),
+            json!([
+                {"jsonrpc": "2.0", "id": "a", "method": "tools/list", "params": {}},
+                {"jsonrpc": "2.0", "method": "notifications/initialized"}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(parse("{not json").unwrap_err().code, PARSE_ERROR);
+        assert_eq!(parse("[]").unwrap_err().code, INVALID_REQUEST);
+        let messages = parse(r#"{"id":1,"result":{}}"#).unwrap();
+        assert_eq!(messages[0].as_ref().unwrap_err().code, INVALID_REQUEST);
+        let messages = parse(r#"{"jsonrpc":"2.0","result":{}}"#).unwrap();
+        assert_eq!(messages[0].as_ref().unwrap_err().code, INVALID_REQUEST);
+    }
+}
@@ -5,7 +5,26 @@ pub mod event;
 pub mod mcp;
 pub mod mode;
 pub mod state;
+pub mod viewport;
+pub mod highlight;
 pub mod ui;
 pub mod args;
 pub mod completion;
 pub mod tool_formatter;
+pub mod auth;
+pub mod tls;
+pub mod sse;
+pub mod jsonrpc;
+pub mod inspector;
+pub mod recording;
+pub mod results;
+pub mod json_tree;
+pub mod query;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod headless;
+pub mod cli;
+pub mod watch;
+
+#[cfg(test)]
+mod test_support;
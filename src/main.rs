@@ -1,31 +1,85 @@
 use anyhow::Result;
+use base64::Engine;
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
+    style::Print,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use mcp_client::{app::App, config::Config, event::EventLoop, ui::UI};
+use mcp_client::cli::{self, CliOptions};
+use mcp_client::headless::{self, HeadlessCommand, HeadlessError};
+use mcp_client::{app::App, config::{self, Config}, event::EventLoop, ui::UI};
 use ratatui::{backend::CrosstermBackend, Terminal};
+use std::fs::OpenOptions;
 use std::io;
+use std::path::Path;
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match CliOptions::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            return Ok(ExitCode::from(headless::exit::USAGE));
+        }
+    };
+    if options.version {
+        println!("{}", cli::version());
+        return Ok(ExitCode::SUCCESS);
+    }
+    if options.help {
+        println!("{}\n\n{}", cli::USAGE, headless::USAGE);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    match HeadlessCommand::parse(&options.subcommand) {
+        Ok(Some(command)) => {
+            return Ok(run_headless(command, options.config.as_deref(), options.profile.as_deref()).await)
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, headless::USAGE);
+            return Ok(ExitCode::from(e.exit_code()));
+        }
+    }
+
+    // Load config and open the log before touching the terminal so
+    // errors are printed normally. Without a config file the app starts
+    // empty and `:mcp add` creates one.
+    let config_path = config::resolve(options.config.as_deref())?;
+    let config = Config::load(&config_path)?
+        .with_profile(options.profile.as_deref())
+        .map_err(anyhow::Error::msg)?;
+
+    let mut app = App::new(config)
+        .with_config_watch()
+        .with_mouse(!options.no_mouse)
+        .with_startup_commands(options.startup_commands());
+    if let Some(path) = &options.log_file {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        app = app.with_log_file(file);
+    }
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen)?;
+    if app.mouse_enabled() {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Load config
-    let config = Config::from_file("config.json")?;
-
     // Run app
-    let app = App::new(config);
     let mut event_loop = EventLoop::new();
     let ui = UI::new();
 
-    let res = run_loop(&mut terminal, app, &mut event_loop, &ui).await;
+    let res = match app.run_startup_commands().await {
+        Ok(app) => run_loop(&mut terminal, app, &mut event_loop, &ui).await,
+        Err(e) => Err(e),
+    };
 
     // Restore terminal
     disable_raw_mode()?;
@@ -36,7 +90,40 @@ async fn main() -> Result<()> {
     )?;
     terminal.show_cursor()?;
 
-    res
+    res.map(|_| ExitCode::SUCCESS)
+}
+
+/// Runs a subcommand without the TUI: result JSON on stdout, diagnostics
+/// on stderr. JSON-RPC errors are printed as the error object.
+async fn run_headless(command: HeadlessCommand, config_path: Option<&Path>, profile: Option<&str>) -> ExitCode {
+    let config = config::locate(config_path)
+        .and_then(|path| Config::from_file(path).map_err(anyhow::Error::from))
+        .and_then(|config| config.with_profile(profile).map_err(anyhow::Error::msg));
+    let result = match config {
+        Ok(config) => {
+            for warning in &config.warnings {
+                eprintln!("warning: {}", warning);
+            }
+            command.run(&config).await
+        }
+        Err(e) => Err(HeadlessError::Config(e.to_string())),
+    };
+
+    match result {
+        Ok(value) => {
+            println!("{}", serde_json::to_string_pretty(&value).unwrap_or_default());
+            ExitCode::from(headless::result_exit_code(&value))
+        }
+        Err(HeadlessError::Rpc(error)) => {
+            println!("{}", serde_json::to_string_pretty(&error).unwrap_or_default());
+            eprintln!("error: {} ({})", error.message, error.code);
+            ExitCode::from(headless::exit::FAILED)
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
 }
 
 async fn run_loop(
@@ -75,7 +162,10 @@ async fn run_loop(
             last_size = size;
         }
 
-        terminal.draw(|frame| ui.render(frame, &mut app))?;
+        // Redraw only when the last event changed something
+        if app.take_redraw() {
+            terminal.draw(|frame| ui.render(frame, &mut app))?;
+        }
 
         // Handle mouse capture state changes
         let current_mouse_state = app.mouse_enabled();
@@ -88,14 +178,22 @@ async fn run_loop(
             last_mouse_state = current_mouse_state;
         }
 
-        if let Some(event) = event_loop.next()? {
-            app = app.handle_event(event).await?;
+        // Yanked text goes to the system clipboard through the terminal
+        if let Some(text) = app.take_clipboard() {
+            execute!(terminal.backend_mut(), Print(osc52(&text)))?;
+        }
 
-            if app.should_quit() {
-                break;
-            }
+        let event = event_loop.next(&mut app.mcp_event_rx).await?;
+        app = app.handle_event(event).await?;
+        if app.should_quit() {
+            break;
         }
     }
 
     Ok(())
 }
+
+/// Pure function: text → OSC 52 "set clipboard" escape sequence
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64::engine::general_purpose::STANDARD.encode(text))
+}
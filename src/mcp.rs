@@ -1,38 +1,21 @@
+use crate::auth::{open_browser, parse_www_authenticate, Authorizer, TokenStore};
+use crate::config::McpServerConfig;
+use crate::inspector::{TrafficDirection, TrafficRecord};
+use crate::jsonrpc::{self, ErrorObject, Message, Notification, Request, RequestId, Response};
+use crate::recording::ReplayServer;
+use crate::sse::SseDecoder;
+use crate::tls::{self, TlsDetails};
 use futures_util::StreamExt;
-use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, StatusCode};
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use thiserror::Error;
 use tokio::sync::{mpsc, oneshot, Mutex};
 use std::sync::atomic::{AtomicI64, Ordering};
 use tokio::time::sleep;
-use tokio::task;
-
-// helper function for safe JSON formatting
-async fn format_json_safely(value: &serde_json::Value) -> String {
-    let value_clone = value.clone();
-    
-    // Run formatting in blocking task to prevent UI freeze
-    match task::spawn_blocking(move || {
-        serde_json::to_string_pretty(&value_clone)
-    }).await {
-        Ok(Ok(formatted)) => formatted,
-        _ => value.to_string(), // Fallback to compact format
-    }
-}
-
-// helper to truncate large JSON
-fn truncate_json_display(json_str: &str, max_lines: usize) -> (String, bool) {
-    let lines: Vec<&str> = json_str.lines().collect();
-    
-    if lines.len() <= max_lines {
-        (json_str.to_string(), false)
-    } else {
-        let truncated = lines[..max_lines].join("\n");
-        (truncated, true)
-    }
-}
 
 // ═══════════════════════════════════════════════════════════════
 // EVENTS
@@ -43,6 +26,17 @@ pub struct ToolInfo {
     pub name: String,
     pub description: String,
     pub input_schema: serde_json::Value,
+    /// Behaviour hints (`readOnlyHint`, `destructiveHint`, ...); Null if absent
+    pub annotations: serde_json::Value,
+}
+
+impl ToolInfo {
+    /// Per the MCP spec a tool may be destructive unless it is marked
+    /// read-only or explicitly `destructiveHint: false`
+    pub fn is_destructive(&self) -> bool {
+        let hint = |key: &str| self.annotations.get(key).and_then(|v| v.as_bool());
+        hint("readOnlyHint") != Some(true) && hint("destructiveHint") != Some(false)
+    }
 }
 
 impl std::fmt::Display for ToolInfo {
@@ -59,23 +53,114 @@ pub enum McpClientEvent {
     Error(String),
     ToolsListed(Vec<ToolInfo>),
     Debug(String),
-    LargeResponse { total_lines: usize, chunk: String },
+    Traffic(TrafficRecord),
+    /// A notification from the server (progress, log messages, list changes)
+    Notification(Notification),
+    /// Result of the initialize handshake (server info and capabilities)
+    Initialized(serde_json::Value),
+    /// Response to a request sent with `McpClient::send_request`
+    RpcResponse {
+        method: String,
+        elapsed: Duration,
+        response: Response,
+    },
+    /// A response nobody waits for (tools/call results and errors); the
+    /// app renders it. tools/list results arrive as `ToolsListed` instead.
+    Response(Response),
+    /// The request could not be delivered; no response will follow
+    RequestFailed {
+        id: RequestId,
+        method: String,
+        error: McpError,
+    },
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ERRORS
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum McpError {
+    #[error("Parse error (-32700): server could not parse the request - {0}")]
+    Parse(String),
+    #[error("Invalid request (-32600): {0}")]
+    InvalidRequest(String),
+    #[error("Method not found (-32601): {0}")]
+    MethodNotFound(String),
+    #[error("Invalid params (-32602): {0}")]
+    InvalidParams(String),
+    #[error("Internal error (-32603): {0}")]
+    Internal(String),
+    #[error("Server error ({code}): {message}")]
+    Server { code: i64, message: String },
+    #[error("Error {code}: {message}")]
+    Application { code: i64, message: String },
+    #[error("Not connected")]
+    NotConnected,
+    #[error("POST HTTP error: 401 Unauthorized - reconnect to re-authorize")]
+    Unauthorized,
+    #[error("POST HTTP error: {0}")]
+    Http(StatusCode),
+    #[error("POST error: {0}")]
+    Transport(String),
+}
+
+impl McpError {
+    /// JSON-RPC error code, None for transport failures
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            McpError::Parse(_) => Some(jsonrpc::PARSE_ERROR),
+            McpError::InvalidRequest(_) => Some(jsonrpc::INVALID_REQUEST),
+            McpError::MethodNotFound(_) => Some(jsonrpc::METHOD_NOT_FOUND),
+            McpError::InvalidParams(_) => Some(jsonrpc::INVALID_PARAMS),
+            McpError::Internal(_) => Some(jsonrpc::INTERNAL_ERROR),
+            McpError::Server { code, .. } | McpError::Application { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl From<ErrorObject> for McpError {
+    fn from(e: ErrorObject) -> Self {
+        let message = e.message;
+        match e.code {
+            jsonrpc::PARSE_ERROR => McpError::Parse(message),
+            jsonrpc::INVALID_REQUEST => McpError::InvalidRequest(message),
+            jsonrpc::METHOD_NOT_FOUND => McpError::MethodNotFound(message),
+            jsonrpc::INVALID_PARAMS => McpError::InvalidParams(message),
+            jsonrpc::INTERNAL_ERROR => McpError::Internal(message),
+            code @ -32099..=-32000 => McpError::Server { code, message },
+            code => McpError::Application { code, message },
+        }
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════
 // CLIENT
 // ═══════════════════════════════════════════════════════════════
 
-#[derive(Debug)]
+/// Cheap to clone: all session state is shared, so a clone can post
+/// requests from a background task
+#[derive(Debug, Clone)]
 pub struct McpClient {
     event_tx: mpsc::Sender<McpClientEvent>,
     client: Client,
     base_url: Option<String>,
     session_endpoint: Arc<Mutex<Option<String>>>,
-    pending: Arc<Mutex<HashMap<i64, oneshot::Sender<serde_json::Value>>>>,
+    pending: Arc<Mutex<HashMap<RequestId, oneshot::Sender<Response>>>>,
+    /// Requests given up on; their late responses are dropped
+    cancelled: Arc<Mutex<HashSet<RequestId>>>,
     next_id: Arc<AtomicI64>,
     sse_shutdown: Arc<Mutex<Option<oneshot::Sender<()>>>>,
     available_tools: Arc<Mutex<Vec<ToolInfo>>>,
+    access_token: Arc<Mutex<Option<String>>>,
+    authorizer: Option<Authorizer>,
+    tls_details: Arc<Mutex<Option<TlsDetails>>>,
+    replay: Option<ReplayTransport>,
+    initialize_id: Arc<Mutex<Option<RequestId>>>,
+    auto_load_tools: bool,
+    /// Configured per-server headers, sent with every request
+    headers: HeaderMap,
 }
 
 impl McpClient {
@@ -86,125 +171,321 @@ impl McpClient {
             base_url: None,
             session_endpoint: Arc::new(Mutex::new(None)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            cancelled: Arc::new(Mutex::new(HashSet::new())),
             next_id: Arc::new(AtomicI64::new(1)),
             sse_shutdown: Arc::new(Mutex::new(None)),
             available_tools: Arc::new(Mutex::new(Vec::new())),
+            access_token: Arc::new(Mutex::new(None)),
+            authorizer: None,
+            tls_details: Arc::new(Mutex::new(None)),
+            replay: None,
+            initialize_id: Arc::new(Mutex::new(None)),
+            auto_load_tools: true,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Whether tools/list is sent right after initialize (default: on)
+    pub fn with_auto_load_tools(mut self, enabled: bool) -> Self {
+        self.auto_load_tools = enabled;
+        self
+    }
+
+    fn session_context(&self, base_url: String) -> SessionContext {
+        SessionContext {
+            event_tx: self.event_tx.clone(),
+            client: self.client.clone(),
+            base_url,
+            session_endpoint: self.session_endpoint.clone(),
+            pending: self.pending.clone(),
+            cancelled: self.cancelled.clone(),
+            next_id: self.next_id.clone(),
+            available_tools: self.available_tools.clone(),
+            access_token: self.access_token.clone(),
+            initialize_id: self.initialize_id.clone(),
+            replay: self.replay.clone(),
+            auto_load_tools: self.auto_load_tools,
+            headers: self.headers.clone(),
         }
     }
 
-    pub async fn connect(&mut self, url: String, server_name: String) {
+    pub async fn connect(&mut self, server: &McpServerConfig) {
+        let url = server.url.clone();
+        let server_name = server.name.clone();
+        if url.is_empty() {
+            let _ = self.event_tx.send(McpClientEvent::Error(format!(
+                "'{}' is a stdio server ({}); only HTTP/SSE servers can be connected",
+                server_name,
+                server.endpoint()
+            ))).await;
+            return;
+        }
+        self.headers = match header_map(&server.headers) {
+            Ok(headers) => headers,
+            Err(e) => {
+                let _ = self.event_tx.send(McpClientEvent::Error(
+                    format!("Headers for '{}': {}", server_name, e)
+                )).await;
+                return;
+            }
+        };
         self.base_url = Some(url.clone());
+        self.replay = None;
+        *self.session_endpoint.lock().await = None;
+        *self.tls_details.lock().await = None;
+        self.forget_requests().await;
+
+        self.client = match tls::build_client(server.tls.as_ref()) {
+            Ok(client) => client,
+            Err(e) => {
+                let _ = self.event_tx.send(McpClientEvent::Error(
+                    format!("TLS configuration for '{}': {}", server_name, e)
+                )).await;
+                return;
+            }
+        };
+        if server.tls.as_ref().is_some_and(|t| t.danger_accept_invalid_certs) {
+            let _ = self.event_tx.send(McpClientEvent::Message(
+                format!("⚠️  TLS certificate verification is DISABLED for '{}' (danger_accept_invalid_certs)", server_name)
+            )).await;
+        }
 
-        let event_tx = self.event_tx.clone();
-        let client = self.client.clone();
-        let session_endpoint = self.session_endpoint.clone();
-        let pending = self.pending.clone();
-        let sse_shutdown = self.sse_shutdown.clone();
-        let next_id = self.next_id.clone();
-        let available_tools = self.available_tools.clone();
+        let authorizer = Authorizer::new(
+            self.client.clone(),
+            TokenStore::default_location(),
+            server_name.clone(),
+            url.clone(),
+        );
+        self.authorizer = Some(authorizer.clone());
+
+        let ctx = self.session_context(url.clone());
 
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
-        *sse_shutdown.lock().await = Some(shutdown_tx);
+        *self.sse_shutdown.lock().await = Some(shutdown_tx);
+
+        let tls_config = server.tls.clone();
+        let tls_details = self.tls_details.clone();
 
         tokio::spawn(async move {
-            let _ = event_tx.send(McpClientEvent::Debug(
+            let _ = ctx.event_tx.send(McpClientEvent::Debug(
                 format!("🔌 Connecting to {} at {}", server_name, url)
             )).await;
 
-                match client.get(&url).send().await {
-                    Ok(response) => {
-                    let _ = event_tx.send(McpClientEvent::Debug(
-                        format!("📡 Initial response: HTTP {}", response.status())
-                    )).await;
-
-                        if !response.status().is_success() {
-                            let _ = event_tx.send(McpClientEvent::Error(
-                            format!("HTTP connect failed: {}", response.status()),
-                            )).await;
-                        return;
-                        }
+            match open_sse_stream(&ctx, &authorizer).await {
+                Ok(response) => {
+                    *tls_details.lock().await = TlsDetails::from_response(&response, tls_config.as_ref());
+                    let _ = ctx.event_tx.send(McpClientEvent::Connected).await;
 
-                        let _ = event_tx.send(McpClientEvent::Connected).await;
-                    
                     // KLUCZ: Rozpocznij długotrwałe nasłuchiwanie SSE
-                    sse_listener_loop(
-                        response,
-                        event_tx.clone(),
-                        client.clone(),
-                        url.clone(),
-                        session_endpoint.clone(),
-                        pending.clone(),
-                        next_id.clone(),
-                        available_tools.clone(),
-                        shutdown_rx,
-                                            ).await;
+                    sse_listener_loop(response, ctx, shutdown_rx).await;
                 }
                 Err(e) => {
-                    let _ = event_tx.send(McpClientEvent::Error(
-                        format!("Connect error: {}", e)
-                    )).await;
+                    let _ = ctx.event_tx.send(McpClientEvent::Error(e)).await;
                 }
             }
         });
     }
 
-    pub async fn list_tools(&self) {
-            let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+    /// Connects to a local fake transport that answers from a recording
+    pub async fn connect_replay(&mut self, name: &str, server: ReplayServer) {
+        let (incoming_tx, incoming_rx) = mpsc::unbounded_channel();
+        self.base_url = Some(format!("replay://{}", name));
+        self.authorizer = None;
+        *self.session_endpoint.lock().await = None;
+        *self.tls_details.lock().await = None;
+        self.forget_requests().await;
+        self.replay = Some(ReplayTransport {
+            server: Arc::new(Mutex::new(server)),
+            incoming: incoming_tx,
+        });
 
-            let req = json!({
-                "jsonrpc": "2.0",
-                "id": id,
-                "method": "tools/list",
-                "params": {}
-            });
+        let ctx = self.session_context(format!("replay://{}", name));
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        *self.sse_shutdown.lock().await = Some(shutdown_tx);
 
-        let _ = self.event_tx.send(McpClientEvent::Debug(
-            format!("📤 Sending tools/list (id={})", id)
-        )).await;
+        tokio::spawn(replay_loop(ctx, incoming_rx, shutdown_rx));
+    }
 
-            if let Err(e) = self.send_jsonrpc(req, Some(id)).await {
-                let _ = self.event_tx.send(
-                    McpClientEvent::Error(format!("list_tools send: {}", e))
-                ).await;
+    /// Forgets cached OAuth tokens for the current server
+    pub fn logout(&self) -> bool {
+        match &self.authorizer {
+            Some(authorizer) => {
+                authorizer.logout();
+                true
             }
+            None => false,
+        }
+    }
+
+    fn next_request_id(&self) -> RequestId {
+        RequestId::Number(self.next_id.fetch_add(1, Ordering::SeqCst))
+    }
+
+    pub fn list_tools(&self) -> RequestId {
+        let id = self.next_request_id();
+        let req = Request::new(id.clone(), "tools/list", json!({}));
+        self.dispatch(req, format!("📤 Sending tools/list (id={})", id))
+    }
+
+    pub fn call_tool(&self, tool_name: String, arguments: serde_json::Value) -> RequestId {
+        let id = self.next_request_id();
+        let note = format!("📤 Calling tool '{}' (id={})", tool_name, id);
+        let req = Request::new(id, "tools/call", json!({
+            "name": tool_name,
+            "arguments": arguments
+        }));
+        self.dispatch(req, note)
     }
 
-    pub async fn call_tool(&self, tool_name: String, arguments: serde_json::Value) {
-        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+    /// Posts a request from a background task, so the caller never waits
+    /// on the network. The response is left to the default renderer; a
+    /// failed POST comes back as `RequestFailed`.
+    fn dispatch(&self, request: Request, note: String) -> RequestId {
+        let client = self.clone();
+        let id = request.id.clone();
+        tokio::spawn(async move {
+            let _ = client.event_tx.send(McpClientEvent::Debug(note)).await;
+            if let Err(error) = client.send_jsonrpc(&request).await {
+                let _ = client.event_tx.send(McpClientEvent::RequestFailed {
+                    id: request.id,
+                    method: request.method,
+                    error,
+                }).await;
+            }
+        });
+        id
+    }
 
-        let req = json!({
-            "jsonrpc": "2.0",
-            "id": id,
-            "method": "tools/call",
-            "params": {
-                "name": tool_name,
-                "arguments": arguments
+    /// Sends an arbitrary JSON-RPC request from a background task; the
+    /// correlated response is delivered as `McpClientEvent::RpcResponse`,
+    /// a failed POST as `RequestFailed`
+    pub fn send_request(&self, method: String, params: Option<serde_json::Value>) -> RequestId {
+        let id = self.next_request_id();
+        let req = Request::with_params(id.clone(), method.clone(), params);
+        let client = self.clone();
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let id = req.id.clone();
+            match client.send_awaiting(req).await {
+                Ok(rx) => {
+                    if let Ok(response) = rx.await {
+                        let _ = client.event_tx.send(McpClientEvent::RpcResponse {
+                            method,
+                            elapsed: started.elapsed(),
+                            response,
+                        }).await;
+                    }
+                }
+                Err(error) => {
+                    let _ = client.event_tx.send(McpClientEvent::RequestFailed { id, method, error }).await;
+                }
             }
         });
+        id
+    }
 
-        let _ = self.event_tx.send(McpClientEvent::Debug(
-            format!("📤 Calling tool '{}' (id={})", tool_name, id)
-        )).await;
+    /// Gives up on a request: its waiter is dropped, a late response is
+    /// ignored and the server is told with `notifications/cancelled`
+    pub async fn cancel(&self, id: RequestId, reason: String) {
+        self.pending.lock().await.remove(&id);
+        self.cancelled.lock().await.insert(id.clone());
 
-        if let Err(e) = self.send_jsonrpc(req, Some(id)).await {
-            let _ = self.event_tx.send(
-                McpClientEvent::Error(format!("call_tool send: {}", e))
-            ).await;
-        }
+        let notification = Notification::new(
+            "notifications/cancelled",
+            Some(json!({ "requestId": id, "reason": reason })),
+        );
+        let client = self.clone();
+        tokio::spawn(async move {
+            let Ok(payload) = serde_json::to_value(&notification) else {
+                return;
+            };
+            if let Err(e) = client.post_message(&payload).await {
+                let _ = client.event_tx.send(McpClientEvent::Debug(
+                    format!("Cancellation of #{} not delivered: {}", id, e)
+                )).await;
+            }
+        });
+    }
+
+    /// Drops the waiters and cancelled ids of the previous session; their
+    /// responses can no longer arrive
+    async fn forget_requests(&self) {
+        self.pending.lock().await.clear();
+        self.cancelled.lock().await.clear();
     }
 
     pub async fn get_available_tools(&self) -> Vec<ToolInfo> {
         self.available_tools.lock().await.clone()
     }
 
-    async fn send_jsonrpc(
-        &self,
-        payload: serde_json::Value,
-        expect_id: Option<i64>,
-    ) -> Result<(), String> {
+    /// TLS details of the current connection (None for plain HTTP)
+    pub async fn tls_details(&self) -> Option<TlsDetails> {
+        self.tls_details.lock().await.clone()
+    }
+
+    async fn post_json(&self, url: &str, payload: &serde_json::Value) -> reqwest::Result<reqwest::Response> {
+        let token = self.access_token.lock().await.clone();
+        with_bearer(self.client.post(url).headers(self.headers.clone()), token.as_deref())
+            .header("Content-Type", "application/json")
+            .body(payload.to_string())
+            .send()
+            .await
+    }
+
+    /// Refreshes an access token that expired mid-session
+    async fn refresh_access_token(&self) -> bool {
+        let Some(authorizer) = &self.authorizer else {
+            return false;
+        };
+        match authorizer.refresh().await {
+            Ok(token) => {
+                *self.access_token.lock().await = Some(token);
+                let _ = self.event_tx.send(McpClientEvent::Debug(
+                    "🔑 Access token refreshed".to_string()
+                )).await;
+                true
+            }
+            Err(e) => {
+                let _ = self.event_tx.send(McpClientEvent::Debug(
+                    format!("🔑 Token refresh failed: {}", e)
+                )).await;
+                false
+            }
+        }
+    }
+
+    /// Posts a request and registers a waiter for its response. The waiter
+    /// is in place before the POST so a reply racing it over SSE is not lost.
+    async fn send_awaiting(&self, request: Request) -> Result<oneshot::Receiver<Response>, McpError> {
+        let (tx, rx) = oneshot::channel::<Response>();
+        self.pending.lock().await.insert(request.id.clone(), tx);
+        match self.send_jsonrpc(&request).await {
+            Ok(()) => Ok(rx),
+            Err(e) => {
+                self.pending.lock().await.remove(&request.id);
+                Err(e)
+            }
+        }
+    }
+
+    /// Posts a request; its response is left to the default renderer
+    async fn send_jsonrpc(&self, request: &Request) -> Result<(), McpError> {
+        let payload = serde_json::to_value(request)
+            .map_err(|e| McpError::Transport(e.to_string()))?;
+        self.post_message(&payload).await
+    }
+
+    /// Posts any JSON-RPC message to the session endpoint
+    async fn post_message(&self, payload: &serde_json::Value) -> Result<(), McpError> {
+        if let Some(replay) = &self.replay {
+            record_traffic(&self.event_tx, TrafficDirection::Outgoing, payload, None).await;
+            replay.send(payload).await;
+            return Ok(());
+        }
+
         let base = match &self.base_url {
             Some(b) => b.clone(),
-            None => return Err("No base URL".into()),
+            None => return Err(McpError::NotConnected),
         };
 
         let ep = {
@@ -228,18 +509,14 @@ impl McpClient {
             join_url(&base, &endpoint_str)
         };
 
-        if let Some(id) = expect_id {
-            let (tx, _) = oneshot::channel::<serde_json::Value>();
-            self.pending.lock().await.insert(id, tx);
+        record_traffic(&self.event_tx, TrafficDirection::Outgoing, payload, None).await;
+        let mut resp = self.post_json(&url, payload).await;
+        if matches!(&resp, Ok(r) if r.status() == StatusCode::UNAUTHORIZED)
+            && self.refresh_access_token().await
+        {
+            resp = self.post_json(&url, payload).await;
         }
 
-        let resp = self.client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .body(payload.to_string())
-            .send()
-            .await;
-
         match resp {
             Ok(r) => {
                 let status = r.status();
@@ -252,33 +529,210 @@ impl McpClient {
                             format!("📄 Error body: {}", body)
                         )).await;
                     }
-                    Err(format!("POST HTTP error: {}", status))
+                    if status == StatusCode::UNAUTHORIZED {
+                        return Err(McpError::Unauthorized);
+                    }
+                    Err(McpError::Http(status))
                 }
             }
-            Err(e) => Err(format!("POST error: {}", e)),
+            Err(e) => Err(McpError::Transport(tls::describe_error(&e))),
         }
     }
 }
 
 // ═══════════════════════════════════════════════════════════════════
-// SSE LISTENER LOOP
+// SESSION CONTEXT
 // ═══════════════════════════════════════════════════════════════════
 
-async fn sse_listener_loop(
-    response: reqwest::Response,
+/// Shared handles the background SSE task needs for one session
+#[derive(Clone)]
+struct SessionContext {
     event_tx: mpsc::Sender<McpClientEvent>,
     client: Client,
     base_url: String,
     session_endpoint: Arc<Mutex<Option<String>>>,
-    pending: Arc<Mutex<HashMap<i64, oneshot::Sender<serde_json::Value>>>>,
+    pending: Arc<Mutex<HashMap<RequestId, oneshot::Sender<Response>>>>,
+    cancelled: Arc<Mutex<HashSet<RequestId>>>,
     next_id: Arc<AtomicI64>,
     available_tools: Arc<Mutex<Vec<ToolInfo>>>,
+    access_token: Arc<Mutex<Option<String>>>,
+    initialize_id: Arc<Mutex<Option<RequestId>>>,
+    replay: Option<ReplayTransport>,
+    auto_load_tools: bool,
+    headers: HeaderMap,
+}
+
+impl SessionContext {
+    async fn get_sse(&self) -> reqwest::Result<reqwest::Response> {
+        let token = self.access_token.lock().await.clone();
+        with_bearer(self.client.get(&self.base_url).headers(self.headers.clone()), token.as_deref())
+            .send()
+            .await
+    }
+
+    async fn post_json(&self, url: &str, payload: &serde_json::Value) -> reqwest::Result<reqwest::Response> {
+        let token = self.access_token.lock().await.clone();
+        with_bearer(self.client.post(url).headers(self.headers.clone()), token.as_deref())
+            .header("Content-Type", "application/json")
+            .body(payload.to_string())
+            .send()
+            .await
+    }
+
+    /// Sends a request nobody waits for; the reply arrives over SSE (or
+    /// from the replay server) and goes to the default renderer
+    async fn send_request(&self, request: Request) {
+        if let Ok(payload) = serde_json::to_value(&request) {
+            self.send_payload(&payload).await;
+        }
+    }
+
+    /// Answers a request the server sent to us
+    async fn reply(&self, response: Response) {
+        if let Ok(payload) = serde_json::to_value(&response) {
+            self.send_payload(&payload).await;
+        }
+    }
+
+    async fn send_payload(&self, payload: &serde_json::Value) {
+        if let Some(replay) = &self.replay {
+            record_traffic(&self.event_tx, TrafficDirection::Outgoing, payload, None).await;
+            replay.send(payload).await;
+            return;
+        }
+        let Some(endpoint) = self.session_endpoint.lock().await.clone() else {
+            return;
+        };
+        record_traffic(&self.event_tx, TrafficDirection::Outgoing, payload, None).await;
+        let _ = self.post_json(&join_url(&self.base_url, &endpoint), payload).await;
+    }
+}
+
+/// Local fake transport: outgoing messages go to a ReplayServer whose
+/// answers are queued for the replay loop
+#[derive(Debug, Clone)]
+struct ReplayTransport {
+    server: Arc<Mutex<ReplayServer>>,
+    incoming: mpsc::UnboundedSender<serde_json::Value>,
+}
+
+impl ReplayTransport {
+    async fn send(&self, payload: &serde_json::Value) {
+        let answers = self.server.lock().await.respond(payload);
+        for answer in answers {
+            let _ = self.incoming.send(answer);
+        }
+    }
+}
+
+/// Reports one message to the traffic inspector; `size` defaults to the
+/// compact serialized length
+async fn record_traffic(
+    event_tx: &mpsc::Sender<McpClientEvent>,
+    direction: TrafficDirection,
+    body: &serde_json::Value,
+    size: Option<usize>,
+) {
+    let size = size.unwrap_or_else(|| body.to_string().len());
+    let _ = event_tx.send(McpClientEvent::Traffic(
+        TrafficRecord::new(direction, body.clone(), size)
+    )).await;
+}
+
+/// Pure function: configured headers → HeaderMap (config validation
+/// already rejects invalid names and values)
+fn header_map(headers: &std::collections::BTreeMap<String, String>) -> Result<HeaderMap, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| format!("{}: {}", name, e))?;
+            let value = HeaderValue::from_str(value).map_err(|e| format!("{}: {}", name, e))?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn with_bearer(builder: reqwest::RequestBuilder, token: Option<&str>) -> reqwest::RequestBuilder {
+    match token {
+        Some(token) => builder.bearer_auth(token),
+        None => builder,
+    }
+}
+
+/// Opens the SSE stream, running the OAuth flow when the server answers 401
+async fn open_sse_stream(
+    ctx: &SessionContext,
+    authorizer: &Authorizer,
+) -> Result<reqwest::Response, String> {
+    let cached = authorizer.cached_token().await;
+    if cached.is_some() {
+        let _ = ctx.event_tx.send(McpClientEvent::Debug(
+            "🔑 Using cached access token".to_string()
+        )).await;
+    }
+    *ctx.access_token.lock().await = cached;
+
+    let mut response = ctx.get_sse().await
+        .map_err(|e| format!("Connect error: {}", tls::describe_error(&e)))?;
+    let _ = ctx.event_tx.send(McpClientEvent::Debug(
+        format!("📡 Initial response: HTTP {}", response.status())
+    )).await;
+
+    if response.status() == StatusCode::UNAUTHORIZED {
+        let challenge = response
+            .headers()
+            .get(reqwest::header::WWW_AUTHENTICATE)
+            .and_then(|h| h.to_str().ok())
+            .and_then(parse_www_authenticate)
+            .unwrap_or_default();
+
+        let _ = ctx.event_tx.send(McpClientEvent::Message(
+            "🔐 Server requires authorization - starting OAuth flow".to_string()
+        )).await;
+
+        let event_tx = ctx.event_tx.clone();
+        let token = authorizer
+            .authorize(&challenge, move |auth_url| {
+                let _ = event_tx.try_send(McpClientEvent::Message(
+                    format!("🌐 Complete authorization in your browser: {}", auth_url)
+                ));
+                open_browser(auth_url);
+            })
+            .await
+            .map_err(|e| format!("Authorization failed: {}", e))?;
+
+        let _ = ctx.event_tx.send(McpClientEvent::Message(
+            "✅ Authorization complete".to_string()
+        )).await;
+        *ctx.access_token.lock().await = Some(token);
+
+        response = ctx.get_sse().await
+            .map_err(|e| format!("Connect error: {}", tls::describe_error(&e)))?;
+        let _ = ctx.event_tx.send(McpClientEvent::Debug(
+            format!("📡 Authorized response: HTTP {}", response.status())
+        )).await;
+    }
+
+    if !response.status().is_success() {
+        return Err(format!("HTTP connect failed: {}", response.status()));
+    }
+
+    Ok(response)
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// SSE LISTENER LOOP
+// ═══════════════════════════════════════════════════════════════════
+
+async fn sse_listener_loop(
+    response: reqwest::Response,
+    ctx: SessionContext,
     mut shutdown_rx: oneshot::Receiver<()>,
 ) {
+    let event_tx = &ctx.event_tx;
     let mut stream = response.bytes_stream();
-    let mut buf = String::new();
+    let mut decoder = SseDecoder::new();
     let mut endpoint_received = false;
-    let mut initialized = false;
 
     let _ = event_tx.send(McpClientEvent::Debug(
         "📥 SSE listener loop started".to_string()
@@ -299,27 +753,10 @@ async fn sse_listener_loop(
             item = stream.next() => {
                 match item {
                     Some(Ok(chunk)) => {
-                        let txt = String::from_utf8_lossy(&chunk).to_string();
-                        buf.push_str(&txt);
-
                         // Przetwarzaj kompletne wiadomości SSE
-                        while let Some(split) = buf.find("\n\n") {
-                            let block = buf[..split].to_string();
-                            buf = buf[split + 2..].to_string();
-
-                            let mut event_type = String::new();
-                            let mut data = String::new();
-
-                            for line in block.lines() {
-                                if let Some(rest) = line.strip_prefix("event:") {
-                                    event_type = rest.trim().to_string();
-                                } else if let Some(rest) = line.strip_prefix("data:") {
-                                    if !data.is_empty() {
-                                        data.push('\n');
-                                    }
-                                    data.push_str(rest.trim());
-                                }
-                            }
+                        for event in decoder.feed(&chunk) {
+                            let event_type = event.event;
+                            let data = event.data;
 
                             if data.is_empty() {
                                 continue;
@@ -327,8 +764,9 @@ async fn sse_listener_loop(
 
                             // Obsługa endpointu
                             if event_type == "endpoint" && !endpoint_received {
+                                let data = data.trim().to_string();
                                 {
-                                    let mut lock = session_endpoint.lock().await;
+                                    let mut lock = ctx.session_endpoint.lock().await;
                                     *lock = Some(data.clone());
                                 }
                                 endpoint_received = true;
@@ -338,52 +776,19 @@ async fn sse_listener_loop(
                                 )).await;
 
                                 // Wysłanie initialize
-                                send_initialize(
-                                    &client,
-                                    &base_url,
-                                    &data,
-                                    &next_id,
-                                    &event_tx,
-                                ).await;
-                                
+                                send_initialize(&ctx).await;
+
                                 continue;
                             }
 
-                            // Parsowanie JSON-RPC
-                            if let Ok(v) = serde_json::from_str::<serde_json::Value>(&data) {
-                                // Sprawdź czy to odpowiedź na initialize
-                                if !initialized {
-                                    if let Some(id) = v.get("id").and_then(|i| i.as_i64()) {
-                                        if id == 1 && v.get("result").is_some() {
-                                            initialized = true;
-                                            let _ = event_tx.send(McpClientEvent::Message(
-                                                "✅ MCP session initialized".to_string()
-                                            )).await;
-
-                                            // Automatycznie pobierz listę narzędzi
-                                            auto_load_tools(
-                                                &client,
-                                                &base_url,
-                                                &session_endpoint,
-                                                &next_id,
-                                                &event_tx,
-                                            ).await;
-                                            continue;
-                                        }
-                                    }
-                                }
-
-                                handle_json_rpc_event(
-                                    v,
-                                    &event_tx,
-                                    &pending,
-                                    &available_tools,
-                                ).await;
-                            } else {
+                            // Parsowanie JSON-RPC (pojedyncza wiadomość lub batch)
+                            let Ok(value) = serde_json::from_str::<serde_json::Value>(&data) else {
                                 let _ = event_tx.send(
                                     McpClientEvent::Message(data.clone())
                                 ).await;
-                            }
+                                continue;
+                            };
+                            dispatch_incoming(value, Some(data.len()), &ctx).await;
                         }
                     }
 
@@ -412,271 +817,235 @@ async fn sse_listener_loop(
 }
 
 // ═══════════════════════════════════════════════════════════════════
-// AUTO-LOAD TOOLS
+// REPLAY LOOP
 // ═══════════════════════════════════════════════════════════════════
 
-async fn auto_load_tools(
-    client: &Client,
-    base_url: &str,
-    session_endpoint: &Arc<Mutex<Option<String>>>,
-    next_id: &Arc<AtomicI64>,
-    event_tx: &mpsc::Sender<McpClientEvent>,
+async fn replay_loop(
+    ctx: SessionContext,
+    mut incoming_rx: mpsc::UnboundedReceiver<serde_json::Value>,
+    mut shutdown_rx: oneshot::Receiver<()>,
 ) {
-    sleep(Duration::from_millis(100)).await;
+    let _ = ctx.event_tx.send(McpClientEvent::Connected).await;
+    let _ = ctx.event_tx.send(McpClientEvent::Debug(
+        format!("📼 Replay transport ready ({})", ctx.base_url)
+    )).await;
+    send_initialize(&ctx).await;
 
-    let ep = {
-        let lock = session_endpoint.lock().await;
-        lock.clone()
-    };
+    loop {
+        tokio::select! {
+            biased;
 
-    if let Some(endpoint) = ep {
-        let id = next_id.fetch_add(1, Ordering::SeqCst);
-        let req = json!({
-            "jsonrpc": "2.0",
-            "id": id,
-            "method": "tools/list",
-            "params": {}
-        });
+            _ = &mut shutdown_rx => {
+                let _ = ctx.event_tx.send(McpClientEvent::Disconnected).await;
+                break;
+            }
+
+            value = incoming_rx.recv() => {
+                match value {
+                    Some(value) => dispatch_incoming(value, None, &ctx).await,
+                    None => break,
+                }
+            }
+        }
+    }
+}
 
-        let full_url = join_url(base_url, &endpoint);
+// ═══════════════════════════════════════════════════════════════════
+// INCOMING DISPATCH
+// ═══════════════════════════════════════════════════════════════════
 
-        let _ = event_tx.send(McpClientEvent::Debug(
-            "🔄 Auto loading tools...".to_string()
-        )).await;
+/// Records a decoded message (or batch) and routes each element
+async fn dispatch_incoming(value: serde_json::Value, size: Option<usize>, ctx: &SessionContext) {
+    let event_tx = &ctx.event_tx;
 
-        let _ = client
-            .post(&full_url)
-            .header("Content-Type", "application/json")
-            .body(req.to_string())
-            .send()
-            .await;
+    match &value {
+        serde_json::Value::Array(items) => {
+            for item in items {
+                record_traffic(event_tx, TrafficDirection::Incoming, item, None).await;
+            }
+        }
+        single => {
+            record_traffic(event_tx, TrafficDirection::Incoming, single, size).await;
+        }
+    }
+
+    let messages = jsonrpc::parse_value(value).unwrap_or_else(|e| vec![Err(e)]);
+
+    for message in messages {
+        let message = match message {
+            Ok(message) => message,
+            Err(e) => {
+                let _ = event_tx.send(McpClientEvent::Error(
+                    format!("Malformed JSON-RPC message: {}", e.message)
+                )).await;
+                continue;
+            }
+        };
+
+        // Sprawdź czy to odpowiedź na initialize
+        if let Message::Response(resp) = &message {
+            let is_initialize = {
+                let mut init_id = ctx.initialize_id.lock().await;
+                let matched = resp.id.is_some() && *init_id == resp.id && resp.result.is_some();
+                if matched {
+                    *init_id = None;
+                }
+                matched
+            };
+            if is_initialize {
+                let _ = event_tx.send(McpClientEvent::Message(
+                    "✅ MCP session initialized".to_string()
+                )).await;
+                let _ = event_tx.send(McpClientEvent::Initialized(
+                    resp.result.clone().unwrap_or_default()
+                )).await;
+
+                // Automatycznie pobierz listę narzędzi
+                if ctx.auto_load_tools {
+                    auto_load_tools(ctx).await;
+                }
+                continue;
+            }
+        }
+
+        handle_json_rpc_event(message, ctx).await;
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// AUTO-LOAD TOOLS
+// ═══════════════════════════════════════════════════════════════════
+
+async fn auto_load_tools(ctx: &SessionContext) {
+    if ctx.replay.is_none() {
+        sleep(Duration::from_millis(100)).await;
     }
+
+    let id = ctx.next_id.fetch_add(1, Ordering::SeqCst);
+    let req = Request::new(id, "tools/list", json!({}));
+
+    let _ = ctx.event_tx.send(McpClientEvent::Debug(
+        "🔄 Auto loading tools...".to_string()
+    )).await;
+
+    ctx.send_request(req).await;
 }
 
 // ═══════════════════════════════════════════════════════════════════
 // INITIALIZE REQUEST
 // ═══════════════════════════════════════════════════════════════════
 
-async fn send_initialize(
-    client: &Client,
-    base_url: &str,
-    endpoint: &str,
-    next_id: &Arc<AtomicI64>,
-    event_tx: &mpsc::Sender<McpClientEvent>,
-) {
-    sleep(Duration::from_millis(100)).await;
-
-    let id = next_id.fetch_add(1, Ordering::SeqCst);
-    let init = json!({
-        "jsonrpc": "2.0",
-        "id": id,
-        "method": "initialize",
-        "params": {
-            "protocolVersion": "2024-11-05",
-            "capabilities": {},
-            "clientInfo": {
-                "name": "mcp-client",
-                "version": "0.1.0"
-            }
-        }
-    });
+async fn send_initialize(ctx: &SessionContext) {
+    if ctx.replay.is_none() {
+        sleep(Duration::from_millis(100)).await;
+    }
 
-    let full_url = join_url(base_url, endpoint);
+    let id = ctx.next_id.fetch_add(1, Ordering::SeqCst);
+    *ctx.initialize_id.lock().await = Some(RequestId::Number(id));
+    let init = Request::new(id, "initialize", json!({
+        "protocolVersion": "2024-11-05",
+        "capabilities": {},
+        "clientInfo": {
+            "name": "mcp-client",
+            "version": "0.1.0"
+        }
+    }));
 
-    let _ = event_tx.send(McpClientEvent::Debug(
-        format!("📤 Sending initialize to: {}", full_url)
+    let _ = ctx.event_tx.send(McpClientEvent::Debug(
+        format!("📤 Sending initialize (id={})", id)
     )).await;
 
-    let _ = client.post(&full_url)
-        .header("Content-Type", "application/json")
-        .body(init.to_string())
-        .send()
-        .await;
+    ctx.send_request(init).await;
 }
 
 // ═══════════════════════════════════════════════════════════════════
 // JSON-RPC EVENT HANDLER
 // ═══════════════════════════════════════════════════════════════
 
-async fn handle_json_rpc_event(
-    v: serde_json::Value,
-    event_tx: &mpsc::Sender<McpClientEvent>,
-    pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<serde_json::Value>>>>,
-    available_tools: &Arc<Mutex<Vec<ToolInfo>>>,
-) {
-    // Handle responses (with id)
-    if let Some(id) = v.get("id").and_then(|v| v.as_i64()) {
-
-
-        // let mut pending_guard = pending.lock().await;
-        // if let Some(tx) = pending_guard.remove(&id) {
-
-
-            if let Some(result) = v.get("result") {
-            // Special handling for tools/list
-                if let Some(tools) = result.get("tools") {
-                    if let Some(tools_array) = tools.as_array() {
-                        let tool_infos: Vec<ToolInfo> = tools_array
-                            .iter()
-                            .filter_map(|t| {
-                                Some(ToolInfo {
-                                    description: t.get("description")?.as_str()?.to_string(),
-                                    input_schema: t.get("inputSchema")?.clone(),
-                                    name: t.get("name")?.as_str()?.to_string(),
-                                })
-                            })
-                            .collect();
-
-                        if !tool_infos.is_empty() {
-                            // Store tools in memory
-                            {
-                                let mut tools_lock = available_tools.lock().await;
-                                *tools_lock = tool_infos.clone();
-                            }
-
-                            let _ = event_tx.send(McpClientEvent::Debug(
-                                format!("✅ Stored {} tools in client memory", tool_infos.len())
-                            )).await;
+async fn handle_json_rpc_event(message: Message, ctx: &SessionContext) {
+    let event_tx = &ctx.event_tx;
 
-                            let _ = event_tx.send(
-                                McpClientEvent::ToolsListed(tool_infos)
-                            ).await;
-                            return;
-                        }
-                    }
+    match message {
+        Message::Response(resp) => {
+            if let Some(id) = &resp.id {
+                if ctx.cancelled.lock().await.remove(id) {
+                    let _ = event_tx.send(McpClientEvent::Debug(
+                        format!("Ignoring late response to cancelled request #{}", id)
+                    )).await;
+                    return;
                 }
+            }
 
-                // FIXED: Handle tools/call result with formatted JSON
-                if let Some(content) = result.get("content") {
-                    if let Some(content_array) = content.as_array() {
-                        for item in content_array {
-                            if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
-                            // Try to parse as JSON for pretty formatting
-                            if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(text) {
-                                let formatted = format_json_safely(&json_value).await;
-                                
-                                // FIXED: Truncate large responses
-                                let (display_text, truncated) = truncate_json_display(&formatted, 200);
-                                
-                                let _ = event_tx.send(McpClientEvent::Message(
-                                    "📋 Tool result:".to_string()
-                                )).await;
-                                
-                                // CRITICAL FIX: Split by lines and send separately
-                                for line in display_text.lines() {
-                                    let _ = event_tx.send(McpClientEvent::Message(
-                                        line.to_string()
-                                    )).await;
-                                }
-                                
-                                if truncated {
-                                    let total_lines = formatted.lines().count();
-                                    let _ = event_tx.send(McpClientEvent::Message(
-                                        "".to_string()
-                                    )).await;
-                                    let _ = event_tx.send(McpClientEvent::Message(
-                                        format!("⚠️  Response truncated: showing 200 of {} lines", total_lines)
-                                    )).await;
-                                    let _ = event_tx.send(McpClientEvent::Message(
-                                        "💡 Full response may cause UI performance issues".to_string()
-                                    )).await;
-                                }
-                            } else {
-                                // Not JSON - plain text
-                                let lines: Vec<&str> = text.lines().collect();
-                                
-                                let _ = event_tx.send(McpClientEvent::Message(
-                                    "📋 Tool result:".to_string()
-                                )).await;
-                                
-                                // CRITICAL FIX: Send line by line
-                                let max_lines = 200;
-                                for (i, line) in lines.iter().enumerate() {
-                                    if i >= max_lines {
-                                        break;
-                                    }
-                                    let _ = event_tx.send(McpClientEvent::Message(
-                                        line.to_string()
-                                    )).await;
-                                }
-                                
-                                if lines.len() > max_lines {
-                                    let _ = event_tx.send(McpClientEvent::Message(
-                                        format!("\n⚠️  Output truncated: {} of {} lines shown", 
-                                                max_lines, lines.len())
-                                    )).await;
-                                }
-                            }
-                        }
-                    }
+            // A caller awaiting this response renders it itself
+            if let Some(id) = &resp.id {
+                let waiter = ctx.pending.lock().await.remove(id);
+                if let Some(tx) = waiter {
+                    if tx.send(resp.clone()).is_ok() {
                         return;
                     }
                 }
-
-            // ═══════════════════════════════════════════════════════════════
-            // FIX: Generic result - send line by line
-            // ═══════════════════════════════════════════════════════════════
-            let formatted = format_json_safely(result).await;
-            let (display_text, truncated) = truncate_json_display(&formatted, 200);
-            
-            // CRITICAL FIX: Split by lines
-            for line in display_text.lines() {
-                let _ = event_tx.send(McpClientEvent::Message(
-                    line.to_string()
-                )).await;
             }
-            
-            if truncated {
-                let total_lines = formatted.lines().count();
-                let _ = event_tx.send(McpClientEvent::Message(
-                    "".to_string()
-                )).await;
-                let _ = event_tx.send(McpClientEvent::Message(
-                    format!("⚠️  Response truncated: showing 200 of {} lines", total_lines)
-                )).await;
+
+            if let Some(result) = &resp.result {
+                if store_tools(result, event_tx, &ctx.available_tools).await {
+                    return;
+                }
             }
-            
-            } else if let Some(error) = v.get("error") {
-            // ═══════════════════════════════════════════════════════════════
-            // FIX: Error response - send line by line
-            // ═══════════════════════════════════════════════════════════════
-            let formatted = format_json_safely(error).await;
-            
-            let _ = event_tx.send(McpClientEvent::Error(
-                "RPC error:".to_string()
+            let _ = event_tx.send(McpClientEvent::Response(resp)).await;
+        }
+
+        Message::Notification(notification) => {
+            let _ = event_tx.send(McpClientEvent::Notification(notification)).await;
+        }
+
+        // Server → client requests: answer ping, reject everything else
+        Message::Request(req) => {
+            let _ = event_tx.send(McpClientEvent::Debug(
+                format!("📥 Server request: {} (id={})", req.method, req.id)
             )).await;
-            
-            // CRITICAL FIX: Split by lines
-            for line in formatted.lines() {
-                let _ = event_tx.send(McpClientEvent::Error(
-                    line.to_string()
-                )).await;
-            }
-            }
-            return;
+            let response = match req.method.as_str() {
+                "ping" => Response::success(req.id, json!({})),
+                _ => Response::failure(
+                    Some(req.id),
+                    ErrorObject::new(jsonrpc::METHOD_NOT_FOUND, format!("{} is not supported", req.method)),
+                ),
+            };
+            ctx.reply(response).await;
+        }
     }
+}
 
-    // Handle notifications (no id)
-    if let Some(method) = v.get("method").and_then(|m| m.as_str()) {
-        match method {
-            "notifications/tools/list_changed" => {
-                let _ = event_tx.send(McpClientEvent::Message(
-                    "🔔 Tools list changed - use :mcp tools to refresh".to_string()
-                )).await;
-            }
-            _ => {
-                let _ = event_tx.send(McpClientEvent::Message(
-                    format!("🔔 Notification: {}", method)
-                )).await;
-            }
-        }
-        return;
+/// A tools/list result updates the tool cache and is announced as
+/// `ToolsListed`; returns false for any other result
+async fn store_tools(
+    result: &serde_json::Value,
+    event_tx: &mpsc::Sender<McpClientEvent>,
+    available_tools: &Arc<Mutex<Vec<ToolInfo>>>,
+) -> bool {
+    let Some(tools_array) = result.get("tools").and_then(|t| t.as_array()) else {
+        return false;
+    };
+    let tool_infos: Vec<ToolInfo> = tools_array
+        .iter()
+        .filter_map(|t| {
+            Some(ToolInfo {
+                description: t.get("description")?.as_str()?.to_string(),
+                input_schema: t.get("inputSchema")?.clone(),
+                name: t.get("name")?.as_str()?.to_string(),
+                annotations: t.get("annotations").cloned().unwrap_or_default(),
+            })
+        })
+        .collect();
+    if tool_infos.is_empty() {
+        return false;
     }
 
-    // Fallback: wyświetl surowy JSON
-    // let _ = event_tx.send(McpClientEvent::Message(
-    //     serde_json::to_string_pretty(&v).unwrap_or_default()
-    // )).await;
+    *available_tools.lock().await = tool_infos.clone();
+    let _ = event_tx.send(McpClientEvent::Debug(
+        format!("✅ Stored {} tools in client memory", tool_infos.len())
+    )).await;
+    let _ = event_tx.send(McpClientEvent::ToolsListed(tool_infos)).await;
+    true
 }
 
 // ═══════════════════════════════════════════════════════════════
@@ -762,4 +1131,67 @@ mod url_tests {
             "http://localhost:8080/messages"
         );
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code_mapping() {
+        let err = McpError::from(ErrorObject::new(-32601, "tools/nope"));
+        assert_eq!(err, McpError::MethodNotFound("tools/nope".into()));
+        assert_eq!(err.to_string(), "Method not found (-32601): tools/nope");
+
+        assert!(matches!(McpError::from(ErrorObject::new(-32700, "x")), McpError::Parse(_)));
+        assert!(matches!(McpError::from(ErrorObject::new(-32602, "x")), McpError::InvalidParams(_)));
+        assert!(matches!(
+            McpError::from(ErrorObject::new(-32001, "x")),
+            McpError::Server { code: -32001, .. }
+        ));
+        assert!(matches!(
+            McpError::from(ErrorObject::new(42, "x")),
+            McpError::Application { code: 42, .. }
+        ));
+        assert_eq!(McpError::from(ErrorObject::new(-32603, "x")).code(), Some(-32603));
+        assert_eq!(McpError::NotConnected.code(), None);
+    }
+}
+
+#[cfg(test)]
+mod tool_tests {
+    use super::*;
+
+    fn tool(annotations: serde_json::Value) -> ToolInfo {
+        ToolInfo { name: "t".into(), description: String::new(), input_schema: json!({}), annotations }
+    }
+
+    #[test]
+    fn test_destructive_hint_defaults() {
+        assert!(tool(serde_json::Value::Null).is_destructive());
+        assert!(!tool(json!({"readOnlyHint": true})).is_destructive());
+        assert!(!tool(json!({"destructiveHint": false})).is_destructive());
+        assert!(tool(json!({"readOnlyHint": false, "idempotentHint": true})).is_destructive());
+    }
+
+    #[test]
+    fn test_header_map() {
+        let headers = std::collections::BTreeMap::from([("X-Env".to_string(), "prod".to_string())]);
+        assert_eq!(header_map(&headers).unwrap()["x-env"], "prod");
+        let bad = std::collections::BTreeMap::from([("bad name".to_string(), "x".to_string())]);
+        assert!(header_map(&bad).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connect_forgets_cancelled_requests() {
+        let (tx, _rx) = mpsc::channel(100);
+        let mut client = McpClient::new(tx);
+        client.cancel(RequestId::Number(7), "timed out".into()).await;
+        assert_eq!(client.cancelled.lock().await.len(), 1);
+
+        let replay = crate::recording::ReplayServer::new(&crate::recording::Recording::default());
+        client.connect_replay("empty", replay).await;
+        assert!(client.cancelled.lock().await.is_empty());
+        assert!(client.pending.lock().await.is_empty());
+    }
+}
@@ -0,0 +1,1097 @@
+// ============================================================================
+// src/mock.rs - Scriptable mock MCP server (SSE, Streamable HTTP, stdio)
+// ============================================================================
+
+use crate::jsonrpc::{
+    ErrorObject, Message, Notification, Response, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND,
+    PARSE_ERROR,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+/// MCP error code for an unknown resource URI
+pub const RESOURCE_NOT_FOUND: i64 = -32002;
+
+#[derive(Debug, Error)]
+pub enum MockError {
+    #[error("cannot read fixture {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("invalid fixture {path}: {reason}")]
+    Parse { path: PathBuf, reason: String },
+    #[error("cannot bind {addr}: {source}")]
+    Bind { addr: String, source: std::io::Error },
+    #[error("stdio transport failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// FIXTURE MODEL
+// ═══════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerInfo {
+    pub name: String,
+    pub version: String,
+    #[serde(alias = "protocolVersion")]
+    pub protocol_version: String,
+    pub instructions: Option<String>,
+}
+
+impl Default for ServerInfo {
+    fn default() -> Self {
+        Self {
+            name: "mock-mcp-server".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_version: "2024-11-05".to_string(),
+            instructions: None,
+        }
+    }
+}
+
+/// JSON-RPC error injected instead of a result
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockErrorReply {
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub data: Option<Value>,
+}
+
+impl MockErrorReply {
+    fn to_error_object(&self) -> ErrorObject {
+        ErrorObject { code: self.code, message: self.message.clone(), data: self.data.clone() }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockNotification {
+    /// Delay before sending (scheduled notifications only)
+    #[serde(default, alias = "afterMs")]
+    pub after_ms: u64,
+    pub method: String,
+    #[serde(default)]
+    pub params: Option<Value>,
+}
+
+impl MockNotification {
+    fn to_value(&self) -> Value {
+        serde_json::to_value(Notification::new(self.method.clone(), self.params.clone()))
+            .unwrap_or(Value::Null)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "empty_schema", alias = "inputSchema")]
+    pub input_schema: Value,
+    /// Listed as-is, e.g. `{ readOnlyHint = true }`
+    #[serde(default)]
+    pub annotations: Option<Value>,
+    /// Text content returned by tools/call; `{{arg}}` is substituted
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Raw tools/call result (takes precedence over `text`)
+    #[serde(default)]
+    pub result: Option<Value>,
+    #[serde(default, alias = "isError")]
+    pub is_error: bool,
+    #[serde(default, alias = "delayMs")]
+    pub delay_ms: u64,
+    #[serde(default)]
+    pub error: Option<MockErrorReply>,
+    /// Sent before the result, e.g. progress updates
+    #[serde(default)]
+    pub notifications: Vec<MockNotification>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockResource {
+    pub uri: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, alias = "mimeType")]
+    pub mime_type: Option<String>,
+    #[serde(default)]
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockPromptArgument {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockPromptMessage {
+    #[serde(default = "user_role")]
+    pub role: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockPrompt {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<MockPromptArgument>,
+    #[serde(default)]
+    pub messages: Vec<MockPromptMessage>,
+}
+
+/// Applied to matching requests before normal handling. Without `code`
+/// the fault only adds latency.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockFault {
+    /// Method name, or "*" for every request
+    pub method: String,
+    #[serde(default)]
+    pub code: Option<i64>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default, alias = "delayMs")]
+    pub delay_ms: u64,
+    /// How many times the fault fires (unlimited when absent)
+    #[serde(default)]
+    pub times: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Fixture {
+    pub server: ServerInfo,
+    pub tools: Vec<MockTool>,
+    pub resources: Vec<MockResource>,
+    pub prompts: Vec<MockPrompt>,
+    pub faults: Vec<MockFault>,
+    /// Sent after initialize (SSE and stdio only)
+    pub notifications: Vec<MockNotification>,
+}
+
+fn empty_schema() -> Value {
+    json!({"type": "object", "properties": {}})
+}
+
+fn user_role() -> String {
+    "user".to_string()
+}
+
+impl Fixture {
+    /// The bundled fixture (fixtures/demo.toml)
+    pub fn demo() -> Self {
+        Self::from_toml(include_str!("../fixtures/demo.toml")).expect("bundled fixture is valid")
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// Loads a fixture, choosing TOML or JSON by file extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MockError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|source| MockError::Read { path: path.to_path_buf(), source })?;
+        let is_toml = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        let parsed = if is_toml { Self::from_toml(&text) } else { Self::from_json(&text) };
+        parsed.map_err(|reason| MockError::Parse { path: path.to_path_buf(), reason })
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// SERVER CORE (transport independent)
+// ═══════════════════════════════════════════════════════════════════
+
+/// What to send back for one incoming message
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MockReply {
+    /// Sent immediately
+    pub notifications: Vec<Value>,
+    /// Wait before sending `response`
+    pub delay: Duration,
+    pub response: Option<Value>,
+    /// Sent on the session stream after the given delay
+    pub scheduled: Vec<(Duration, Value)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockServer {
+    fixture: Fixture,
+    fault_hits: Vec<u32>,
+    received: Vec<Value>,
+}
+
+impl MockServer {
+    pub fn new(fixture: Fixture) -> Self {
+        let fault_hits = vec![0; fixture.faults.len()];
+        Self { fixture, fault_hits, received: Vec::new() }
+    }
+
+    pub fn fixture(&self) -> &Fixture {
+        &self.fixture
+    }
+
+    /// Every message received so far, in order (batches are flattened)
+    pub fn received(&self) -> &[Value] {
+        &self.received
+    }
+
+    /// Handles a message or a batch. Batch responses are collected
+    /// into one array; the longest delay wins.
+    pub fn handle(&mut self, value: Value) -> MockReply {
+        match value {
+            Value::Array(items) if items.is_empty() => MockReply {
+                response: Some(error_value(None, ErrorObject::new(INVALID_REQUEST, "empty batch"))),
+                ..MockReply::default()
+            },
+            Value::Array(items) => {
+                let mut batch = MockReply::default();
+                let mut responses = Vec::new();
+                for item in items {
+                    let reply = self.handle_single(item);
+                    batch.notifications.extend(reply.notifications);
+                    batch.scheduled.extend(reply.scheduled);
+                    batch.delay = batch.delay.max(reply.delay);
+                    responses.extend(reply.response);
+                }
+                batch.response = (!responses.is_empty()).then_some(Value::Array(responses));
+                batch
+            }
+            single => self.handle_single(single),
+        }
+    }
+
+    fn handle_single(&mut self, value: Value) -> MockReply {
+        self.received.push(value.clone());
+
+        let request = match Message::from_value(value) {
+            Ok(Message::Request(request)) => request,
+            // Notifications and replies to our own requests need no answer
+            Ok(_) => return MockReply::default(),
+            Err(e) => {
+                return MockReply { response: Some(error_value(None, e)), ..MockReply::default() };
+            }
+        };
+
+        let mut delay = Duration::ZERO;
+        if let Some(fault) = self.take_fault(&request.method) {
+            delay = Duration::from_millis(fault.delay_ms);
+            if let Some(code) = fault.code {
+                let message = fault.message.unwrap_or_else(|| "Injected fault".to_string());
+                return MockReply {
+                    delay,
+                    response: Some(error_value(Some(request.id), ErrorObject::new(code, message))),
+                    ..MockReply::default()
+                };
+            }
+        }
+
+        let params = request.params.unwrap_or(Value::Null);
+        let mut reply = MockReply::default();
+        let outcome = match request.method.as_str() {
+            "initialize" => {
+                reply.scheduled = self
+                    .fixture
+                    .notifications
+                    .iter()
+                    .map(|n| (Duration::from_millis(n.after_ms), n.to_value()))
+                    .collect();
+                Ok(self.initialize_result())
+            }
+            "ping" | "logging/setLevel" => Ok(json!({})),
+            "tools/list" => Ok(self.tools_list()),
+            "tools/call" => self.call_tool(&params, &mut reply),
+            "resources/list" => Ok(self.resources_list()),
+            "resources/templates/list" => Ok(json!({"resourceTemplates": []})),
+            "resources/read" => self.read_resource(&params),
+            "prompts/list" => Ok(self.prompts_list()),
+            "prompts/get" => self.get_prompt(&params),
+            other => Err(ErrorObject::new(METHOD_NOT_FOUND, format!("Method not found: {}", other))),
+        };
+
+        reply.delay += delay;
+        reply.response = Some(match outcome {
+            Ok(result) => serde_json::to_value(Response::success(request.id, result)).unwrap_or(Value::Null),
+            Err(error) => error_value(Some(request.id), error),
+        });
+        reply
+    }
+
+    fn take_fault(&mut self, method: &str) -> Option<MockFault> {
+        let index = self.fixture.faults.iter().enumerate().position(|(i, f)| {
+            (f.method == method || f.method == "*")
+                && f.times.is_none_or(|times| self.fault_hits[i] < times)
+        })?;
+        self.fault_hits[index] += 1;
+        Some(self.fixture.faults[index].clone())
+    }
+
+    fn initialize_result(&self) -> Value {
+        let server = &self.fixture.server;
+        let mut result = json!({
+            "protocolVersion": server.protocol_version,
+            "capabilities": {
+                "tools": {"listChanged": false},
+                "resources": {"subscribe": false, "listChanged": false},
+                "prompts": {"listChanged": false},
+                "logging": {}
+            },
+            "serverInfo": {"name": server.name, "version": server.version}
+        });
+        if let Some(instructions) = &server.instructions {
+            result["instructions"] = json!(instructions);
+        }
+        result
+    }
+
+    fn tools_list(&self) -> Value {
+        let tools: Vec<Value> = self
+            .fixture
+            .tools
+            .iter()
+            .map(|t| {
+                let mut tool = json!({"name": t.name, "description": t.description, "inputSchema": t.input_schema});
+                if let Some(annotations) = &t.annotations {
+                    tool["annotations"] = annotations.clone();
+                }
+                tool
+            })
+            .collect();
+        json!({ "tools": tools })
+    }
+
+    fn call_tool(&self, params: &Value, reply: &mut MockReply) -> Result<Value, ErrorObject> {
+        let name = params.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+        let tool = self
+            .fixture
+            .tools
+            .iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| ErrorObject::new(INVALID_PARAMS, format!("Unknown tool: {}", name)))?;
+        let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+
+        let missing: Vec<&str> = tool
+            .input_schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.as_str())
+            .filter(|r| arguments.get(r).is_none())
+            .collect();
+        if !missing.is_empty() {
+            return Err(ErrorObject::new(
+                INVALID_PARAMS,
+                format!("Missing required argument(s) for {}: {}", name, missing.join(", ")),
+            ));
+        }
+
+        reply.notifications = tool.notifications.iter().map(MockNotification::to_value).collect();
+        reply.delay = Duration::from_millis(tool.delay_ms);
+
+        if let Some(error) = &tool.error {
+            return Err(error.to_error_object());
+        }
+        if let Some(result) = &tool.result {
+            return Ok(result.clone());
+        }
+        let text = match &tool.text {
+            Some(template) => substitute(template, &arguments),
+            None => serde_json::to_string_pretty(&arguments).unwrap_or_default(),
+        };
+        Ok(json!({
+            "content": [{"type": "text", "text": text}],
+            "isError": tool.is_error
+        }))
+    }
+
+    fn resources_list(&self) -> Value {
+        let resources: Vec<Value> = self
+            .fixture
+            .resources
+            .iter()
+            .map(|r| {
+                let mut entry = json!({"uri": r.uri, "name": r.name});
+                if let Some(description) = &r.description {
+                    entry["description"] = json!(description);
+                }
+                if let Some(mime_type) = &r.mime_type {
+                    entry["mimeType"] = json!(mime_type);
+                }
+                entry
+            })
+            .collect();
+        json!({ "resources": resources })
+    }
+
+    fn read_resource(&self, params: &Value) -> Result<Value, ErrorObject> {
+        let uri = params.get("uri").and_then(|u| u.as_str()).unwrap_or_default();
+        let resource = self.fixture.resources.iter().find(|r| r.uri == uri).ok_or_else(|| ErrorObject {
+            code: RESOURCE_NOT_FOUND,
+            message: "Resource not found".to_string(),
+            data: Some(json!({ "uri": uri })),
+        })?;
+        let mut content = json!({"uri": resource.uri, "text": resource.text});
+        if let Some(mime_type) = &resource.mime_type {
+            content["mimeType"] = json!(mime_type);
+        }
+        Ok(json!({ "contents": [content] }))
+    }
+
+    fn prompts_list(&self) -> Value {
+        let prompts: Vec<Value> = self
+            .fixture
+            .prompts
+            .iter()
+            .map(|p| {
+                let arguments: Vec<Value> = p
+                    .arguments
+                    .iter()
+                    .map(|a| json!({"name": a.name, "description": a.description, "required": a.required}))
+                    .collect();
+                json!({"name": p.name, "description": p.description, "arguments": arguments})
+            })
+            .collect();
+        json!({ "prompts": prompts })
+    }
+
+    fn get_prompt(&self, params: &Value) -> Result<Value, ErrorObject> {
+        let name = params.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+        let prompt = self
+            .fixture
+            .prompts
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| ErrorObject::new(INVALID_PARAMS, format!("Unknown prompt: {}", name)))?;
+        let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+
+        if let Some(missing) = prompt.arguments.iter().find(|a| a.required && arguments.get(&a.name).is_none()) {
+            return Err(ErrorObject::new(
+                INVALID_PARAMS,
+                format!("Missing required argument for {}: {}", name, missing.name),
+            ));
+        }
+
+        let messages: Vec<Value> = prompt
+            .messages
+            .iter()
+            .map(|m| json!({"role": m.role, "content": {"type": "text", "text": substitute(&m.text, &arguments)}}))
+            .collect();
+        Ok(json!({"description": prompt.description, "messages": messages}))
+    }
+}
+
+fn error_value(id: Option<crate::jsonrpc::RequestId>, error: ErrorObject) -> Value {
+    serde_json::to_value(Response::failure(id, error)).unwrap_or(Value::Null)
+}
+
+/// Pure function: template × arguments → text with `{{name}}` replaced
+pub fn substitute(template: &str, arguments: &Value) -> String {
+    let Some(arguments) = arguments.as_object() else {
+        return template.to_string();
+    };
+    arguments.iter().fold(template.to_string(), |text, (key, value)| {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        text.replace(&format!("{{{{{}}}}}", key), &value)
+    })
+}
+
+/// Applies a reply to an outgoing stream: notifications first, then the
+/// response after its delay. Scheduled notifications run detached.
+async fn deliver(server: Arc<Mutex<MockServer>>, message: Value, out: mpsc::UnboundedSender<Value>) {
+    let reply = server.lock().await.handle(message);
+
+    for (after, notification) in reply.scheduled {
+        let out = out.clone();
+        tokio::spawn(async move {
+            sleep(after).await;
+            let _ = out.send(notification);
+        });
+    }
+    for notification in reply.notifications {
+        let _ = out.send(notification);
+    }
+    if !reply.delay.is_zero() {
+        sleep(reply.delay).await;
+    }
+    if let Some(response) = reply.response {
+        let _ = out.send(response);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// HTTP TRANSPORTS
+// ═══════════════════════════════════════════════════════════════════
+//
+//   GET  /sse                    legacy SSE stream, first event is `endpoint`
+//   POST /messages?session_id=X  202 Accepted, reply arrives on the stream
+//   POST /mcp                    Streamable HTTP: JSON or an SSE body
+//   DELETE /mcp                  ends a Streamable HTTP session
+
+#[derive(Debug, Default)]
+struct Sessions {
+    sse: HashMap<String, mpsc::UnboundedSender<Value>>,
+    streamable: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Shared {
+    server: Arc<Mutex<MockServer>>,
+    sessions: Arc<Mutex<Sessions>>,
+}
+
+/// A running HTTP mock; the listener stops when the handle is dropped
+#[derive(Debug)]
+pub struct MockHandle {
+    addr: SocketAddr,
+    server: Arc<Mutex<MockServer>>,
+    task: JoinHandle<()>,
+}
+
+impl MockHandle {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// URL for the legacy SSE transport (what `McpClient` speaks)
+    pub fn sse_url(&self) -> String {
+        format!("{}/sse", self.base_url())
+    }
+
+    /// URL for the Streamable HTTP transport
+    pub fn http_url(&self) -> String {
+        format!("{}/mcp", self.base_url())
+    }
+
+    /// Messages received over any transport so far
+    pub async fn received(&self) -> Vec<Value> {
+        self.server.lock().await.received().to_vec()
+    }
+
+    /// Methods of the received messages, in order
+    pub async fn received_methods(&self) -> Vec<String> {
+        self.received()
+            .await
+            .iter()
+            .filter_map(|m| m.get("method").and_then(|m| m.as_str()).map(str::to_string))
+            .collect()
+    }
+
+    /// Runs until the listener fails (used by the binary)
+    pub async fn wait(mut self) {
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Drop for MockHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Starts the HTTP transports on an ephemeral localhost port
+pub async fn spawn(fixture: Fixture) -> Result<MockHandle, MockError> {
+    bind(fixture, "127.0.0.1:0").await
+}
+
+/// Starts the HTTP transports on `addr`
+pub async fn bind(fixture: Fixture, addr: &str) -> Result<MockHandle, MockError> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|source| MockError::Bind { addr: addr.to_string(), source })?;
+    let local = listener
+        .local_addr()
+        .map_err(|source| MockError::Bind { addr: addr.to_string(), source })?;
+
+    let shared = Shared {
+        server: Arc::new(Mutex::new(MockServer::new(fixture))),
+        sessions: Arc::new(Mutex::new(Sessions::default())),
+    };
+    let server = shared.server.clone();
+
+    let task = tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handle_connection(stream, shared.clone()));
+        }
+    });
+
+    Ok(MockHandle { addr: local, server, task })
+}
+
+#[derive(Debug)]
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v)
+    }
+}
+
+async fn read_http_request(stream: &mut TcpStream) -> Option<HttpRequest> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut parts = lines.next()?.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+
+    let content_length = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.parse::<usize>().ok())
+        .unwrap_or(0);
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    Some(HttpRequest {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body: buf[header_end..].to_vec(),
+    })
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, headers: &[(&str, String)], body: &str) {
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        body.len()
+    );
+    for (k, v) in headers {
+        response.push_str(&format!("{}: {}\r\n", k, v));
+    }
+    response.push_str("\r\n");
+    response.push_str(body);
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Pure function: SSE event → wire format
+fn sse_event(event: &str, data: &str) -> String {
+    format!("event: {}\ndata: {}\n\n", event, data)
+}
+
+async fn write_chunk(stream: &mut TcpStream, data: &str) -> std::io::Result<()> {
+    stream
+        .write_all(format!("{:x}\r\n{}\r\n", data.len(), data).as_bytes())
+        .await?;
+    stream.flush().await
+}
+
+async fn handle_connection(mut stream: TcpStream, shared: Shared) {
+    let Some(request) = read_http_request(&mut stream).await else {
+        return;
+    };
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/sse") => serve_sse_stream(stream, shared).await,
+        ("POST", "/messages") | ("POST", "/message") => {
+            handle_sse_post(&mut stream, request, shared).await
+        }
+        ("POST", "/mcp") => handle_streamable_post(&mut stream, request, shared).await,
+        ("DELETE", "/mcp") => {
+            let id = request.header("mcp-session-id").unwrap_or_default().to_string();
+            shared.sessions.lock().await.streamable.retain(|s| *s != id);
+            respond(&mut stream, "200 OK", &[], "").await;
+        }
+        ("GET", "/mcp") => respond(&mut stream, "405 Method Not Allowed", &[], "").await,
+        _ => respond(&mut stream, "404 Not Found", &[], "not found").await,
+    }
+}
+
+async fn serve_sse_stream(mut stream: TcpStream, shared: Shared) {
+    let session_id = uuid::Uuid::new_v4().simple().to_string();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    shared.sessions.lock().await.sse.insert(session_id.clone(), tx);
+
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nTransfer-Encoding: chunked\r\n\r\n";
+    let endpoint = sse_event("endpoint", &format!("/messages?session_id={}", session_id));
+    let opened = async {
+        stream.write_all(head.as_bytes()).await?;
+        write_chunk(&mut stream, &endpoint).await
+    };
+
+    if opened.await.is_ok() {
+        while let Some(message) = rx.recv().await {
+            if write_chunk(&mut stream, &sse_event("message", &message.to_string())).await.is_err() {
+                break;
+            }
+        }
+    }
+    shared.sessions.lock().await.sse.remove(&session_id);
+}
+
+async fn handle_sse_post(stream: &mut TcpStream, request: HttpRequest, shared: Shared) {
+    let session = request
+        .query_param("session_id")
+        .or_else(|| request.query_param("sessionId"))
+        .unwrap_or_default();
+    let Some(out) = shared.sessions.lock().await.sse.get(session).cloned() else {
+        respond(stream, "404 Not Found", &[], "unknown session").await;
+        return;
+    };
+
+    let Ok(message) = serde_json::from_slice::<Value>(&request.body) else {
+        let error = error_value(None, ErrorObject::new(PARSE_ERROR, "Parse error"));
+        respond(stream, "400 Bad Request", &[("Content-Type", "application/json".into())], &error.to_string()).await;
+        return;
+    };
+
+    respond(stream, "202 Accepted", &[], "").await;
+    tokio::spawn(deliver(shared.server, message, out));
+}
+
+async fn handle_streamable_post(stream: &mut TcpStream, request: HttpRequest, shared: Shared) {
+    let Ok(message) = serde_json::from_slice::<Value>(&request.body) else {
+        let error = error_value(None, ErrorObject::new(PARSE_ERROR, "Parse error"));
+        respond(stream, "400 Bad Request", &[("Content-Type", "application/json".into())], &error.to_string()).await;
+        return;
+    };
+
+    let is_initialize = message.get("method").and_then(|m| m.as_str()) == Some("initialize");
+    let mut headers: Vec<(&str, String)> = Vec::new();
+
+    if is_initialize {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        shared.sessions.lock().await.streamable.push(id.clone());
+        headers.push(("Mcp-Session-Id", id));
+    } else if let Some(id) = request.header("mcp-session-id") {
+        if !shared.sessions.lock().await.streamable.iter().any(|s| s == id) {
+            respond(stream, "404 Not Found", &[], "unknown session").await;
+            return;
+        }
+    }
+
+    let reply = shared.server.lock().await.handle(message);
+    if !reply.delay.is_zero() {
+        sleep(reply.delay).await;
+    }
+
+    let Some(response) = reply.response else {
+        respond(stream, "202 Accepted", &headers, "").await;
+        return;
+    };
+
+    if reply.notifications.is_empty() {
+        headers.push(("Content-Type", "application/json".into()));
+        respond(stream, "200 OK", &headers, &response.to_string()).await;
+    } else {
+        let body: String = reply
+            .notifications
+            .iter()
+            .chain(std::iter::once(&response))
+            .map(|m| sse_event("message", &m.to_string()))
+            .collect();
+        headers.push(("Content-Type", "text/event-stream".into()));
+        respond(stream, "200 OK", &headers, &body).await;
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════
+// STDIO TRANSPORT
+// ═══════════════════════════════════════════════════════════════════
+
+/// Newline-delimited JSON-RPC over any reader/writer pair. Requests are
+/// handled concurrently so a slow tool does not block a ping.
+pub async fn serve_lines<R, W>(fixture: Fixture, reader: R, mut writer: W) -> Result<(), MockError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let server = Arc::new(Mutex::new(MockServer::new(fixture)));
+    let (out_tx, mut out_rx) = mpsc::unbounded_channel::<Value>();
+
+    let writer_task = tokio::spawn(async move {
+        while let Some(message) = out_rx.recv().await {
+            let line = format!("{}\n", message);
+            if writer.write_all(line.as_bytes()).await.is_err() || writer.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut lines = reader.lines();
+    let mut in_flight = Vec::new();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Value>(&line) {
+            Ok(message) => in_flight.push(tokio::spawn(deliver(server.clone(), message, out_tx.clone()))),
+            Err(_) => {
+                let _ = out_tx.send(error_value(None, ErrorObject::new(PARSE_ERROR, "Parse error")));
+            }
+        }
+    }
+
+    // Input closed: let outstanding replies finish, then stop writing
+    for task in in_flight {
+        let _ = task.await;
+    }
+    drop(out_tx);
+    let _ = writer_task.await;
+    Ok(())
+}
+
+/// Serves on the process stdin/stdout
+pub async fn serve_stdio(fixture: Fixture) -> Result<(), MockError> {
+    let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+    serve_lines(fixture, stdin, tokio::io::stdout()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(id: i64, method: &str, params: Value) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    fn result_of(reply: &MockReply) -> &Value {
+        &reply.response.as_ref().unwrap()["result"]
+    }
+
+    fn error_code(reply: &MockReply) -> i64 {
+        reply.response.as_ref().unwrap()["error"]["code"].as_i64().unwrap()
+    }
+
+    #[test]
+    fn test_demo_fixture_loads() {
+        let fixture = Fixture::demo();
+        assert!(fixture.tools.iter().any(|t| t.name == "echo"));
+        assert_eq!(fixture.resources.len(), 1);
+        assert_eq!(fixture.prompts.len(), 1);
+    }
+
+    #[test]
+    fn test_json_fixture_with_camel_case() {
+        let fixture = Fixture::from_json(
+            r#"{"tools": [{"name": "t", "inputSchema": {"type": "object", "properties": {}}, "delayMs": 5}],
+                "resources": [{"uri": "mem://a", "mimeType": "text/plain", "text": "A"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(fixture.tools[0].delay_ms, 5);
+        assert_eq!(fixture.resources[0].mime_type.as_deref(), Some("text/plain"));
+        assert_eq!(fixture.server.name, "mock-mcp-server");
+    }
+
+    #[test]
+    fn test_initialize_and_tools() {
+        let mut server = MockServer::new(Fixture::demo());
+        let reply = server.handle(request(1, "initialize", json!({})));
+        assert_eq!(result_of(&reply)["serverInfo"]["name"], "mock-mcp-server");
+        assert_eq!(reply.scheduled.len(), 1);
+
+        let reply = server.handle(request(2, "tools/list", json!({})));
+        let tools = result_of(&reply)["tools"].as_array().unwrap();
+        assert!(tools.iter().any(|t| t["name"] == "echo" && t["inputSchema"]["required"][0] == "text"));
+        assert_eq!(tools[0]["annotations"]["readOnlyHint"], true);
+        assert!(tools.iter().find(|t| t["name"] == "explode").unwrap().get("annotations").is_none());
+    }
+
+    #[test]
+    fn test_tool_call_templates_and_errors() {
+        let mut server = MockServer::new(Fixture::demo());
+
+        let reply = server.handle(request(1, "tools/call", json!({"name": "echo", "arguments": {"text": "hi"}})));
+        assert_eq!(result_of(&reply)["content"][0]["text"], "hi");
+
+        let reply = server.handle(request(2, "tools/call", json!({"name": "echo", "arguments": {}})));
+        assert_eq!(error_code(&reply), INVALID_PARAMS);
+
+        let reply = server.handle(request(3, "tools/call", json!({"name": "nope"})));
+        assert_eq!(error_code(&reply), INVALID_PARAMS);
+
+        let reply = server.handle(request(4, "tools/call", json!({"name": "explode"})));
+        assert_eq!(error_code(&reply), -32603);
+
+        let reply = server.handle(request(5, "tools/call", json!({"name": "soft_fail"})));
+        assert_eq!(result_of(&reply)["isError"], true);
+
+        let reply = server.handle(request(6, "tools/call", json!({"name": "slow_search", "arguments": {"pattern": "U"}})));
+        assert_eq!(reply.delay, Duration::from_millis(1500));
+        assert_eq!(reply.notifications[0]["method"], "notifications/progress");
+    }
+
+    #[test]
+    fn test_resources_and_prompts() {
+        let mut server = MockServer::new(Fixture::demo());
+
+        let reply = server.handle(request(1, "resources/read", json!({"uri": "file:///board/README.md"})));
+        assert_eq!(result_of(&reply)["contents"][0]["mimeType"], "text/markdown");
+
+        let reply = server.handle(request(2, "resources/read", json!({"uri": "file:///missing"})));
+        assert_eq!(error_code(&reply), RESOURCE_NOT_FOUND);
+
+        let reply = server.handle(request(3, "prompts/get", json!({"name": "review_net", "arguments": {"net": "GND"}})));
+        assert_eq!(result_of(&reply)["messages"][0]["content"]["text"], "Review the routing of net GND.");
+
+        let reply = server.handle(request(4, "prompts/get", json!({"name": "review_net"})));
+        assert_eq!(error_code(&reply), INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_faults_fire_limited_times() {
+        let fixture = Fixture::from_toml(
+            r#"
+            [[faults]]
+            method = "ping"
+            code = -32000
+            message = "busy"
+            delay_ms = 10
+            times = 1
+            "#,
+        )
+        .unwrap();
+        let mut server = MockServer::new(fixture);
+
+        let reply = server.handle(request(1, "ping", json!({})));
+        assert_eq!(error_code(&reply), -32000);
+        assert_eq!(reply.delay, Duration::from_millis(10));
+
+        let reply = server.handle(request(2, "ping", json!({})));
+        assert_eq!(result_of(&reply), &json!({}));
+    }
+
+    #[test]
+    fn test_notifications_batches_and_unknown_methods() {
+        let mut server = MockServer::new(Fixture::default());
+        let reply = server.handle(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}));
+        assert!(reply.response.is_none());
+
+        let reply = server.handle(request(1, "nope/nope", json!({})));
+        assert_eq!(error_code(&reply), METHOD_NOT_FOUND);
+
+        let reply = server.handle(json!([
+            request(2, "ping", json!({})),
+            {"jsonrpc": "2.0", "method": "notifications/cancelled"},
+            request(3, "ping", json!({}))
+        ]));
+        assert_eq!(reply.response.unwrap().as_array().unwrap().len(), 2);
+        assert_eq!(server.received().len(), 5);
+    }
+
+    #[test]
+    fn test_substitute() {
+        assert_eq!(substitute("{{a}} and {{b}}", &json!({"a": "x", "b": 2})), "x and 2");
+        assert_eq!(substitute("{{missing}}", &json!({})), "{{missing}}");
+    }
+
+    #[tokio::test]
+    async fn test_stdio_transport() {
+        let input = format!(
+            "{}\n{}\n",
+            request(1, "initialize", json!({})),
+            request(2, "tools/call", json!({"name": "echo", "arguments": {"text": "over stdio"}}))
+        );
+        let fixture = Fixture { notifications: Vec::new(), ..Fixture::demo() };
+        let (client, mut server_out) = tokio::io::duplex(64 * 1024);
+        serve_lines(fixture, input.as_bytes(), client).await.unwrap();
+
+        let mut output = String::new();
+        server_out.read_to_string(&mut output).await.unwrap();
+        let replies: Vec<Value> = output.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(replies.len(), 2);
+        assert!(replies.iter().any(|r| r["result"]["content"][0]["text"] == "over stdio"));
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_transport() {
+        let mock = spawn(Fixture::demo()).await.unwrap();
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(mock.http_url())
+            .json(&request(1, "initialize", json!({})))
+            .send()
+            .await
+            .unwrap();
+        let session = response.headers().get("mcp-session-id").unwrap().to_str().unwrap().to_string();
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["result"]["protocolVersion"], "2024-11-05");
+
+        let response = client
+            .post(mock.http_url())
+            .header("Mcp-Session-Id", &session)
+            .json(&request(2, "tools/list", json!({})))
+            .send()
+            .await
+            .unwrap();
+        let body: Value = response.json().await.unwrap();
+        assert!(body["result"]["tools"].as_array().unwrap().len() >= 3);
+
+        let status = client.get(mock.http_url()).send().await.unwrap().status();
+        assert_eq!(status, reqwest::StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(mock.received_methods().await, vec!["initialize", "tools/list"]);
+    }
+}
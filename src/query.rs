@@ -0,0 +1,816 @@
+// ============================================================================
+// src/query.rs - jq subset and JSONPath queries behind :query
+// ============================================================================
+//
+// Supported:
+//   .  .a  .a.b  ."odd key"  .[0]  .[-1]  .["k"]  .[]  ..  ?
+//   a | b   a, b   ( ... )   == != < <= > >=   and  or
+//   select(f) map(f) startswith(s) endswith(s) contains(x) has(k)
+//   length keys not type first last
+//   "strings" numbers true false null
+//   JSONPath: $.a[*].b  $..name  $.a[?(@.ref == "U1")]  $['k']
+
+use serde_json::Value;
+use std::cmp::Ordering;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum QueryError {
+    #[error("syntax error at column {0}: {1}")]
+    Syntax(usize, String),
+    #[error("{0}")]
+    Eval(String),
+}
+
+type QueryResult<T> = Result<T, QueryError>;
+
+/// A parsed expression, ready to run against any number of values
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query(Expr);
+
+impl Query {
+    /// Pure parser: &str → Query. `$`-rooted input is read as JSONPath.
+    pub fn parse(input: &str) -> QueryResult<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0, end: input.chars().count() + 1 };
+        let expr = parser.pipe()?;
+        match parser.peek() {
+            None => Ok(Query(expr)),
+            Some(token) => Err(parser.error(format!("unexpected {}", token.describe()))),
+        }
+    }
+
+    /// Pure function: Query × Value → every output, in order
+    pub fn run(&self, input: &Value) -> QueryResult<Vec<Value>> {
+        eval(&self.0, input)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Syntax tree
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Identity,
+    Literal(Value),
+    Path(Box<Expr>, Vec<Step>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CmpOp, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(String),
+    Index(i64),
+    /// `[]`, `[*]`, `.*`
+    Iterate,
+    /// `..`: the value and everything below it
+    Recurse,
+    /// `..name` (JSONPath): every `name` member below the value
+    Descendant(String),
+    /// `[?(expr)]` (JSONPath): children for which expr holds
+    Filter(Box<Expr>),
+    /// `step?`: errors produce no output
+    Optional(Box<Step>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Select,
+    Map,
+    StartsWith,
+    EndsWith,
+    Contains,
+    Has,
+    Length,
+    Keys,
+    Not,
+    Type,
+    First,
+    Last,
+}
+
+impl Function {
+    /// Name → (function, number of arguments)
+    fn lookup(name: &str) -> Option<(Self, usize)> {
+        Some(match name {
+            "select" => (Function::Select, 1),
+            "map" => (Function::Map, 1),
+            "startswith" => (Function::StartsWith, 1),
+            "endswith" => (Function::EndsWith, 1),
+            "contains" => (Function::Contains, 1),
+            "has" => (Function::Has, 1),
+            "length" => (Function::Length, 0),
+            "keys" => (Function::Keys, 0),
+            "not" => (Function::Not, 0),
+            "type" => (Function::Type, 0),
+            "first" => (Function::First, 0),
+            "last" => (Function::Last, 0),
+            _ => return None,
+        })
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Tokenizer
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    DotDot,
+    Ident(String),
+    Str(String),
+    Num(f64),
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Pipe,
+    Comma,
+    Semicolon,
+    Question,
+    Star,
+    /// `$` and `@`: the JSONPath root and current node
+    Root,
+    Cmp(CmpOp),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(name) => format!("'{}'", name),
+            Token::Str(s) => format!("string {:?}", s),
+            Token::Num(n) => format!("number {}", n),
+            other => format!("'{}'", match other {
+                Token::Dot => ".",
+                Token::DotDot => "..",
+                Token::LBracket => "[",
+                Token::RBracket => "]",
+                Token::LParen => "(",
+                Token::RParen => ")",
+                Token::Pipe => "|",
+                Token::Comma => ",",
+                Token::Semicolon => ";",
+                Token::Question => "?",
+                Token::Star => "*",
+                Token::Root => "$",
+                Token::Cmp(CmpOp::Eq) => "==",
+                Token::Cmp(CmpOp::Ne) => "!=",
+                Token::Cmp(CmpOp::Lt) => "<",
+                Token::Cmp(CmpOp::Le) => "<=",
+                Token::Cmp(CmpOp::Gt) => ">",
+                Token::Cmp(CmpOp::Ge) => ">=",
+                _ => unreachable!(),
+            }),
+        }
+    }
+}
+
+/// Pure function: &str → tokens with their 1-based columns
+fn tokenize(input: &str) -> QueryResult<Vec<(usize, Token)>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '.' if next == Some('.') => (Token::DotDot, 2),
+            '.' => (Token::Dot, 1),
+            '[' => (Token::LBracket, 1),
+            ']' => (Token::RBracket, 1),
+            '(' => (Token::LParen, 1),
+            ')' => (Token::RParen, 1),
+            '|' => (Token::Pipe, 1),
+            ',' => (Token::Comma, 1),
+            ';' => (Token::Semicolon, 1),
+            '?' => (Token::Question, 1),
+            '*' => (Token::Star, 1),
+            '$' | '@' => (Token::Root, 1),
+            '=' if next == Some('=') => (Token::Cmp(CmpOp::Eq), 2),
+            '!' if next == Some('=') => (Token::Cmp(CmpOp::Ne), 2),
+            '<' if next == Some('=') => (Token::Cmp(CmpOp::Le), 2),
+            '>' if next == Some('=') => (Token::Cmp(CmpOp::Ge), 2),
+            '<' => (Token::Cmp(CmpOp::Lt), 1),
+            '>' => (Token::Cmp(CmpOp::Gt), 1),
+            '"' | '\'' => {
+                let (text, len) = string_literal(&chars[i..])
+                    .ok_or_else(|| QueryError::Syntax(column, "unterminated string".into()))?;
+                (Token::Str(text), len)
+            }
+            c if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                let len = 1 + chars[i + 1..].iter().take_while(|c| c.is_ascii_digit() || **c == '.').count();
+                let text: String = chars[i..i + len].iter().collect();
+                let n = text
+                    .parse()
+                    .map_err(|_| QueryError::Syntax(column, format!("bad number '{}'", text)))?;
+                (Token::Num(n), len)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let len = chars[i..].iter().take_while(|c| c.is_alphanumeric() || **c == '_').count();
+                (Token::Ident(chars[i..i + len].iter().collect()), len)
+            }
+            other => return Err(QueryError::Syntax(column, format!("unexpected character '{}'", other))),
+        };
+        tokens.push((column, token));
+        i += len;
+    }
+    Ok(tokens)
+}
+
+/// Quoted string starting at `chars[0]` → (text, chars consumed)
+fn string_literal(chars: &[char]) -> Option<(String, usize)> {
+    let quote = chars[0];
+    let mut text = String::new();
+    let mut i = 1;
+    loop {
+        match *chars.get(i)? {
+            c if c == quote => return Some((text, i + 1)),
+            '\\' => {
+                text.push(match *chars.get(i + 1)? {
+                    'n' => '\n',
+                    't' => '\t',
+                    other => other,
+                });
+                i += 2;
+            }
+            c => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Parser - precedence: | < , < or < and < comparison < postfix
+// ═══════════════════════════════════════════════════════════════
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    /// Column reported for errors at the end of input
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(_, token)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.eat(&Token::Ident(keyword.to_string()))
+    }
+
+    fn error(&self, message: String) -> QueryError {
+        let column = self.tokens.get(self.pos).map_or(self.end, |(column, _)| *column);
+        QueryError::Syntax(column, message)
+    }
+
+    fn expect(&mut self, token: Token) -> QueryResult<()> {
+        if self.eat(&token) {
+            return Ok(());
+        }
+        let found = self.peek().map_or_else(|| "end of input".to_string(), Token::describe);
+        Err(self.error(format!("expected {}, found {}", token.describe(), found)))
+    }
+
+    fn pipe(&mut self) -> QueryResult<Expr> {
+        let mut left = self.comma()?;
+        while self.eat(&Token::Pipe) {
+            left = Expr::Pipe(Box::new(left), Box::new(self.comma()?));
+        }
+        Ok(left)
+    }
+
+    fn comma(&mut self) -> QueryResult<Expr> {
+        let mut left = self.or()?;
+        while self.eat(&Token::Comma) {
+            left = Expr::Comma(Box::new(left), Box::new(self.or()?));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> QueryResult<Expr> {
+        let mut left = self.and()?;
+        while self.eat_keyword("or") {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> QueryResult<Expr> {
+        let mut left = self.comparison()?;
+        while self.eat_keyword("and") {
+            left = Expr::And(Box::new(left), Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> QueryResult<Expr> {
+        let left = self.postfix()?;
+        if let Some(Token::Cmp(op)) = self.peek().cloned() {
+            self.pos += 1;
+            return Ok(Expr::Compare(Box::new(left), op, Box::new(self.postfix()?)));
+        }
+        Ok(left)
+    }
+
+    /// A primary term followed by any number of path steps
+    fn postfix(&mut self) -> QueryResult<Expr> {
+        let (base, mut steps) = self.primary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    match self.next() {
+                        Some(Token::Ident(name) | Token::Str(name)) => steps.push(Step::Field(name)),
+                        Some(Token::Star) => steps.push(Step::Iterate),
+                        Some(Token::LBracket) => steps.push(self.bracket()?),
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.error("expected a field name after '.'".into()));
+                        }
+                    }
+                }
+                Some(Token::DotDot) => {
+                    self.pos += 1;
+                    steps.push(self.descend());
+                }
+                Some(Token::LBracket) => {
+                    self.pos += 1;
+                    steps.push(self.bracket()?);
+                }
+                Some(Token::Question) => {
+                    self.pos += 1;
+                    if let Some(last) = steps.pop() {
+                        steps.push(Step::Optional(Box::new(last)));
+                    }
+                }
+                _ => break,
+            }
+        }
+        Ok(match (base, steps.is_empty()) {
+            (base, true) => base,
+            (base, false) => Expr::Path(Box::new(base), steps),
+        })
+    }
+
+    /// After `..`: `..name` or plain recursion
+    fn descend(&mut self) -> Step {
+        match self.peek().cloned() {
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                Step::Descendant(name)
+            }
+            _ => Step::Recurse,
+        }
+    }
+
+    /// After `[`: `]`, `*]`, `n]`, `"key"]` or `?(expr)]`
+    fn bracket(&mut self) -> QueryResult<Step> {
+        let step = match self.next() {
+            Some(Token::RBracket) => return Ok(Step::Iterate),
+            Some(Token::Star) => Step::Iterate,
+            Some(Token::Num(n)) if n.fract() == 0.0 => Step::Index(n as i64),
+            Some(Token::Str(key)) => Step::Field(key),
+            Some(Token::Question) => {
+                self.expect(Token::LParen)?;
+                let filter = self.pipe()?;
+                self.expect(Token::RParen)?;
+                Step::Filter(Box::new(filter))
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("expected ], *, an index, a key or ?(filter) inside [ ]".into()));
+            }
+        };
+        self.expect(Token::RBracket)?;
+        Ok(step)
+    }
+
+    fn primary(&mut self) -> QueryResult<(Expr, Vec<Step>)> {
+        let Some(token) = self.next() else {
+            return Err(self.error("expression expected".into()));
+        };
+        Ok(match token {
+            Token::Root => (Expr::Identity, Vec::new()),
+            Token::Dot => match self.peek().cloned() {
+                Some(Token::Ident(name) | Token::Str(name)) => {
+                    self.pos += 1;
+                    (Expr::Identity, vec![Step::Field(name)])
+                }
+                Some(Token::Star) => {
+                    self.pos += 1;
+                    (Expr::Identity, vec![Step::Iterate])
+                }
+                _ => (Expr::Identity, Vec::new()),
+            },
+            Token::DotDot => (Expr::Identity, vec![self.descend()]),
+            Token::Str(s) => (Expr::Literal(Value::String(s)), Vec::new()),
+            Token::Num(n) => (Expr::Literal(number(n)), Vec::new()),
+            Token::LParen => {
+                let inner = self.pipe()?;
+                self.expect(Token::RParen)?;
+                (inner, Vec::new())
+            }
+            Token::Ident(name) => (self.call(name)?, Vec::new()),
+            other => {
+                self.pos -= 1;
+                return Err(self.error(format!("unexpected {}", other.describe())));
+            }
+        })
+    }
+
+    /// Literal keyword or function call, arguments separated by `;`
+    fn call(&mut self, name: String) -> QueryResult<Expr> {
+        match name.as_str() {
+            "true" => return Ok(Expr::Literal(Value::Bool(true))),
+            "false" => return Ok(Expr::Literal(Value::Bool(false))),
+            "null" => return Ok(Expr::Literal(Value::Null)),
+            _ => {}
+        }
+        self.pos -= 1;
+        let (function, arity) = Function::lookup(&name).ok_or_else(|| self.error(format!("unknown function '{}'", name)))?;
+        self.pos += 1;
+
+        let mut args = Vec::new();
+        if self.eat(&Token::LParen) {
+            args.push(self.pipe()?);
+            while self.eat(&Token::Semicolon) {
+                args.push(self.pipe()?);
+            }
+            self.expect(Token::RParen)?;
+        }
+        if args.len() != arity {
+            return Err(self.error(format!("{} takes {} argument(s), got {}", name, arity, args.len())));
+        }
+        Ok(Expr::Call(function, args))
+    }
+}
+
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Value::from(n as i64)
+    } else {
+        serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Evaluation
+// ═══════════════════════════════════════════════════════════════
+
+fn eval(expr: &Expr, input: &Value) -> QueryResult<Vec<Value>> {
+    match expr {
+        Expr::Identity => Ok(vec![input.clone()]),
+        Expr::Literal(value) => Ok(vec![value.clone()]),
+        Expr::Path(base, steps) => steps.iter().try_fold(eval(base, input)?, |values, step| {
+            values.iter().map(|value| apply(step, value)).collect::<QueryResult<Vec<_>>>().map(|v| v.concat())
+        }),
+        Expr::Pipe(left, right) => each(&eval(left, input)?, |value| eval(right, value)),
+        Expr::Comma(left, right) => Ok([eval(left, input)?, eval(right, input)?].concat()),
+        Expr::Compare(left, op, right) => {
+            let rights = eval(right, input)?;
+            each(&eval(left, input)?, |l| {
+                Ok(rights
+                    .iter()
+                    .map(|r| {
+                        let ordering = order(l, r);
+                        Value::Bool(match op {
+                            CmpOp::Eq => ordering == Ordering::Equal,
+                            CmpOp::Ne => ordering != Ordering::Equal,
+                            CmpOp::Lt => ordering == Ordering::Less,
+                            CmpOp::Le => ordering != Ordering::Greater,
+                            CmpOp::Gt => ordering == Ordering::Greater,
+                            CmpOp::Ge => ordering != Ordering::Less,
+                        })
+                    })
+                    .collect())
+            })
+        }
+        Expr::And(left, right) => each(&eval(left, input)?, |l| {
+            if !truthy(l) {
+                return Ok(vec![Value::Bool(false)]);
+            }
+            Ok(eval(right, input)?.iter().map(|r| Value::Bool(truthy(r))).collect())
+        }),
+        Expr::Or(left, right) => each(&eval(left, input)?, |l| {
+            if truthy(l) {
+                return Ok(vec![Value::Bool(true)]);
+            }
+            Ok(eval(right, input)?.iter().map(|r| Value::Bool(truthy(r))).collect())
+        }),
+        Expr::Call(function, args) => call(*function, args, input),
+    }
+}
+
+/// Runs `f` on every value and concatenates the outputs
+fn each(values: &[Value], f: impl Fn(&Value) -> QueryResult<Vec<Value>>) -> QueryResult<Vec<Value>> {
+    values.iter().map(f).collect::<QueryResult<Vec<_>>>().map(|v| v.concat())
+}
+
+fn apply(step: &Step, value: &Value) -> QueryResult<Vec<Value>> {
+    match (step, value) {
+        (Step::Field(key), Value::Object(map)) => Ok(vec![map.get(key).cloned().unwrap_or(Value::Null)]),
+        (Step::Field(_) | Step::Index(_), Value::Null) => Ok(vec![Value::Null]),
+        (Step::Field(key), other) => Err(QueryError::Eval(format!("cannot index {} with \"{}\"", type_name(other), key))),
+        (Step::Index(i), Value::Array(items)) => {
+            let index = if *i < 0 { items.len() as i64 + i } else { *i };
+            Ok(vec![usize::try_from(index).ok().and_then(|i| items.get(i)).cloned().unwrap_or(Value::Null)])
+        }
+        (Step::Index(i), other) => Err(QueryError::Eval(format!("cannot index {} with {}", type_name(other), i))),
+        (Step::Iterate, _) => children(value)
+            .ok_or_else(|| QueryError::Eval(format!("cannot iterate over {}", type_name(value)))),
+        (Step::Recurse, _) => {
+            let mut out = Vec::new();
+            descendants(value, &mut |v| out.push(v.clone()));
+            Ok(out)
+        }
+        (Step::Descendant(key), _) => {
+            let mut out = Vec::new();
+            descendants(value, &mut |v| {
+                if let Some(found) = v.get(key.as_str()) {
+                    out.push(found.clone());
+                }
+            });
+            Ok(out)
+        }
+        (Step::Filter(filter), _) => {
+            let mut out = Vec::new();
+            for child in children(value).unwrap_or_default() {
+                if eval(filter, &child)?.iter().any(truthy) {
+                    out.push(child);
+                }
+            }
+            Ok(out)
+        }
+        (Step::Optional(step), _) => Ok(apply(step, value).unwrap_or_default()),
+    }
+}
+
+fn children(value: &Value) -> Option<Vec<Value>> {
+    match value {
+        Value::Array(items) => Some(items.clone()),
+        Value::Object(map) => Some(map.values().cloned().collect()),
+        _ => None,
+    }
+}
+
+/// Visits the value and everything below it, parents first
+fn descendants(value: &Value, visit: &mut impl FnMut(&Value)) {
+    visit(value);
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| descendants(item, visit)),
+        Value::Object(map) => map.values().for_each(|item| descendants(item, visit)),
+        _ => {}
+    }
+}
+
+fn call(function: Function, args: &[Expr], input: &Value) -> QueryResult<Vec<Value>> {
+    let type_error = |name: &str| QueryError::Eval(format!("{} cannot be applied to {}", name, type_name(input)));
+    let with_arg = |f: &dyn Fn(&Value) -> QueryResult<Value>| -> QueryResult<Vec<Value>> {
+        eval(&args[0], input)?.iter().map(f).collect()
+    };
+
+    match function {
+        Function::Select => Ok(eval(&args[0], input)?
+            .iter()
+            .filter(|value| truthy(value))
+            .map(|_| input.clone())
+            .collect()),
+        Function::Map => match input {
+            Value::Array(items) => Ok(vec![Value::Array(each(items, |item| eval(&args[0], item))?)]),
+            _ => Err(type_error("map")),
+        },
+        Function::StartsWith | Function::EndsWith => with_arg(&|arg| match (input, arg) {
+            (Value::String(s), Value::String(affix)) => Ok(Value::Bool(if function == Function::StartsWith {
+                s.starts_with(affix.as_str())
+            } else {
+                s.ends_with(affix.as_str())
+            })),
+            _ => Err(QueryError::Eval(format!(
+                "{} requires strings, got {} and {}",
+                if function == Function::StartsWith { "startswith" } else { "endswith" },
+                type_name(input),
+                type_name(arg)
+            ))),
+        }),
+        Function::Contains => with_arg(&|arg| {
+            if type_name(input) != type_name(arg) {
+                return Err(QueryError::Eval(format!("{} cannot contain {}", type_name(input), type_name(arg))));
+            }
+            Ok(Value::Bool(contains(input, arg)))
+        }),
+        Function::Has => with_arg(&|arg| match (input, arg) {
+            (Value::Object(map), Value::String(key)) => Ok(Value::Bool(map.contains_key(key))),
+            (Value::Array(items), Value::Number(n)) => {
+                Ok(Value::Bool(n.as_u64().is_some_and(|i| (i as usize) < items.len())))
+            }
+            _ => Err(QueryError::Eval(format!("cannot check whether {} has a {} key", type_name(input), type_name(arg)))),
+        }),
+        Function::Length => Ok(vec![match input {
+            Value::Null => Value::from(0),
+            Value::String(s) => Value::from(s.chars().count()),
+            Value::Array(items) => Value::from(items.len()),
+            Value::Object(map) => Value::from(map.len()),
+            Value::Number(n) => number(n.as_f64().unwrap_or(0.0).abs()),
+            Value::Bool(_) => return Err(type_error("length")),
+        }]),
+        Function::Keys => match input {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                Ok(vec![Value::Array(keys.into_iter().map(|k| Value::String(k.clone())).collect())])
+            }
+            Value::Array(items) => Ok(vec![Value::Array((0..items.len()).map(Value::from).collect())]),
+            _ => Err(type_error("keys")),
+        },
+        Function::Not => Ok(vec![Value::Bool(!truthy(input))]),
+        Function::Type => Ok(vec![Value::String(type_name(input).to_string())]),
+        Function::First | Function::Last => match input {
+            Value::Array(items) => {
+                let item = if function == Function::First { items.first() } else { items.last() };
+                Ok(vec![item.cloned().unwrap_or(Value::Null)])
+            }
+            Value::Null => Ok(vec![Value::Null]),
+            _ => Err(type_error(if function == Function::First { "first" } else { "last" })),
+        },
+    }
+}
+
+/// jq's `contains`: substrings, and recursively for arrays and objects
+fn contains(haystack: &Value, needle: &Value) -> bool {
+    match (haystack, needle) {
+        (Value::String(h), Value::String(n)) => h.contains(n.as_str()),
+        (Value::Array(h), Value::Array(n)) => n.iter().all(|n| h.iter().any(|h| contains(h, n))),
+        (Value::Object(h), Value::Object(n)) => n.iter().all(|(k, n)| h.get(k).is_some_and(|h| contains(h, n))),
+        (h, n) => order(h, n) == Ordering::Equal,
+    }
+}
+
+const fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+const fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// jq's total order: null < false < true < numbers < strings < arrays < objects
+fn order(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            x.as_f64().unwrap_or(0.0).partial_cmp(&y.as_f64().unwrap_or(0.0)).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| order(x, y))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (Value::Object(x), Value::Object(y)) => {
+            let mut xs: Vec<_> = x.iter().collect();
+            let mut ys: Vec<_> = y.iter().collect();
+            xs.sort_by(|a, b| a.0.cmp(b.0));
+            ys.sort_by(|a, b| a.0.cmp(b.0));
+            xs.iter()
+                .map(|(k, _)| k)
+                .cmp(ys.iter().map(|(k, _)| k))
+                .then_with(|| {
+                    xs.iter()
+                        .zip(&ys)
+                        .map(|((_, x), (_, y))| order(x, y))
+                        .find(|o| *o != Ordering::Equal)
+                        .unwrap_or(Ordering::Equal)
+                })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn board() -> Value {
+        json!({
+            "name": "demo",
+            "components": [
+                {"ref": "U1", "pins": 8, "tags": ["mcu"]},
+                {"ref": "R1", "pins": 2},
+                {"ref": "U2", "pins": 14}
+            ]
+        })
+    }
+
+    fn run(expr: &str) -> Vec<Value> {
+        Query::parse(expr).and_then(|q| q.run(&board())).unwrap_or_else(|e| panic!("{}: {}", expr, e))
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(run("."), vec![board()]);
+        assert_eq!(run(".name"), vec![json!("demo")]);
+        assert_eq!(run(".components[-1].ref"), vec![json!("U2")]);
+        assert_eq!(run(".components[].pins"), vec![json!(8), json!(2), json!(14)]);
+        assert_eq!(run(".missing.deeper"), vec![Value::Null]);
+        assert_eq!(run(r#".["name"], (.components | length)"#), vec![json!("demo"), json!(3)]);
+    }
+
+    #[test]
+    fn test_select_pipeline() {
+        assert_eq!(
+            run(r#".components[] | select(.ref|startswith("U")) | .pins"#),
+            vec![json!(8), json!(14)]
+        );
+        assert_eq!(run(".components[] | select(.pins > 2 and has(\"tags\")) | .ref"), vec![json!("U1")]);
+        assert_eq!(run(".components | map(.ref)"), vec![json!(["U1", "R1", "U2"])]);
+        assert_eq!(run(".components[0] | keys"), vec![json!(["pins", "ref", "tags"])]);
+        assert_eq!(run(".components[1].tags | not"), vec![json!(true)]);
+    }
+
+    #[test]
+    fn test_jsonpath() {
+        assert_eq!(run("$.components[*].ref"), vec![json!("U1"), json!("R1"), json!("U2")]);
+        assert_eq!(run("$..ref").len(), 3);
+        assert_eq!(run("$.components[?(@.pins >= 8)].ref"), vec![json!("U1"), json!("U2")]);
+        assert_eq!(run("$['name']"), vec![json!("demo")]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Query::parse(".components[").unwrap_err(),
+            QueryError::Syntax(13, "expected ], *, an index, a key or ?(filter) inside [ ]".into())
+        );
+        assert!(matches!(Query::parse("frobnicate"), Err(QueryError::Syntax(1, _))));
+        assert!(matches!(Query::parse("select(.a; .b)"), Err(QueryError::Syntax(_, _))));
+
+        let err = Query::parse(".name[]").unwrap().run(&board()).unwrap_err();
+        assert_eq!(err, QueryError::Eval("cannot iterate over string".into()));
+        assert_eq!(Query::parse(".name[]?").unwrap().run(&board()), Ok(vec![]));
+    }
+}
@@ -0,0 +1,366 @@
+// ============================================================================
+// src/recording.rs - JSONL session recordings and deterministic replay
+// ============================================================================
+
+use crate::inspector::{TrafficDirection, TrafficRecord};
+use crate::jsonrpc::{ErrorObject, Response, RequestId, INTERNAL_ERROR, METHOD_NOT_FOUND};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error("cannot open {path}: {source}")]
+    Open { path: PathBuf, source: std::io::Error },
+    #[error("cannot write recording: {0}")]
+    Write(#[from] std::io::Error),
+    #[error("{path}:{line}: {reason}")]
+    Parse { path: PathBuf, line: usize, reason: String },
+    #[error("unsupported recording version {0} (expected {FORMAT_VERSION})")]
+    Version(u32),
+}
+
+/// One line of a recording file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordEntry {
+    Start { ts: DateTime<Local>, version: u32 },
+    Command { ts: DateTime<Local>, text: String },
+    Message { ts: DateTime<Local>, direction: TrafficDirection, body: Value },
+}
+
+impl RecordEntry {
+    pub fn command(text: impl Into<String>) -> Self {
+        RecordEntry::Command { ts: Local::now(), text: text.into() }
+    }
+}
+
+impl From<&TrafficRecord> for RecordEntry {
+    fn from(record: &TrafficRecord) -> Self {
+        RecordEntry::Message {
+            ts: record.timestamp,
+            direction: record.direction,
+            body: record.body.clone(),
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Recorder - appends entries, flushing every line
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Debug)]
+pub struct Recorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    entries: usize,
+}
+
+impl Recorder {
+    pub fn start(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::create(&path)
+            .map_err(|source| RecordingError::Open { path: path.clone(), source })?;
+        let mut recorder = Self { path, writer: BufWriter::new(file), entries: 0 };
+        recorder.write(&RecordEntry::Start { ts: Local::now(), version: FORMAT_VERSION })?;
+        Ok(recorder)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Entries written, excluding the header
+    pub fn entries(&self) -> usize {
+        self.entries.saturating_sub(1)
+    }
+
+    pub fn write(&mut self, entry: &RecordEntry) -> Result<(), RecordingError> {
+        let line = serde_json::to_string(entry).map_err(std::io::Error::from)?;
+        writeln!(self.writer, "{}", line)?;
+        // Flush per line so a crash still leaves a usable recording
+        self.writer.flush()?;
+        self.entries += 1;
+        Ok(())
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Recording - parsed file
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    pub entries: Vec<RecordEntry>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .map_err(|source| RecordingError::Open { path: path.to_path_buf(), source })?;
+
+        let mut entries = Vec::new();
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: RecordEntry = serde_json::from_str(&line).map_err(|e| RecordingError::Parse {
+                path: path.to_path_buf(),
+                line: i + 1,
+                reason: e.to_string(),
+            })?;
+            if let RecordEntry::Start { version, .. } = entry {
+                if version != FORMAT_VERSION {
+                    return Err(RecordingError::Version(version));
+                }
+            }
+            entries.push(entry);
+        }
+        Ok(Self { entries })
+    }
+
+    /// User commands in the order they were typed
+    pub fn commands(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter_map(|e| match e {
+                RecordEntry::Command { text, .. } => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn message_count(&self) -> usize {
+        self.entries.iter().filter(|e| matches!(e, RecordEntry::Message { .. })).count()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// ReplayServer - answers requests from a recording
+// ═══════════════════════════════════════════════════════════════
+
+/// A recorded request with its response and the unsolicited server
+/// messages (notifications, server requests) that arrived after that
+/// response and before the next one
+#[derive(Debug, Clone)]
+struct Exchange {
+    recorded_id: Value,
+    method: String,
+    params: Option<Value>,
+    response: Option<Value>,
+    followups: Vec<Value>,
+    used: bool,
+}
+
+/// Fake server: each request is matched to the first unused recorded
+/// request with the same method (preferring identical params) and
+/// answered with the recorded response under the new id. Delivery is
+/// immediate, so a replay is deterministic regardless of original timing.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayServer {
+    exchanges: Vec<Exchange>,
+}
+
+impl ReplayServer {
+    pub fn new(recording: &Recording) -> Self {
+        let mut exchanges: Vec<Exchange> = Vec::new();
+        let mut last_answered: Option<usize> = None;
+
+        for entry in &recording.entries {
+            let RecordEntry::Message { direction, body, .. } = entry else {
+                continue;
+            };
+            let method = body.get("method").and_then(|m| m.as_str());
+            let id = body.get("id").filter(|id| !id.is_null());
+
+            match (direction, method, id) {
+                // Client request
+                (TrafficDirection::Outgoing, Some(method), Some(id)) => exchanges.push(Exchange {
+                    recorded_id: id.clone(),
+                    method: method.to_string(),
+                    params: body.get("params").cloned(),
+                    response: None,
+                    followups: Vec::new(),
+                    used: false,
+                }),
+                // Server response: attach to the request with the same id
+                (TrafficDirection::Incoming, None, Some(id)) => {
+                    let slot = exchanges
+                        .iter()
+                        .rposition(|x| x.response.is_none() && &x.recorded_id == id);
+                    if let Some(slot) = slot {
+                        exchanges[slot].response = Some(body.clone());
+                        last_answered = Some(slot);
+                    }
+                }
+                // Notification or server request
+                (TrafficDirection::Incoming, Some(_), _) => {
+                    let target = last_answered.or(exchanges.len().checked_sub(1));
+                    if let Some(target) = target {
+                        exchanges[target].followups.push(body.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self { exchanges }
+    }
+
+    pub fn exchange_count(&self) -> usize {
+        self.exchanges.len()
+    }
+
+    /// Pure-ish function: outgoing message → incoming messages to deliver
+    pub fn respond(&mut self, message: &Value) -> Vec<Value> {
+        let Some(method) = message.get("method").and_then(|m| m.as_str()) else {
+            return Vec::new(); // our own reply to a server request
+        };
+        let Some(id) = message
+            .get("id")
+            .and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok())
+        else {
+            return Vec::new(); // notification
+        };
+        let params = message.get("params");
+
+        let index = self
+            .exchanges
+            .iter()
+            .position(|x| !x.used && x.method == method && x.params.as_ref() == params)
+            .or_else(|| self.exchanges.iter().position(|x| !x.used && x.method == method));
+
+        let Some(index) = index else {
+            let seen = self.exchanges.iter().any(|x| x.method == method);
+            let error = if seen {
+                ErrorObject::new(INTERNAL_ERROR, format!("replay: recording has no more responses for {}", method))
+            } else {
+                ErrorObject::new(METHOD_NOT_FOUND, format!("replay: {} was never called in the recording", method))
+            };
+            return vec![serde_json::to_value(Response::failure(Some(id), error)).unwrap_or_default()];
+        };
+
+        let exchange = &mut self.exchanges[index];
+        exchange.used = true;
+
+        let mut out = Vec::new();
+        match &exchange.response {
+            Some(response) => {
+                let mut response = response.clone();
+                response["id"] = serde_json::to_value(&id).unwrap_or_default();
+                out.push(response);
+            }
+            None => {
+                let error = ErrorObject::new(INTERNAL_ERROR, format!("replay: {} had no response in the recording", method));
+                out.push(serde_json::to_value(Response::failure(Some(id), error)).unwrap_or_default());
+            }
+        }
+        out.extend(exchange.followups.iter().cloned());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(direction: TrafficDirection, body: Value) -> RecordEntry {
+        RecordEntry::Message { ts: Local::now(), direction, body }
+    }
+
+    fn sample() -> Recording {
+        use TrafficDirection::*;
+        Recording {
+            entries: vec![
+                RecordEntry::Start { ts: Local::now(), version: FORMAT_VERSION },
+                message(Outgoing, json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}})),
+                message(Incoming, json!({"jsonrpc": "2.0", "id": 1, "result": {"protocolVersion": "2024-11-05"}})),
+                RecordEntry::command("mcp run echo hi"),
+                message(Outgoing, json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "hi"}}})),
+                message(Outgoing, json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "echo", "arguments": {"text": "bye"}}})),
+                message(Incoming, json!({"jsonrpc": "2.0", "id": 3, "result": {"content": [{"type": "text", "text": "bye"}]}})),
+                message(Incoming, json!({"jsonrpc": "2.0", "id": 2, "result": {"content": [{"type": "text", "text": "hi"}]}})),
+                message(Incoming, json!({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"})),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_round_trip_file() {
+        let path = std::env::temp_dir().join(format!("mcp-rec-{}.jsonl", uuid::Uuid::new_v4()));
+        let mut recorder = Recorder::start(&path).unwrap();
+        for entry in sample().entries.iter().skip(1) {
+            recorder.write(entry).unwrap();
+        }
+        assert_eq!(recorder.entries(), sample().entries.len() - 1);
+        drop(recorder);
+
+        let loaded = Recording::load(&path).unwrap();
+        assert_eq!(loaded.entries.len(), sample().entries.len());
+        assert_eq!(loaded.commands(), vec!["mcp run echo hi".to_string()]);
+        assert_eq!(loaded.message_count(), 7);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_entry_format() {
+        let line = serde_json::to_value(RecordEntry::command(":q")).unwrap();
+        assert_eq!(line["kind"], "command");
+        let line = serde_json::to_value(message(TrafficDirection::Incoming, json!({}))).unwrap();
+        assert_eq!(line["kind"], "message");
+        assert_eq!(line["direction"], "in");
+    }
+
+    #[test]
+    fn test_replay_rewrites_ids_and_matches_params() {
+        let mut server = ReplayServer::new(&sample());
+        assert_eq!(server.exchange_count(), 3);
+
+        let init = server.respond(&json!({"jsonrpc": "2.0", "id": 10, "method": "initialize", "params": {}}));
+        assert_eq!(init[0]["id"], 10);
+        assert_eq!(init[0]["result"]["protocolVersion"], "2024-11-05");
+
+        // Identical params win over recording order
+        let bye = server.respond(&json!({"jsonrpc": "2.0", "id": "b", "method": "tools/call",
+            "params": {"name": "echo", "arguments": {"text": "bye"}}}));
+        assert_eq!(bye.len(), 1);
+        assert_eq!(bye[0]["id"], "b");
+        assert_eq!(bye[0]["result"]["content"][0]["text"], "bye");
+
+        // Unknown params fall back to the next unused call of that method,
+        // followed by the notification recorded after its response
+        let other = server.respond(&json!({"jsonrpc": "2.0", "id": 12, "method": "tools/call",
+            "params": {"name": "echo", "arguments": {"text": "other"}}}));
+        assert_eq!(other.len(), 2);
+        assert_eq!(other[0]["result"]["content"][0]["text"], "hi");
+        assert_eq!(other[1]["method"], "notifications/tools/list_changed");
+    }
+
+    #[test]
+    fn test_replay_followups_and_errors() {
+        let mut server = ReplayServer::new(&sample());
+        server.respond(&json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}));
+        let first = server.respond(&json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {}}));
+        assert_eq!(first[1]["method"], "notifications/tools/list_changed");
+        let second = server.respond(&json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {}}));
+        assert_eq!(second.len(), 1);
+
+        let exhausted = server.respond(&json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call"}));
+        assert_eq!(exhausted[0]["error"]["code"], INTERNAL_ERROR);
+
+        let unknown = server.respond(&json!({"jsonrpc": "2.0", "id": 5, "method": "prompts/list"}));
+        assert_eq!(unknown[0]["error"]["code"], METHOD_NOT_FOUND);
+
+        let notification = server.respond(&json!({"jsonrpc": "2.0", "method": "notifications/initialized"}));
+        assert!(notification.is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+// ============================================================================
+// src/results.rs - Complete result payloads behind :result and the pager
+// ============================================================================
+
+use crate::jsonrpc::RequestId;
+use crate::tool_formatter::result_lines;
+use chrono::{DateTime, Local};
+use serde_json::Value;
+
+/// Results kept for `:result`; the output log only shows a preview
+const MAX_STORED_RESULTS: usize = 100;
+
+/// One result exactly as the server sent it
+#[derive(Debug, Clone)]
+pub struct StoredResult {
+    /// 1-based, as used by `:result <n>`
+    pub number: usize,
+    pub request: Option<RequestId>,
+    /// Tool or method that produced the result
+    pub label: String,
+    pub server: Option<String>,
+    pub timestamp: DateTime<Local>,
+    pub value: Value,
+}
+
+impl StoredResult {
+    /// Pure function: StoredResult → every display line, untruncated
+    pub fn lines(&self) -> Vec<String> {
+        result_lines(&self.value)
+    }
+
+    /// Pure function: StoredResult → the JSON it carries. Text content
+    /// that parses as JSON is unwrapped (several items become an array);
+    /// anything else is the raw result.
+    pub fn json(&self) -> Value {
+        let Some(content) = self.value.get("content").and_then(|c| c.as_array()) else {
+            return self.value.clone();
+        };
+        let mut items: Vec<Value> = content
+            .iter()
+            .filter_map(|item| item.get("text")?.as_str())
+            .map(|text| serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())))
+            .collect();
+        match items.len() {
+            0 => self.value.clone(),
+            1 => items.remove(0),
+            _ => Value::Array(items),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        let request = self.request.as_ref().map_or_else(String::new, |id| format!(" #{}", id));
+        format!("Result {} · {}{} · {}", self.number, self.label, request, self.timestamp.format("%H:%M:%S"))
+    }
+}
+
+/// Bounded store of complete results, numbered in arrival order
+#[derive(Debug, Clone, Default)]
+pub struct ResultStore {
+    results: Vec<StoredResult>,
+    next: usize,
+}
+
+impl ResultStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Number the next stored result will get
+    pub const fn next_number(&self) -> usize {
+        self.next + 1
+    }
+
+    /// Pure transformation: stores a result under [`Self::next_number`]
+    pub fn with_result(mut self, request: Option<RequestId>, label: String, server: Option<String>, value: Value) -> Self {
+        self.next += 1;
+        self.results.push(StoredResult {
+            number: self.next,
+            request,
+            label,
+            server,
+            timestamp: Local::now(),
+            value,
+        });
+        if self.results.len() > MAX_STORED_RESULTS {
+            let excess = self.results.len() - MAX_STORED_RESULTS;
+            self.results.drain(0..excess);
+        }
+        self
+    }
+
+    pub fn get(&self, number: usize) -> Option<&StoredResult> {
+        self.results.iter().find(|result| result.number == number)
+    }
+
+    pub fn last(&self) -> Option<&StoredResult> {
+        self.results.last()
+    }
+
+    /// Result `n`, or the last one
+    pub fn find(&self, number: Option<usize>) -> Option<&StoredResult> {
+        number.map_or_else(|| self.last(), |n| self.get(n))
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Full-screen pager
+// ═══════════════════════════════════════════════════════════════
+
+/// Read-only view of a long text; only the visible window is rendered
+#[derive(Debug, Clone)]
+pub struct Pager {
+    title: String,
+    lines: Vec<String>,
+    /// Stored result shown, if any; `t` switches to its tree
+    result: Option<usize>,
+    offset: usize,
+    column: usize,
+    /// Visible rows, set by the renderer
+    height: usize,
+}
+
+impl Pager {
+    pub fn new(title: String, lines: Vec<String>) -> Self {
+        Self { title, lines, result: None, offset: 0, column: 0, height: 1 }
+    }
+
+    pub fn with_result(mut self, number: usize) -> Self {
+        self.result = Some(number);
+        self
+    }
+
+    pub const fn result(&self) -> Option<usize> {
+        self.result
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub const fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Lines in the window starting at the current offset
+    pub fn visible(&self) -> &[String] {
+        let end = (self.offset + self.height).min(self.lines.len());
+        &self.lines[self.offset.min(end)..end]
+    }
+
+    fn max_offset(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    pub fn with_height(mut self, height: usize) -> Self {
+        self.height = height.max(1);
+        self.offset = self.offset.min(self.max_offset());
+        self
+    }
+
+    /// Pure transformation: moves the window by `delta` lines
+    pub fn scroll(mut self, delta: isize) -> Self {
+        self.offset = self.offset.saturating_add_signed(delta).min(self.max_offset());
+        self
+    }
+
+    /// Pure transformation: moves the window by `pages` screens
+    pub fn page(self, pages: isize) -> Self {
+        let height = self.height as isize;
+        self.scroll(pages * height)
+    }
+
+    pub fn top(mut self) -> Self {
+        self.offset = 0;
+        self
+    }
+
+    pub fn bottom(mut self) -> Self {
+        self.offset = self.max_offset();
+        self
+    }
+
+    /// Pure transformation: horizontal scroll, lines are not wrapped
+    pub fn shift(mut self, delta: isize) -> Self {
+        self.column = self.column.saturating_add_signed(delta);
+        self
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_store_numbers_and_bounds() {
+        let mut store = ResultStore::new();
+        for i in 0..(MAX_STORED_RESULTS + 5) {
+            store = store.with_result(Some(RequestId::Number(i as i64)), "echo".into(), None, json!(i));
+        }
+        assert_eq!(store.len(), MAX_STORED_RESULTS);
+        assert!(store.get(1).is_none());
+        assert_eq!(store.get(6).unwrap().value, json!(5));
+        assert_eq!(store.last().unwrap().number, MAX_STORED_RESULTS + 5);
+        assert_eq!(store.next_number(), MAX_STORED_RESULTS + 6);
+    }
+
+    #[test]
+    fn test_stored_result_keeps_every_line() {
+        let text = (0..1000).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n");
+        let store = ResultStore::new().with_result(None, "dump".into(), None, json!({
+            "content": [{ "type": "text", "text": text }]
+        }));
+        let lines = store.last().unwrap().lines();
+        assert_eq!(lines.last().map(String::as_str), Some("line 999"));
+    }
+
+    #[test]
+    fn test_stored_result_json() {
+        let store = ResultStore::new()
+            .with_result(None, "board".into(), None, json!({
+                "content": [{ "type": "text", "text": "{\"name\": \"demo\"}" }]
+            }))
+            .with_result(None, "ping".into(), None, json!({ "ok": true }));
+        assert_eq!(store.get(1).unwrap().json(), json!({ "name": "demo" }));
+        assert_eq!(store.get(2).unwrap().json(), json!({ "ok": true }));
+    }
+
+    #[test]
+    fn test_pager_window() {
+        let lines = (0..50).map(|i| i.to_string()).collect();
+        let pager = Pager::new("t".into(), lines).with_height(10);
+        assert_eq!(pager.visible().first().map(String::as_str), Some("0"));
+
+        let pager = pager.page(1).scroll(3);
+        assert_eq!(pager.offset(), 13);
+        let pager = pager.bottom();
+        assert_eq!(pager.visible().len(), 10);
+        assert_eq!(pager.visible().last().map(String::as_str), Some("49"));
+        let pager = pager.scroll(100).scroll(-45);
+        assert_eq!(pager.offset(), 0);
+    }
+}
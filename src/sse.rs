@@ -0,0 +1,423 @@
+// ============================================================================
+// src/sse.rs - Server-Sent Events stream decoder
+// ============================================================================
+//
+// Incremental decoder following the WHATWG "event stream interpretation":
+// - Lines end with CRLF, LF or CR (a CRLF may be split across chunks)
+// - A leading UTF-8 BOM is ignored
+// - Lines starting with ':' are comments
+// - `data` lines accumulate (joined with '\n'), `event` sets the type,
+//   `id` sets the last event id (ignored if it contains NUL),
+//   `retry` sets the reconnection delay (digits only)
+// - A blank line dispatches the event; an event without data is dropped
+// - Lines are decoded as UTF-8 only once complete, so multi-byte
+//   characters split across chunks are preserved
+
+use std::time::Duration;
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// One dispatched event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event type, "message" when the stream did not set one
+    pub event: String,
+    pub data: String,
+    /// Last event id at dispatch time (None if never set or empty)
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SseDecoder {
+    /// Bytes of the current, not yet terminated line
+    buffer: Vec<u8>,
+    /// Length of the start of `buffer` already scanned for line endings,
+    /// so a long line arriving in many chunks is scanned only once
+    scanned: usize,
+    /// Previous chunk ended with CR: a leading LF belongs to it
+    skip_lf: bool,
+    bom_checked: bool,
+    event_type: String,
+    data: String,
+    last_event_id: String,
+    retry: Option<Duration>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            scanned: 0,
+            skip_lf: false,
+            bom_checked: false,
+            event_type: String::new(),
+            data: String::new(),
+            last_event_id: String::new(),
+            retry: None,
+        }
+    }
+
+    /// Last event id seen on the stream (for `Last-Event-ID` on reconnect)
+    pub fn last_event_id(&self) -> &str {
+        &self.last_event_id
+    }
+
+    /// Reconnection delay requested by the server via `retry:`
+    pub const fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Feeds a chunk of bytes, returning every event completed by it
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        if !self.bom_checked {
+            if self.buffer.len() < BOM.len() && BOM.starts_with(&self.buffer) {
+                return Vec::new();
+            }
+            if self.buffer.starts_with(BOM) {
+                self.buffer.drain(..BOM.len());
+            }
+            self.bom_checked = true;
+        }
+
+        let mut events = Vec::new();
+        let mut start = 0;
+        let buffer = std::mem::take(&mut self.buffer);
+
+        for (i, &byte) in buffer.iter().enumerate().skip(self.scanned) {
+            if self.skip_lf {
+                self.skip_lf = false;
+                if byte == b'\n' {
+                    start = i + 1;
+                    continue;
+                }
+            }
+            if byte == b'\n' || byte == b'\r' {
+                let line = String::from_utf8_lossy(&buffer[start..i]);
+                if let Some(event) = self.process_line(&line) {
+                    events.push(event);
+                }
+                self.skip_lf = byte == b'\r';
+                start = i + 1;
+            }
+        }
+
+        self.scanned = buffer.len() - start;
+        self.buffer = buffer;
+        self.buffer.drain(..start);
+        events
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event_type = value.to_string(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.last_event_id = value.to_string(),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(ms) = value.parse::<u64>() {
+                    self.retry = Some(Duration::from_millis(ms));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event_type = std::mem::take(&mut self.event_type);
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let mut data = std::mem::take(&mut self.data);
+        data.pop(); // trailing '\n'
+
+        Some(SseEvent {
+            event: if event_type.is_empty() { "message".to_string() } else { event_type },
+            data,
+            id: (!self.last_event_id.is_empty()).then(|| self.last_event_id.clone()),
+        })
+    }
+}
+
+impl Default for SseDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(input: &[u8]) -> Vec<SseEvent> {
+        SseDecoder::new().feed(input)
+    }
+
+    fn decode_chunks(chunks: &[&[u8]]) -> Vec<SseEvent> {
+        let mut decoder = SseDecoder::new();
+        chunks.iter().flat_map(|c| decoder.feed(c)).collect()
+    }
+
+    fn event(event: &str, data: &str, id: Option<&str>) -> SseEvent {
+        SseEvent {
+            event: event.into(),
+            data: data.into(),
+            id: id.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn test_basic_event() {
+        assert_eq!(
+            decode(b"event: endpoint\ndata: /messages?session=1\n\n"),
+            vec![event("endpoint", "/messages?session=1", None)]
+        );
+    }
+
+    #[test]
+    fn test_default_event_type() {
+        assert_eq!(decode(b"data: hi\n\n"), vec![event("message", "hi", None)]);
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let expected = vec![event("message", "a", None), event("x", "b", None)];
+        assert_eq!(decode(b"data: a\n\nevent: x\ndata: b\n\n"), expected);
+        assert_eq!(decode(b"data: a\r\n\r\nevent: x\r\ndata: b\r\n\r\n"), expected);
+        assert_eq!(decode(b"data: a\r\revent: x\rdata: b\r\r"), expected);
+        assert_eq!(decode(b"data: a\r\n\nevent: x\rdata: b\n\r\n"), expected);
+    }
+
+    #[test]
+    fn test_multiline_data() {
+        assert_eq!(
+            decode(b"data: {\ndata:   \"a\": 1\ndata: }\n\n"),
+            vec![event("message", "{\n  \"a\": 1\n}", None)]
+        );
+    }
+
+    #[test]
+    fn test_data_is_not_trimmed() {
+        assert_eq!(decode(b"data:x \n\n")[0].data, "x ");
+        assert_eq!(decode(b"data:  two spaces\n\n")[0].data, " two spaces");
+        assert_eq!(decode(b"data\n\n")[0].data, "");
+        assert_eq!(decode(b"data:\ndata:\n\n")[0].data, "\n");
+    }
+
+    #[test]
+    fn test_comments_and_unknown_fields() {
+        assert_eq!(
+            decode(b": keep-alive\nfoo: bar\ndata: x\n:another\n\n"),
+            vec![event("message", "x", None)]
+        );
+        assert!(decode(b": ping\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_event_without_data_is_dropped() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(b"event: foo\n\n").is_empty());
+        // Event type does not leak into the next event
+        assert_eq!(decoder.feed(b"data: y\n\n"), vec![event("message", "y", None)]);
+    }
+
+    #[test]
+    fn test_event_ids() {
+        let mut decoder = SseDecoder::new();
+        assert_eq!(decoder.feed(b"id: 7\ndata: a\n\n"), vec![event("message", "a", Some("7"))]);
+        // Id persists for later events
+        assert_eq!(decoder.feed(b"data: b\n\n"), vec![event("message", "b", Some("7"))]);
+        // Id containing NUL is ignored
+        assert_eq!(decoder.feed(b"id: 8\0\ndata: c\n\n")[0].id.as_deref(), Some("7"));
+        // Empty id resets
+        assert_eq!(decoder.feed(b"id\ndata: d\n\n")[0].id, None);
+        assert_eq!(decoder.last_event_id(), "");
+    }
+
+    #[test]
+    fn test_retry() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(b"retry: 1500\n\n").is_empty());
+        assert_eq!(decoder.retry(), Some(Duration::from_millis(1500)));
+        decoder.feed(b"retry: 12a\nretry:\nretry: -1\n\n");
+        assert_eq!(decoder.retry(), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_bom_is_stripped() {
+        assert_eq!(decode(b"\xEF\xBB\xBFdata: x\n\n"), vec![event("message", "x", None)]);
+        assert_eq!(
+            decode_chunks(&[b"\xEF", b"\xBB", b"\xBFdata: x\n\n"]),
+            vec![event("message", "x", None)]
+        );
+        // Only a leading BOM is special
+        assert_eq!(decode(b"data: \xEF\xBB\xBFx\n\n")[0].data, "\u{FEFF}x");
+    }
+
+    #[test]
+    fn test_utf8_split_across_chunks() {
+        let text = "data: zażółć 📋\n\n".as_bytes();
+        for split in 0..text.len() {
+            let events = decode_chunks(&[&text[..split], &text[split..]]);
+            assert_eq!(events, vec![event("message", "zażółć 📋", None)], "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn test_incomplete_event_not_dispatched() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(b"data: partial\n").is_empty());
+        assert!(decoder.feed(b"data: more").is_empty());
+        assert_eq!(decoder.feed(b"\n\n"), vec![event("message", "partial\nmore", None)]);
+    }
+
+    // ───────────────────────────────────────────────────────────
+    // Fuzz-style chunking tests
+    // ───────────────────────────────────────────────────────────
+
+    const SAMPLE: &[u8] = b"\xEF\xBB\xBF: comment\r\nevent: endpoint\r\ndata: /messages?s=1\r\n\r\nid: 1\ndata: {\"jsonrpc\":\"2.0\",\ndata: \"id\":1}\n\nretry: 10\rdata: \xC5\xBC\xC3\xB3\xC5\x82w\r\rdata\n\n";
+
+    fn sample_expected() -> Vec<SseEvent> {
+        vec![
+            event("endpoint", "/messages?s=1", None),
+            event("message", "{\"jsonrpc\":\"2.0\",\n\"id\":1}", Some("1")),
+            event("message", "żółw", Some("1")),
+            event("message", "", Some("1")),
+        ]
+    }
+
+    /// Deterministic pseudo-random generator (LCG) for reproducible fuzzing
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            self.0 >> 33
+        }
+    }
+
+    #[test]
+    fn test_every_single_split_point() {
+        let expected = decode(SAMPLE);
+        assert_eq!(expected, sample_expected());
+        for split in 0..=SAMPLE.len() {
+            assert_eq!(
+                decode_chunks(&[&SAMPLE[..split], &SAMPLE[split..]]),
+                expected,
+                "split at {}",
+                split
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_pair_of_split_points() {
+        let expected = sample_expected();
+        for a in 0..=SAMPLE.len() {
+            for b in a..=SAMPLE.len() {
+                assert_eq!(
+                    decode_chunks(&[&SAMPLE[..a], &SAMPLE[a..b], &SAMPLE[b..]]),
+                    expected,
+                    "splits at {} and {}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_byte_by_byte() {
+        let chunks: Vec<&[u8]> = SAMPLE.chunks(1).collect();
+        assert_eq!(decode_chunks(&chunks), sample_expected());
+    }
+
+    #[test]
+    fn test_long_line_in_small_chunks() {
+        // one 4 MB JSON-RPC message split into 1 KB chunks; rescanning the
+        // partial line on every chunk would make this quadratic
+        let payload = "x".repeat(4 << 20);
+        let stream = format!("data: {}\r\n\r\n", payload);
+        let mut decoder = SseDecoder::new();
+        let mut events = Vec::new();
+        for chunk in stream.as_bytes().chunks(1024) {
+            events.extend(decoder.feed(chunk));
+        }
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data.len(), payload.len());
+        assert!(decoder.buffer.is_empty());
+    }
+
+    #[test]
+    fn test_random_chunkings_and_line_endings() {
+        let mut rng = Lcg(0x5eed);
+        let lines = [
+            ": comment",
+            "event: update",
+            "id: 42",
+            "data: first line",
+            "data:second line",
+            "data: ünïcödé ✓",
+            "",
+            "data: next",
+            "",
+        ];
+        let expected = vec![
+            event("update", "first line\nsecond line\nünïcödé ✓", Some("42")),
+            event("message", "next", Some("42")),
+        ];
+
+        for _ in 0..500 {
+            // Random line endings (a bare CR followed by an empty line
+            // ending in LF would read as one CRLF, so avoid that pairing)
+            let mut stream = Vec::new();
+            let mut after_cr = false;
+            for line in &lines {
+                stream.extend_from_slice(line.as_bytes());
+                let choice = match rng.next() % 3 {
+                    0 if after_cr && line.is_empty() => 2,
+                    n => n,
+                };
+                stream.extend_from_slice(match choice {
+                    0 => b"\n".as_slice(),
+                    1 => b"\r",
+                    _ => b"\r\n",
+                });
+                after_cr = choice == 1;
+            }
+
+            // Random chunk sizes
+            let mut decoder = SseDecoder::new();
+            let mut events = Vec::new();
+            let mut rest = stream.as_slice();
+            while !rest.is_empty() {
+                let size = (rng.next() as usize % 7 + 1).min(rest.len());
+                events.extend(decoder.feed(&rest[..size]));
+                rest = &rest[size..];
+            }
+
+            assert_eq!(events, expected, "stream {:?}", String::from_utf8_lossy(&stream));
+        }
+    }
+}
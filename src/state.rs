@@ -1,7 +1,12 @@
+use crate::highlight::Theme;
+use crate::jsonrpc::RequestId;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
 /// Immutable text buffer with cursor position
 /// Pure functional data structure
-
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Buffer {
     content: String,
@@ -79,40 +84,425 @@ impl Default for Buffer {
 // Output log with functional append
 // ═══════════════════════════════════════════════════════════════
 
-const MAX_LOG_LINES: usize = 1000;
+/// Default bound of the output log (`output_lines` in the config)
+pub const MAX_LOG_LINES: usize = 100_000;
+
+/// What produced an output entry; the renderer styles by kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    /// A command as typed (`:mcp run ...`)
+    Command,
+    /// Result lines of a request
+    Result,
+    Error,
+    Debug,
+    /// Server notifications (progress, log messages, list changes)
+    Notification,
+    /// Everything else the client reports
+    System,
+}
+
+impl EntryKind {
+    pub const ALL: [EntryKind; 6] = [
+        EntryKind::Command,
+        EntryKind::Result,
+        EntryKind::Error,
+        EntryKind::Debug,
+        EntryKind::Notification,
+        EntryKind::System,
+    ];
+
+    /// Pure parser: accepts singular and plural names (`error`, `errors`)
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        let name = name.strip_suffix('s').unwrap_or(&name);
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            EntryKind::Command => "command",
+            EntryKind::Result => "result",
+            EntryKind::Error => "error",
+            EntryKind::Debug => "debug",
+            EntryKind::Notification => "notification",
+            EntryKind::System => "system",
+        }
+    }
+}
+
+/// One line of output with where and when it came from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutputEntry {
+    pub kind: EntryKind,
+    pub text: String,
+    pub timestamp: DateTime<Local>,
+    /// Server the entry came from; None for local messages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Request a result belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<RequestId>,
+    /// Heading line of a result (response header or content item)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub heading: bool,
+}
+
+impl OutputEntry {
+    pub fn new(kind: EntryKind, text: impl Into<String>) -> Self {
+        Self { kind, text: text.into(), timestamp: Local::now(), server: None, request: None, heading: false }
+    }
+
+    pub fn with_server(mut self, server: Option<String>) -> Self {
+        self.server = server;
+        self
+    }
+
+    pub fn with_request(mut self, request: RequestId) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    pub fn as_heading(mut self) -> Self {
+        self.heading = true;
+        self
+    }
+}
+
+/// Which entries the output pane shows and how they are ordered
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputView {
+    /// Kinds to show; empty shows everything
+    pub kinds: Vec<EntryKind>,
+    /// Group entries by kind under a header instead of in arrival order
+    pub grouped: bool,
+    /// Colors for JSON and fenced code in results; None shows them plain
+    pub highlight: Option<Theme>,
+}
+
+impl OutputView {
+    pub fn shows(&self, kind: EntryKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+}
+
+/// Where a request stands, shown in its block header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockStatus {
+    Running,
+    Done,
+    Failed,
+    TimedOut,
+    /// The session ended before a response arrived
+    Cancelled,
+}
+
+/// The output of one request, kept together under a foldable header
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestBlock {
+    pub request: RequestId,
+    /// Tool or method with its compact arguments
+    pub title: String,
+    pub status: BlockStatus,
+    pub duration: Option<Duration>,
+    pub folded: bool,
+    started: Instant,
+    /// Sequence number of the first entry logged after the block opened
+    anchor: u64,
+}
+
+impl RequestBlock {
+    /// Pure function: RequestBlock × hidden entries → header line
+    pub fn header(&self, entries: usize) -> String {
+        let status = match (self.status, self.duration) {
+            (BlockStatus::Running, _) => "⏳ running".to_string(),
+            (BlockStatus::Done, Some(d)) => format!("✅ {} ms", d.as_millis()),
+            (BlockStatus::Failed, Some(d)) => format!("❌ {} ms", d.as_millis()),
+            (BlockStatus::Done | BlockStatus::Failed, None) => "done".to_string(),
+            (BlockStatus::TimedOut, _) => "⏱️  timed out".to_string(),
+            (BlockStatus::Cancelled, _) => "🚫 cancelled".to_string(),
+        };
+        if self.folded {
+            format!("▸ #{} {} · {} · {} lines", self.request, self.title, status, entries)
+        } else {
+            format!("▾ #{} {} · {}", self.request, self.title, status)
+        }
+    }
+}
+
+/// One displayed line: an entry, a kind group header or a request block header
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViewLine<'a> {
+    /// Kind and number of entries in the group
+    Header(EntryKind, usize),
+    /// Block and number of entries it holds
+    Block(&'a RequestBlock, usize),
+    Entry(&'a OutputEntry),
+}
+
+impl ViewLine<'_> {
+    pub fn text(&self) -> String {
+        match self {
+            ViewLine::Header(kind, count) => format!("── {} ({}) ──", kind.name(), count),
+            ViewLine::Block(block, count) => block.header(*count),
+            ViewLine::Entry(entry) => entry.text.clone(),
+        }
+    }
+}
 
 /// Immutable log with bounded size
 #[derive(Debug, Clone)]
 pub struct OutputLog {
-    lines: Vec<String>,
+    entries: Vec<OutputEntry>,
+    /// Request blocks in the order they were opened
+    blocks: Vec<RequestBlock>,
+    /// Sequence number of `entries[0]`; grows as old entries are dropped
+    first_seq: u64,
+    /// Most entries kept
+    limit: usize,
+    /// Bumped on every change
+    revision: u64,
+    /// Bumped on every change other than appending a line at the end of
+    /// the arrival-order view, so renderers can extend instead of rebuild
+    layout_revision: u64,
+    /// No plain entry was logged since the last block opened, so the last
+    /// block ends the arrival-order view
+    block_at_end: bool,
 }
 
 impl OutputLog {
     pub fn new() -> Self {
-        Self { lines: Vec::new() }
+        Self::with_limit(MAX_LOG_LINES)
+    }
+
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            blocks: Vec::new(),
+            first_seq: 0,
+            limit: limit.max(1),
+            revision: 0,
+            layout_revision: 0,
+            block_at_end: false,
+        }
+    }
+
+    /// Pure transformation: changes the bound, dropping old entries if needed
+    pub fn limited(mut self, limit: usize) -> Self {
+        self.limit = limit.max(1);
+        self.trim();
+        self
+    }
+
+    pub const fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub const fn layout_revision(&self) -> u64 {
+        self.layout_revision
+    }
+
+    /// Sequence number of the first entry still in the log
+    pub const fn first_seq(&self) -> u64 {
+        self.first_seq
+    }
+
+    /// Sequence number the next entry will get
+    pub fn end_seq(&self) -> u64 {
+        self.first_seq + self.entries.len() as u64
     }
 
-    pub fn lines(&self) -> &[String] {
-        &self.lines
+    /// Entries appended from sequence number `seq` on
+    pub fn entries_since(&self, seq: u64) -> &[OutputEntry] {
+        let start = seq.saturating_sub(self.first_seq) as usize;
+        &self.entries[start.min(self.entries.len())..]
+    }
+
+    fn relayout(&mut self) {
+        self.revision += 1;
+        self.layout_revision += 1;
+    }
+
+    pub fn blocks(&self) -> &[RequestBlock] {
+        &self.blocks
+    }
+
+    pub fn block(&self, request: &RequestId) -> Option<&RequestBlock> {
+        self.blocks.iter().find(|block| block.request == *request)
+    }
+
+    /// Pure transformation: opens a running block for `request`; entries
+    /// tagged with the request are shown under it from now on
+    pub fn with_block(mut self, request: RequestId, title: String) -> Self {
+        self.blocks.push(RequestBlock {
+            request,
+            title,
+            status: BlockStatus::Running,
+            duration: None,
+            folded: false,
+            started: Instant::now(),
+            anchor: self.first_seq + self.entries.len() as u64,
+        });
+        self.block_at_end = true;
+        self.relayout();
+        self
+    }
+
+    /// Pure transformation: settles a running block (later calls are ignored)
+    pub fn finish_block(mut self, request: &RequestId, status: BlockStatus) -> Self {
+        if let Some(block) = self.blocks.iter_mut().find(|b| b.request == *request && b.status == BlockStatus::Running) {
+            block.status = status;
+            block.duration = Some(block.started.elapsed());
+            self.relayout();
+        }
+        self
+    }
+
+    /// Pure transformation: marks every running block cancelled
+    pub fn cancel_running(mut self) -> Self {
+        for block in self.blocks.iter_mut().filter(|b| b.status == BlockStatus::Running) {
+            block.status = BlockStatus::Cancelled;
+        }
+        self.relayout();
+        self
+    }
+
+    /// Pure transformation: folds (`Some(true)`), unfolds (`Some(false)`)
+    /// or toggles (`None`) a block
+    pub fn with_folded(mut self, request: &RequestId, folded: Option<bool>) -> Self {
+        if let Some(block) = self.blocks.iter_mut().find(|b| b.request == *request) {
+            block.folded = folded.unwrap_or(!block.folded);
+        }
+        self.relayout();
+        self
+    }
+
+    pub fn entries(&self) -> &[OutputEntry] {
+        &self.entries
     }
 
     /// Pure transformation: OutputLog → String → OutputLog
+    /// Appends a local `System` message
+    pub fn with_message(self, msg: String) -> Self {
+        self.with_entry(OutputEntry::new(EntryKind::System, msg))
+    }
+
+    /// Pure transformation: OutputLog → OutputEntry → OutputLog
     /// Maintains bounded size via functional composition
-    pub fn with_message(mut self, msg: String) -> Self {
-        self.lines.push(msg);
-        
-        // Maintain bound by removing oldest entries
-        if self.lines.len() > MAX_LOG_LINES {
-            self.lines.drain(0..(self.lines.len() - MAX_LOG_LINES));
+    pub fn with_entry(mut self, entry: OutputEntry) -> Self {
+        let block = entry
+            .request
+            .as_ref()
+            .and_then(|id| self.blocks.iter().position(|block| block.request == *id));
+        self.entries.push(entry);
+        match block {
+            None => {
+                self.block_at_end = false;
+                self.revision += 1;
+            }
+            // Lines of the last, unfolded block extend the view while it ends it
+            Some(i) if i + 1 == self.blocks.len() && self.block_at_end && !self.blocks[i].folded => {
+                self.revision += 1;
+            }
+            Some(_) => self.relayout(),
         }
-        
+        self.trim();
         self
     }
 
+    /// Maintains the bound by removing the oldest entries, a tenth of the
+    /// log at a time so views are not rebuilt on every append. A block goes
+    /// with the entries logged before it; its remaining lines become plain
+    /// output.
+    fn trim(&mut self) {
+        if self.entries.len() > self.limit {
+            let excess = self.entries.len() - self.limit + self.limit / 10;
+            self.entries.drain(0..excess);
+            self.first_seq += excess as u64;
+            let first_seq = self.first_seq;
+            self.blocks.retain(|block| block.anchor >= first_seq);
+            self.relayout();
+        }
+    }
+
     pub fn clear(mut self) -> Self {
-        self.lines.clear();
+        self.first_seq += self.entries.len() as u64;
+        self.entries.clear();
+        self.blocks.clear();
+        self.relayout();
         self
     }
+
+    /// Pure function: OutputLog × OutputView → displayed lines
+    pub fn view(&self, view: &OutputView) -> Vec<ViewLine<'_>> {
+        let visible = self.entries.iter().filter(|entry| view.shows(entry.kind));
+        if !view.grouped {
+            return self.block_view(view);
+        }
+
+        let visible: Vec<&OutputEntry> = visible.collect();
+        let mut lines = Vec::with_capacity(visible.len());
+        for kind in EntryKind::ALL {
+            let group: Vec<&OutputEntry> = visible.iter().copied().filter(|entry| entry.kind == kind).collect();
+            if !group.is_empty() {
+                lines.push(ViewLine::Header(kind, group.len()));
+                lines.extend(group.into_iter().map(ViewLine::Entry));
+            }
+        }
+        lines
+    }
+
+    /// Arrival order, with each block's entries gathered under its header
+    /// where the block was opened. While a kind filter is active, blocks
+    /// without matching entries are left out.
+    fn block_view<'a>(&'a self, view: &OutputView) -> Vec<ViewLine<'a>> {
+        let requests: HashSet<&RequestId> = self.blocks.iter().map(|block| &block.request).collect();
+        let in_block = |entry: &OutputEntry| entry.request.as_ref().is_some_and(|id| requests.contains(id));
+
+        let mut members: HashMap<&RequestId, Vec<&'a OutputEntry>> = HashMap::new();
+        for entry in self.entries.iter().filter(|entry| view.shows(entry.kind) && in_block(entry)) {
+            if let Some(id) = &entry.request {
+                members.entry(id).or_default().push(entry);
+            }
+        }
+
+        let mut block_lines = |block: &'a RequestBlock| -> Vec<ViewLine<'a>> {
+            let entries = members.remove(&block.request).unwrap_or_default();
+            if entries.is_empty() && !view.kinds.is_empty() {
+                return Vec::new();
+            }
+            let shown = if block.folded { &entries[..0] } else { &entries[..] };
+            std::iter::once(ViewLine::Block(block, entries.len()))
+                .chain(shown.iter().map(|entry| ViewLine::Entry(entry)))
+                .collect()
+        };
+
+        let mut lines = Vec::with_capacity(self.entries.len() + self.blocks.len());
+
+        let mut blocks = self.blocks.iter().peekable();
+        for (seq, entry) in (self.first_seq..).zip(&self.entries) {
+            while let Some(block) = blocks.next_if(|block| block.anchor <= seq) {
+                lines.extend(block_lines(block));
+            }
+            if view.shows(entry.kind) && !in_block(entry) {
+                lines.push(ViewLine::Entry(entry));
+            }
+        }
+        for block in blocks {
+            lines.extend(block_lines(block));
+        }
+        lines
+    }
+
+    /// Pure function: OutputLog → JSON lines, one entry per line
+    pub fn to_jsonl(&self) -> String {
+        self.entries
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect()
+    }
 }
 
 impl Default for OutputLog {
@@ -161,18 +551,128 @@ mod tests {
     fn test_output_log_append() {
         let log = OutputLog::new()
             .with_message("line1".into())
-            .with_message("line2".into());
-        assert_eq!(log.lines().len(), 2);
-        assert_eq!(log.lines()[0], "line1");
-        assert_eq!(log.lines()[1], "line2");
+            .with_entry(OutputEntry::new(EntryKind::Result, "line2").with_server(Some("s".into())).with_request(7.into()));
+        assert_eq!(log.entries().len(), 2);
+        assert_eq!(log.entries()[0].text, "line1");
+        assert_eq!(log.entries()[0].kind, EntryKind::System);
+        assert_eq!(log.entries()[1].server.as_deref(), Some("s"));
+        assert_eq!(log.entries()[1].request, Some(RequestId::Number(7)));
+    }
+
+    #[test]
+    fn test_entry_kind_parse() {
+        assert_eq!(EntryKind::parse("errors"), Some(EntryKind::Error));
+        assert_eq!(EntryKind::parse("Result"), Some(EntryKind::Result));
+        assert_eq!(EntryKind::parse("bogus"), None);
+    }
+
+    #[test]
+    fn test_output_view_filter_and_group() {
+        let log = OutputLog::new()
+            .with_entry(OutputEntry::new(EntryKind::Command, ":one"))
+            .with_entry(OutputEntry::new(EntryKind::Error, "bad"))
+            .with_entry(OutputEntry::new(EntryKind::Command, ":two"))
+            .with_message("hello".into());
+
+        let all = log.view(&OutputView::default());
+        assert_eq!(all.len(), 4);
+
+        let filtered = log.view(&OutputView { kinds: vec![EntryKind::Command], grouped: false, highlight: None });
+        let texts: Vec<String> = filtered.iter().map(ViewLine::text).collect();
+        assert_eq!(texts, vec![":one", ":two"]);
+
+        let grouped = log.view(&OutputView { kinds: vec![], grouped: true, highlight: None });
+        let texts: Vec<String> = grouped.iter().map(ViewLine::text).collect();
+        assert_eq!(
+            texts,
+            vec!["── command (2) ──", ":one", ":two", "── error (1) ──", "bad", "── system (1) ──", "hello"]
+        );
+    }
+
+    #[test]
+    fn test_request_blocks_gather_and_fold() {
+        let id = RequestId::Number(4);
+        let result = |text: &str| OutputEntry::new(EntryKind::Result, text).with_request(id.clone());
+        let log = OutputLog::new()
+            .with_message("before".into())
+            .with_block(id.clone(), "echo {\"text\":\"hi\"}".into())
+            .with_message("unrelated".into())
+            .with_entry(result("hi"))
+            .with_entry(result("there"))
+            .finish_block(&id, BlockStatus::Done);
+
+        let texts: Vec<String> = log.view(&OutputView::default()).iter().map(ViewLine::text).collect();
+        assert_eq!(texts[0], "before");
+        assert!(texts[1].starts_with("▾ #4 echo {\"text\":\"hi\"} · ✅ "), "{}", texts[1]);
+        assert_eq!(&texts[2..], ["hi", "there", "unrelated"]);
+
+        let log = log.with_folded(&id, None);
+        let texts: Vec<String> = log.view(&OutputView::default()).iter().map(ViewLine::text).collect();
+        assert_eq!(texts.len(), 3);
+        assert!(texts[1].starts_with("▸ #4 echo") && texts[1].ends_with("· 2 lines"), "{}", texts[1]);
+
+        let log = log.finish_block(&id, BlockStatus::Failed);
+        assert_eq!(log.block(&id).unwrap().status, BlockStatus::Done);
+    }
+
+    #[test]
+    fn test_running_block_without_output_is_shown() {
+        let log = OutputLog::new()
+            .with_block(RequestId::Number(1), "slow".into())
+            .cancel_running();
+        let texts: Vec<String> = log.view(&OutputView::default()).iter().map(ViewLine::text).collect();
+        assert_eq!(texts, vec!["▾ #1 slow · 🚫 cancelled"]);
+
+        let filtered = log.view(&OutputView { kinds: vec![EntryKind::Error], grouped: false, highlight: None });
+        assert!(filtered.is_empty());
+    }
+
+    #[test]
+    fn test_output_log_jsonl() {
+        let log = OutputLog::new().with_entry(OutputEntry::new(EntryKind::Result, "ok").with_request(3.into()));
+        let line: serde_json::Value = serde_json::from_str(log.to_jsonl().trim()).unwrap();
+        assert_eq!(line["kind"], "result");
+        assert_eq!(line["text"], "ok");
+        assert_eq!(line["request"], 3);
+        assert!(line["timestamp"].is_string());
+        assert!(line.get("server").is_none());
     }
 
     #[test]
     fn test_output_log_bounds() {
-        let mut log = OutputLog::new();
+        let mut log = OutputLog::with_limit(1000);
         for i in 0..1500 {
             log = log.with_message(format!("line{}", i));
         }
-        assert!(log.lines().len() <= MAX_LOG_LINES);
+        assert!(log.entries().len() <= 1000);
+        assert_eq!(log.entries().last().unwrap().text, "line1499");
+        assert_eq!(log.end_seq(), 1500);
+        assert_eq!(log.entries_since(1498).len(), 2);
+
+        let log = log.limited(10);
+        assert!(log.entries().len() <= 10);
+    }
+
+    #[test]
+    fn test_layout_revision_tracks_non_tail_changes() {
+        let id = RequestId::Number(1);
+        let log = OutputLog::new().with_message("a".into());
+        let layout = log.layout_revision();
+        let log = log.with_message("b".into());
+        assert_eq!(log.layout_revision(), layout);
+
+        let log = log.with_block(id.clone(), "echo".into());
+        assert!(log.layout_revision() > layout);
+        let layout = log.layout_revision();
+        let result = |text: &str| OutputEntry::new(EntryKind::Result, text).with_request(id.clone());
+        let log = log.with_entry(result("r1"));
+        assert_eq!(log.layout_revision(), layout);
+
+        // after a plain line the block no longer ends the view
+        let log = log.with_message("c".into()).with_entry(result("r2"));
+        assert!(log.layout_revision() > layout);
+        let layout = log.layout_revision();
+        let log = log.with_block(RequestId::Number(2), "later".into()).with_entry(result("r3"));
+        assert!(log.layout_revision() > layout);
     }
 }
@@ -0,0 +1,28 @@
+// ============================================================================
+// src/test_support.rs - Fixtures shared by the unit tests
+// ============================================================================
+
+use std::fs;
+use std::path::PathBuf;
+
+/// Empty temp directory, removed again on drop
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// `mcp-client-<label>-<uuid>` in the system temp dir
+    pub fn new(label: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("mcp-client-{}-{}", label, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
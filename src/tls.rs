@@ -0,0 +1,475 @@
+// ============================================================================
+// src/tls.rs - Per-server TLS settings and certificate diagnostics
+// ============================================================================
+
+use crate::config::TlsConfig;
+use reqwest::{Certificate, Client, Identity};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("Cannot read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Invalid CA certificate in {path}: {reason}")]
+    InvalidCa { path: PathBuf, reason: String },
+    #[error("Invalid client certificate/key: {0}")]
+    InvalidIdentity(String),
+    #[error("Both tls.client_cert and tls.client_key must be set for mutual TLS")]
+    IncompleteIdentity,
+    #[error("Cannot build HTTP client: {0}")]
+    Build(String),
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, TlsError> {
+    fs::read(path).map_err(|source| TlsError::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Builds the HTTP client for one server, applying its TLS settings
+pub fn build_client(tls: Option<&TlsConfig>) -> Result<Client, TlsError> {
+    let mut builder = Client::builder().tls_info(true);
+
+    let Some(tls) = tls else {
+        return builder.build().map_err(|e| TlsError::Build(e.to_string()));
+    };
+
+    for path in &tls.ca_files {
+        let pem = read(path)?;
+        let certs = Certificate::from_pem_bundle(&pem).map_err(|e| TlsError::InvalidCa {
+            path: path.clone(),
+            reason: e.to_string(),
+        })?;
+        if certs.is_empty() {
+            return Err(TlsError::InvalidCa {
+                path: path.clone(),
+                reason: "no PEM certificates found".into(),
+            });
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    match (&tls.client_cert, &tls.client_key) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert = read(cert_path)?;
+            let key = read(key_path)?;
+            let identity = Identity::from_pkcs8_pem(&cert, &key).map_err(|e| {
+                let key_text = String::from_utf8_lossy(&key);
+                if key_text.contains("BEGIN RSA PRIVATE KEY") || key_text.contains("BEGIN EC PRIVATE KEY") {
+                    TlsError::InvalidIdentity(format!(
+                        "{} (key must be PKCS#8: convert with `openssl pkcs8 -topk8 -nocrypt -in {} -out key.pem`)",
+                        e,
+                        key_path.display()
+                    ))
+                } else {
+                    TlsError::InvalidIdentity(e.to_string())
+                }
+            })?;
+            builder = builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => return Err(TlsError::IncompleteIdentity),
+    }
+
+    if tls.danger_accept_invalid_certs {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    builder.build().map_err(|e| TlsError::Build(e.to_string()))
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Negotiated session details
+// ═══════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: String,
+    pub not_after: String,
+    pub sha256_fingerprint: String,
+}
+
+/// TLS state of the current connection, shown by `:mcp status`
+#[derive(Debug, Clone, Default)]
+pub struct TlsDetails {
+    pub peer: Option<PeerCertificate>,
+    pub custom_ca_count: usize,
+    pub client_certificate: bool,
+    pub verification_disabled: bool,
+}
+
+impl TlsDetails {
+    /// Extracts details from a response; None for plain HTTP connections
+    pub fn from_response(response: &reqwest::Response, tls: Option<&TlsConfig>) -> Option<Self> {
+        if response.url().scheme() != "https" {
+            return None;
+        }
+
+        let peer = response
+            .extensions()
+            .get::<reqwest::tls::TlsInfo>()
+            .and_then(|info| info.peer_certificate())
+            .and_then(parse_certificate);
+
+        Some(Self {
+            peer,
+            custom_ca_count: tls.map_or(0, |t| t.ca_files.len()),
+            client_certificate: tls.is_some_and(|t| t.client_cert.is_some()),
+            verification_disabled: tls.is_some_and(|t| t.danger_accept_invalid_certs),
+        })
+    }
+
+    /// Pure function: TlsDetails → Vec<String>
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.verification_disabled {
+            lines.push("⚠️  Certificate verification: DISABLED (danger_accept_invalid_certs)".to_string());
+        } else if self.custom_ca_count > 0 {
+            lines.push(format!(
+                "Certificate verification: system roots + {} custom CA file(s)",
+                self.custom_ca_count
+            ));
+        } else {
+            lines.push("Certificate verification: system roots".to_string());
+        }
+        lines.push(format!(
+            "Client certificate (mTLS): {}",
+            if self.client_certificate { "presented" } else { "none" }
+        ));
+        match &self.peer {
+            Some(peer) => {
+                lines.push(format!("Server subject: {}", peer.subject));
+                lines.push(format!("Server issuer: {}", peer.issuer));
+                lines.push(format!("Valid: {} → {}", peer.not_before, peer.not_after));
+                lines.push(format!("SHA-256: {}", peer.sha256_fingerprint));
+            }
+            None => lines.push("Server certificate: unavailable".to_string()),
+        }
+        lines
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Minimal X.509 reader (subject, issuer, validity)
+// ═══════════════════════════════════════════════════════════════
+
+/// Reads one DER TLV: returns (tag, content, rest)
+fn read_tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let len = rest[..count].iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        rest = &rest[count..];
+        len
+    };
+    if rest.len() < len {
+        return None;
+    }
+    Some((tag, &rest[..len], &rest[len..]))
+}
+
+fn attribute_name(oid: &[u8]) -> Option<&'static str> {
+    match oid {
+        [0x55, 0x04, 0x03] => Some("CN"),
+        [0x55, 0x04, 0x06] => Some("C"),
+        [0x55, 0x04, 0x07] => Some("L"),
+        [0x55, 0x04, 0x08] => Some("ST"),
+        [0x55, 0x04, 0x0a] => Some("O"),
+        [0x55, 0x04, 0x0b] => Some("OU"),
+        _ => None,
+    }
+}
+
+/// Name ::= SEQUENCE OF SET OF SEQUENCE { OID, value }
+fn format_name(name: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut sets = name;
+    while let Some((_, set, rest)) = read_tlv(sets) {
+        sets = rest;
+        let mut attrs = set;
+        while let Some((_, attr, rest)) = read_tlv(attrs) {
+            attrs = rest;
+            let Some((_, oid, value)) = read_tlv(attr) else { continue };
+            let Some((_, value, _)) = read_tlv(value) else { continue };
+            if let Some(name) = attribute_name(oid) {
+                parts.push(format!("{}={}", name, String::from_utf8_lossy(value)));
+            }
+        }
+    }
+    parts.join(", ")
+}
+
+/// UTCTime (YYMMDDHHMMSSZ) or GeneralizedTime (YYYYMMDDHHMMSSZ)
+/// Anything but plain digits is shown as found
+fn format_time(tag: u8, value: &[u8]) -> String {
+    let text = String::from_utf8_lossy(value).to_string();
+    let digits = value.strip_suffix(b"Z").unwrap_or(value);
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return text;
+    }
+    let digits = text.trim_end_matches('Z');
+    let full = match tag {
+        0x17 if digits.len() >= 12 => {
+            let century = if digits[..2].parse::<u32>().unwrap_or(0) >= 50 { "19" } else { "20" };
+            format!("{}{}", century, digits)
+        }
+        0x18 if digits.len() >= 14 => digits.to_string(),
+        _ => return text,
+    };
+    format!(
+        "{}-{}-{} {}:{}:{} UTC",
+        &full[0..4],
+        &full[4..6],
+        &full[6..8],
+        &full[8..10],
+        &full[10..12],
+        &full[12..14]
+    )
+}
+
+/// Pure function: DER certificate → PeerCertificate
+pub fn parse_certificate(der: &[u8]) -> Option<PeerCertificate> {
+    let (_, cert, _) = read_tlv(der)?;
+    let (_, tbs, _) = read_tlv(cert)?;
+
+    let mut fields = tbs;
+    // Optional explicit [0] version
+    if fields.first() == Some(&0xa0) {
+        fields = read_tlv(fields)?.2;
+    }
+    let (_, _serial, rest) = read_tlv(fields)?;
+    let (_, _signature, rest) = read_tlv(rest)?;
+    let (_, issuer, rest) = read_tlv(rest)?;
+    let (_, validity, rest) = read_tlv(rest)?;
+    let (_, subject, _) = read_tlv(rest)?;
+
+    let (before_tag, not_before, rest) = read_tlv(validity)?;
+    let (after_tag, not_after, _) = read_tlv(rest)?;
+
+    let fingerprint = Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":");
+
+    Some(PeerCertificate {
+        subject: format_name(subject),
+        issuer: format_name(issuer),
+        not_before: format_time(before_tag, not_before),
+        not_after: format_time(after_tag, not_after),
+        sha256_fingerprint: fingerprint,
+    })
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Readable certificate errors
+// ═══════════════════════════════════════════════════════════════
+
+/// Full error message including the source chain
+/// (reqwest hides the TLS reason in nested sources)
+pub fn error_chain(err: &(dyn std::error::Error + 'static)) -> String {
+    let mut parts = vec![err.to_string()];
+    let mut source = err.source();
+    while let Some(e) = source {
+        let text = e.to_string();
+        if !parts.iter().any(|p| p.contains(&text)) {
+            parts.push(text);
+        }
+        source = e.source();
+    }
+    parts.join(": ")
+}
+
+/// Pure function: error text → explanation of a TLS failure
+pub fn explain_tls_message(message: &str) -> Option<&'static str> {
+    let msg = message.to_lowercase();
+    if msg.contains("self signed") || msg.contains("self-signed") {
+        Some("the server uses a self-signed certificate; add it (or its CA) to tls.ca_files for this server")
+    } else if msg.contains("certificate has expired") || msg.contains("certificate expired") {
+        Some("the server certificate has expired (or the local clock is wrong)")
+    } else if msg.contains("hostname mismatch")
+        || msg.contains("not valid for name")
+        || msg.contains("doesn't match")
+        || msg.contains("does not match")
+    {
+        Some("the certificate was issued for a different host name than the one in the server URL")
+    } else if msg.contains("unable to get local issuer")
+        || msg.contains("unknown issuer")
+        || msg.contains("unable to verify the first certificate")
+        || msg.contains("certificate verify failed")
+    {
+        Some("the certificate is signed by a CA that is not trusted; add the private CA PEM to tls.ca_files")
+    } else if msg.contains("certificate required") || msg.contains("handshake failure") {
+        Some("the server rejected the TLS handshake; it may require a client certificate (tls.client_cert / tls.client_key)")
+    } else if msg.contains("wrong version number") {
+        Some("the server does not speak TLS on this port; check http:// vs https:// in the URL")
+    } else {
+        None
+    }
+}
+
+/// Describes a request error, appending a TLS explanation when one applies
+pub fn describe_error(err: &reqwest::Error) -> String {
+    let chain = error_chain(err);
+    match explain_tls_message(&chain) {
+        Some(hint) => format!("{} — 💡 {}", chain, hint),
+        None => chain,
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    const TEST_CERT_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBuDCCAV2gAwIBAgICEjQwCgYIKoZIzj0EAwIwOjEcMBoGA1UECgwTRXhhbXBs
+ZSBJbnRlcm5hbCBDQTEaMBgGA1UEAwwRbWNwLmludGVybmFsLnRlc3QwHhcNMjYx
+MDE4MTY0MTM3WhcNMzYxMDE1MTY0MTM3WjA6MRwwGgYDVQQKDBNFeGFtcGxlIElu
+dGVybmFsIENBMRowGAYDVQQDDBFtY3AuaW50ZXJuYWwudGVzdDBZMBMGByqGSM49
+AgEGCCqGSM49AwEHA0IABP4XFPyPlAVT4Ue6BQhqWiDAyp2RvATUVTZc2kB/Ur+o
+WcRcWZwKYEMG8NN4AVCxjAdaWAf1770wKA3vpdZ2gb6jUzBRMB0GA1UdDgQWBBSw
+uHZkUckVx+ajjFVJ+p18UsPY2zAfBgNVHSMEGDAWgBSwuHZkUckVx+ajjFVJ+p18
+UsPY2zAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0kAMEYCIQD8v+kqRIJI
+aMQ/J+Jfv9ADaEphVa9c6xY+YmJJWnecAgIhAKt1DoxES01ehWxM1h2mHezULfmz
+lfuPXW/WkXPb/H7H
+-----END CERTIFICATE-----
+";
+
+    fn test_cert_der() -> Vec<u8> {
+        let body: String = TEST_CERT_PEM
+            .lines()
+            .filter(|l| !l.starts_with("-----"))
+            .collect();
+        STANDARD.decode(body).unwrap()
+    }
+
+    /// Writes `content` to `name` in `dir`
+    fn temp_file(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_certificate() {
+        let cert = parse_certificate(&test_cert_der()).unwrap();
+        assert_eq!(cert.subject, "O=Example Internal CA, CN=mcp.internal.test");
+        assert_eq!(cert.issuer, cert.subject);
+        assert_eq!(cert.not_before, "2026-10-18 16:41:37 UTC");
+        assert_eq!(cert.not_after, "2036-10-15 16:41:37 UTC");
+        assert!(cert
+            .sha256_fingerprint
+            .starts_with("8B:C5:3F:27:7A:23:9B:F9"));
+    }
+
+    #[test]
+    fn test_parse_certificate_rejects_garbage() {
+        assert_eq!(parse_certificate(&[]), None);
+        assert_eq!(parse_certificate(&[0x30, 0x82, 0xff]), None);
+    }
+
+    #[test]
+    fn test_format_time_keeps_malformed_values() {
+        assert_eq!(format_time(0x17, b"261018164137Z"), "2026-10-18 16:41:37 UTC");
+        assert_eq!(format_time(0x18, "202é1018164137Z".as_bytes()), "202é1018164137Z");
+        assert_eq!(format_time(0x17, &[0xff; 13]), "\u{fffd}".repeat(13));
+        assert_eq!(format_time(0x18, b"20261018"), "20261018");
+    }
+
+    #[test]
+    fn test_build_client_with_custom_ca() {
+        let dir = TempDir::new("tls");
+        let ca = temp_file(&dir, "ca.pem", TEST_CERT_PEM);
+        let tls = TlsConfig {
+            ca_files: vec![ca],
+            ..TlsConfig::default()
+        };
+        assert!(build_client(Some(&tls)).is_ok());
+    }
+
+    #[test]
+    fn test_build_client_errors() {
+        let dir = TempDir::new("tls");
+        let missing = TlsConfig {
+            ca_files: vec![PathBuf::from("/nonexistent/ca.pem")],
+            ..TlsConfig::default()
+        };
+        assert!(matches!(build_client(Some(&missing)), Err(TlsError::Read { .. })));
+
+        let empty = TlsConfig {
+            ca_files: vec![temp_file(&dir, "empty.pem", "not a certificate")],
+            ..TlsConfig::default()
+        };
+        assert!(matches!(build_client(Some(&empty)), Err(TlsError::InvalidCa { .. })));
+
+        let half = TlsConfig {
+            client_cert: Some(PathBuf::from("client.pem")),
+            ..TlsConfig::default()
+        };
+        assert!(matches!(build_client(Some(&half)), Err(TlsError::IncompleteIdentity)));
+    }
+
+    #[test]
+    fn test_explain_tls_message() {
+        assert!(explain_tls_message("error:0A000086:SSL routines:tls_post_process_server_certificate:certificate verify failed:../ssl/statem/statem_clnt.c:1889: (self-signed certificate)")
+            .unwrap()
+            .contains("self-signed"));
+        assert!(explain_tls_message("certificate verify failed: (unable to get local issuer certificate)")
+            .unwrap()
+            .contains("tls.ca_files"));
+        assert!(explain_tls_message("certificate verify failed (certificate has expired)")
+            .unwrap()
+            .contains("expired"));
+        assert!(explain_tls_message("Hostname mismatch")
+            .unwrap()
+            .contains("host name"));
+        assert!(explain_tls_message("sslv3 alert handshake failure")
+            .unwrap()
+            .contains("client certificate"));
+        assert_eq!(explain_tls_message("connection refused"), None);
+    }
+
+    #[test]
+    fn test_summary_lines() {
+        let details = TlsDetails {
+            peer: parse_certificate(&test_cert_der()),
+            custom_ca_count: 1,
+            client_certificate: true,
+            verification_disabled: false,
+        };
+        let lines = details.summary_lines();
+        assert!(lines[0].contains("1 custom CA"));
+        assert!(lines[1].contains("presented"));
+        assert!(lines.iter().any(|l| l.contains("CN=mcp.internal.test")));
+
+        let insecure = TlsDetails {
+            verification_disabled: true,
+            ..TlsDetails::default()
+        };
+        assert!(insecure.summary_lines()[0].contains("DISABLED"));
+    }
+}
@@ -204,7 +204,7 @@ fn wrap_text(text: &str, width: usize, indent: usize) -> Vec<String> {
     for word in text.split_whitespace() {
         if current_line.is_empty() {
             current_line = word.to_string();
-        } else if current_line.len() + word.len() + 1 <= effective_width {
+        } else if current_line.len() + word.len() < effective_width {
             current_line.push(' ');
             current_line.push_str(word);
         } else {
@@ -220,6 +220,79 @@ fn wrap_text(text: &str, width: usize, indent: usize) -> Vec<String> {
     lines
 }
 
+// ============================================================================
+// Result formatting
+// ============================================================================
+
+/// Lines of one result shown in the output log; `:result <n>` shows the rest
+const RESULT_DISPLAY_LINES: usize = 200;
+
+/// One line of a result preview
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResultLine {
+    /// Opens a content item; highlighting starts afresh after it
+    Heading(String),
+    Text(String),
+}
+
+impl ResultLine {
+    pub fn text(&self) -> &str {
+        match self {
+            ResultLine::Heading(text) | ResultLine::Text(text) => text,
+        }
+    }
+
+    pub fn into_text(self) -> String {
+        match self {
+            ResultLine::Heading(text) | ResultLine::Text(text) => text,
+        }
+    }
+}
+
+/// Pure function: JSON-RPC result → every display line. Text content of a
+/// tool result is shown as-is (pretty-printed if it is JSON); any other
+/// result is dumped as pretty JSON.
+pub fn result_lines(result: &Value) -> Vec<String> {
+    content_lines(result, "📋 Tool result:").into_iter().map(ResultLine::into_text).collect()
+}
+
+/// Pure function: JSON-RPC result × result number → preview for the output
+/// log, cut at RESULT_DISPLAY_LINES with a pointer to the full result
+pub fn format_result(result: &Value, number: usize) -> Vec<ResultLine> {
+    let mut lines = content_lines(result, &format!("📋 Tool result {}:", number));
+    let total = lines.len();
+    if total > RESULT_DISPLAY_LINES {
+        lines.truncate(RESULT_DISPLAY_LINES);
+        lines.push(ResultLine::Text(String::new()));
+        lines.push(ResultLine::Text(format!(
+            "⚠️  Showing {} of {} lines - :result {} shows all of it",
+            RESULT_DISPLAY_LINES, total, number
+        )));
+    }
+    lines
+}
+
+fn content_lines(result: &Value, header: &str) -> Vec<ResultLine> {
+    let text_lines = |text: &str| text.lines().map(|line| ResultLine::Text(line.to_string())).collect::<Vec<_>>();
+    let Some(content) = result.get("content").and_then(|c| c.as_array()) else {
+        return text_lines(&pretty(result));
+    };
+
+    let mut lines = Vec::new();
+    for text in content.iter().filter_map(|item| item.get("text")?.as_str()) {
+        lines.push(ResultLine::Heading(header.to_string()));
+        match serde_json::from_str::<Value>(text) {
+            Ok(json) => lines.extend(text_lines(&pretty(&json))),
+            Err(_) => lines.extend(text_lines(text)),
+        }
+    }
+    lines
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -287,4 +360,33 @@ mod tests {
         assert!(hint.contains("<query:string>"));
         assert!(hint.contains("[limit:integer]"));
     }
+
+    #[test]
+    fn test_format_result() {
+        let texts = |lines: Vec<ResultLine>| lines.iter().map(|line| line.text().to_string()).collect::<Vec<_>>();
+        let text = json!({"content": [{"type": "text", "text": "hello\nworld"}, {"type": "text", "text": "again"}]});
+        assert_eq!(
+            format_result(&text, 1),
+            vec![
+                ResultLine::Heading("📋 Tool result 1:".into()),
+                ResultLine::Text("hello".into()),
+                ResultLine::Text("world".into()),
+                ResultLine::Heading("📋 Tool result 1:".into()),
+                ResultLine::Text("again".into()),
+            ]
+        );
+        let text = json!({"content": [{"type": "text", "text": "hello\nworld"}]});
+        assert_eq!(result_lines(&text), vec!["📋 Tool result:", "hello", "world"]);
+
+        let embedded = json!({"content": [{"type": "text", "text": "{\"layers\": 4}"}]});
+        assert_eq!(texts(format_result(&embedded, 2)), vec!["📋 Tool result 2:", "{", "  \"layers\": 4", "}"]);
+
+        let long = json!({"content": [{"type": "text", "text": "x\n".repeat(250)}]});
+        let lines = format_result(&long, 3);
+        assert_eq!(lines.len(), RESULT_DISPLAY_LINES + 2);
+        assert_eq!(lines.last().unwrap().text(), "⚠️  Showing 200 of 251 lines - :result 3 shows all of it");
+        assert_eq!(result_lines(&long).len(), 251);
+
+        assert_eq!(texts(format_result(&json!({"uri": "a"}), 4)), vec!["{", "  \"uri\": \"a\"", "}"]);
+    }
 }
\ No newline at end of file
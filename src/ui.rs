@@ -1,14 +1,20 @@
 use crate::app::App;
 use crate::mode::Mode;
 use crate::completion::CompletionResult;
+use crate::inspector::TrafficDirection;
+use crate::highlight::Palette;
+use crate::json_tree::{NodeKind, TreeRow};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarState, Wrap},
     Frame,
 };
 
+/// Status bar spinner while requests are in flight
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
 /// Pure UI rendering logic - no side effects
 #[derive(Default)]
 pub struct UI;
@@ -18,7 +24,7 @@ impl UI {
         Self
     }
 
-    /// Pure function: Frame × App → ()
+    // Pure function: Frame × App → ()
     // pub fn render(&self, frame: &mut Frame, app: &App) {
     //     let layout = Self::create_layout(frame.area());
 
@@ -55,20 +61,24 @@ impl UI {
 fn render_output(&self, frame: &mut Frame, app: &mut App, area: Rect) {
 
     app.set_output_width(area.width);
-    app.set_output_height(area.height); // ADDED THIS LINE
-    let max_offset = app.max_scroll_offset();
-    let scroll = app.scroll_offset().min(max_offset);
-    app.set_scroll_offset(scroll); // Zsynchronizuj stan
-    // compute visual length using app.visual_lines_count()
-    //let content_visual_len = app.visual_lines_count();
+    app.set_output_height(area.height);
+    app.sync_scroll();
+    let scroll = app.scroll_offset();
     let content_visual = app.content_visual_len();
 
-
-
+    // Only the visible rows are copied; wrapping happened once in the cache
+    let current = app.current_block_rows().unwrap_or_default();
     let lines: Vec<Line> = app
-        .output()
+        .output_window()
         .iter()
-        .map(|s| Line::from(s.as_str()))
+        .enumerate()
+        .map(|(i, row)| {
+            if current.contains(&(scroll + i)) {
+                row.clone().patch_style(Modifier::REVERSED)
+            } else {
+                row.clone()
+            }
+        })
         .collect();
 
     let paragraph = Paragraph::new(lines)
@@ -77,9 +87,7 @@ fn render_output(&self, frame: &mut Frame, app: &mut App, area: Rect) {
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::DarkGray))
                 .title(Span::styled(" Output ", Style::default().fg(Color::Cyan)))
-        )
-        .wrap(Wrap { trim: false })
-        .scroll((scroll as u16, 0)); // visual scroll ✅
+        );
 
     frame.render_widget(paragraph, area);
 
@@ -98,6 +106,147 @@ fn render_output(&self, frame: &mut Frame, app: &mut App, area: Rect) {
     }
 }
 
+    // ═══════════════════════════════════════════════════════════════
+    // Result pager - only the visible window of lines is built
+    // ═══════════════════════════════════════════════════════════════
+
+    fn render_pager(&self, frame: &mut Frame, app: &mut App, area: Rect) {
+        app.set_pager_height(area.height.saturating_sub(2) as usize);
+        let Some(pager) = app.pager() else {
+            return;
+        };
+
+        let first = pager.offset() + 1;
+        let last = pager.offset() + pager.visible().len();
+        let position = format!(" {}-{}/{} ", first.min(last), last, pager.len());
+        let lines: Vec<Line> = pager.visible().iter().map(|line| Line::from(line.as_str())).collect();
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::LightGreen))
+                    .title(Span::styled(format!(" {} ", pager.title()), Style::default().fg(Color::LightGreen)))
+                    .title_bottom(Line::from(position).right_aligned()),
+            )
+            .scroll((0, pager.column().min(u16::MAX as usize) as u16));
+        frame.render_widget(paragraph, area);
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // JSON tree - only the visible rows are built
+    // ═══════════════════════════════════════════════════════════════
+
+    fn render_tree(&self, frame: &mut Frame, app: &mut App, area: Rect) {
+        app.set_tree_height(area.height.saturating_sub(2) as usize);
+        let palette = app.highlight().map(|theme| theme.palette());
+        let Some(tree) = app.tree() else {
+            return;
+        };
+
+        let lines: Vec<Line> = tree
+            .visible()
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let line = tree_line(row, palette.as_ref());
+                if tree.offset() + i == tree.cursor() {
+                    line.style(Style::default().add_modifier(Modifier::REVERSED))
+                } else {
+                    line
+                }
+            })
+            .collect();
+        let position = format!(" {} · {}/{} ", tree.current_path(), tree.cursor() + 1, tree.rows().len());
+
+        let paragraph = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::LightMagenta))
+                .title(Span::styled(format!(" {} ", tree.title()), Style::default().fg(Color::LightMagenta)))
+                .title_bottom(Line::from(position).right_aligned()),
+        );
+        frame.render_widget(paragraph, area);
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    // Inspector pane - traffic list with optional body view
+    // ═══════════════════════════════════════════════════════════════
+
+    fn render_inspector(&self, frame: &mut Frame, app: &App, area: Rect) {
+        let inspector = app.inspector();
+        let entries = inspector.filtered();
+        let selected = inspector.selected();
+
+        let (list_area, detail_area) = if inspector.is_expanded() {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .split(area);
+            (chunks[0], Some(chunks[1]))
+        } else {
+            (area, None)
+        };
+
+        let items: Vec<ListItem> = entries
+            .iter()
+            .map(|entry| {
+                let color = match (entry.is_error, entry.direction) {
+                    (true, _) => Color::Red,
+                    (false, TrafficDirection::Outgoing) => Color::Yellow,
+                    (false, TrafficDirection::Incoming) => Color::Cyan,
+                };
+                ListItem::new(Line::from(Span::styled(entry.summary(), Style::default().fg(color))))
+            })
+            .collect();
+
+        let title = match inspector.filter() {
+            Some(filter) => format!(" Inspector ({}/{}) filter: {} ", entries.len(), inspector.len(), filter),
+            None => format!(" Inspector ({}) ", inspector.len()),
+        };
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::DarkGray))
+                    .title(Span::styled(title, Style::default().fg(Color::LightBlue)))
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▶ ");
+
+        let mut state = ListState::default();
+        if !entries.is_empty() {
+            state.select(Some(selected));
+        }
+        frame.render_stateful_widget(list, list_area, &mut state);
+
+        if let (Some(detail_area), Some(entry)) = (detail_area, entries.get(selected)) {
+            let body = serde_json::to_string_pretty(&entry.body).unwrap_or_default();
+            let title = format!(
+                " {} {} ",
+                entry.id.as_ref().map_or_else(|| "-".to_string(), |id| format!("#{}", id)),
+                entry.method.as_deref().unwrap_or("?")
+            );
+            let lines: Vec<Line> = body.lines().map(Line::from).collect();
+            let paragraph = Paragraph::new(lines)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::DarkGray))
+                        .title(Span::styled(title, Style::default().fg(Color::LightBlue)))
+                )
+                .wrap(Wrap { trim: false })
+                .scroll((inspector.detail_scroll(), 0));
+            frame.render_widget(paragraph, detail_area);
+        }
+    }
+
     // ═══════════════════════════════════════════════════════════════
     // Status bar rendering - shows mode and status message
     // ═══════════════════════════════════════════════════════════════
@@ -110,6 +259,12 @@ fn render_output(&self, frame: &mut Frame, app: &mut App, area: Rect) {
             ("TOOL", Color::Yellow)
         } else if app.server_selection().is_some() {
             ("SELECT", Color::Magenta)
+        } else if app.tree().is_some() && mode == Mode::Normal {
+            ("TREE", Color::LightMagenta)
+        } else if app.pager().is_some() && mode == Mode::Normal {
+            ("PAGER", Color::LightGreen)
+        } else if app.inspector().is_visible() && mode == Mode::Normal {
+            ("INSPECT", Color::LightBlue)
         } else {
             (mode.name(), mode.color())
         };
@@ -127,7 +282,9 @@ fn render_output(&self, frame: &mut Frame, app: &mut App, area: Rect) {
             Style::default().fg(Color::White),
         );
 
-        let help_text = if app.tool_selection().is_some() {
+        let help_text = if app.is_confirming() {
+            Span::styled(" y:Run | any other key:Cancel ", Style::default().fg(Color::DarkGray))
+        } else if app.tool_selection().is_some() {
             Span::styled(
                 " ↑↓:Navigate | Enter:Run | Esc:Cancel ",
                 Style::default().fg(Color::DarkGray),
@@ -137,6 +294,21 @@ fn render_output(&self, frame: &mut Frame, app: &mut App, area: Rect) {
                 " ↑↓:Navigate | Enter:Select | Esc:Cancel ",
                 Style::default().fg(Color::DarkGray),
             )
+        } else if app.tree().is_some() && mode == Mode::Normal {
+            Span::styled(
+                " j/k:Move | h/l:Fold | ↵:Toggle | E/C:All | y/Y:Yank value/path | q:Close ",
+                Style::default().fg(Color::DarkGray),
+            )
+        } else if app.pager().is_some() && mode == Mode::Normal {
+            Span::styled(
+                " j/k:Scroll | PgUp/PgDn:Page | g/G:Top/Bottom | h/l:Shift | t:Tree | q:Close ",
+                Style::default().fg(Color::DarkGray),
+            )
+        } else if app.inspector().is_visible() && mode == Mode::Normal {
+            Span::styled(
+                " j/k:Select | ↵:Expand | PgUp/PgDn:Scroll body | Esc:Close ",
+                Style::default().fg(Color::DarkGray),
+            )
         } else {
             Span::styled(
             format!(" {} ", mode.help_text()),
@@ -144,7 +316,30 @@ fn render_output(&self, frame: &mut Frame, app: &mut App, area: Rect) {
             )
         };
 
-        let line = Line::from(vec![mode_indicator, status_text, help_text]);
+        let mut spans = vec![mode_indicator];
+        if let Some(profile) = app.profile() {
+            let style = if app.is_production() {
+                Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Black).bg(Color::Cyan)
+            };
+            spans.push(Span::styled(format!(" {} ", profile), style));
+        }
+        if !app.in_flight().is_empty() {
+            let frame = SPINNER[app.spinner_frame() % SPINNER.len()];
+            spans.push(Span::styled(
+                format!(" {} {} in flight ", frame, app.in_flight().len()),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        if app.is_recording() {
+            spans.push(Span::styled(
+                " ● REC ",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+        }
+        spans.extend([status_text, help_text]);
+        let line = Line::from(spans);
 
         let paragraph = Paragraph::new(line)
             .style(Style::default().bg(Color::Black));
@@ -286,7 +481,15 @@ fn render_output(&self, frame: &mut Frame, app: &mut App, area: Rect) {
     pub fn render(&self, frame: &mut Frame, app: &mut App) {
         let layout = Self::create_layout(frame.area());
 
-        self.render_output(frame, app, layout.output);
+        if app.tree().is_some() {
+            self.render_tree(frame, app, layout.output);
+        } else if app.pager().is_some() {
+            self.render_pager(frame, app, layout.output);
+        } else if app.inspector().is_visible() {
+            self.render_inspector(frame, app, layout.output);
+        } else {
+            self.render_output(frame, app, layout.output);
+        }
         self.render_status_bar(frame, app, layout.status);
         self.render_input_line(frame, app, layout.input);
 
@@ -302,6 +505,26 @@ fn render_output(&self, frame: &mut Frame, app: &mut App, area: Rect) {
 
 
 
+/// Pure function: TreeRow × highlight palette → indented line, colored
+/// like highlighted JSON unless highlighting is off
+fn tree_line(row: &TreeRow, palette: Option<&Palette>) -> Line<'static> {
+    let style = |pick: fn(&Palette) -> Style| palette.map_or_else(Style::default, pick);
+    let mut spans = vec![Span::raw(format!("{}{}", "  ".repeat(row.depth), row.marker()))];
+    if !row.label.is_empty() {
+        spans.push(Span::styled(row.label.clone(), style(|p| p.key)));
+        spans.push(Span::styled(": ", style(|p| p.punctuation)));
+    }
+    let value_style = match row.kind {
+        NodeKind::Object | NodeKind::Array => style(|p| p.punctuation),
+        NodeKind::String => style(|p| p.string),
+        NodeKind::Number => style(|p| p.number),
+        NodeKind::Bool => style(|p| p.boolean),
+        NodeKind::Null => style(|p| p.null),
+    };
+    spans.push(Span::styled(row.summary.clone(), value_style));
+    Line::from(spans)
+}
+
 // ═══════════════════════════════════════════════════════════════
 // Layout helper structure
 // ═══════════════════════════════════════════════════════════════
@@ -0,0 +1,298 @@
+// ============================================================================
+// src/viewport.rs - Styled, wrapped output rows cached per width
+// ============================================================================
+
+use crate::highlight::Highlighter;
+use crate::jsonrpc::RequestId;
+use crate::state::{BlockStatus, EntryKind, OutputEntry, OutputLog, OutputView, ViewLine};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use std::ops::Range;
+use unicode_width::UnicodeWidthChar;
+
+/// What the cached rows were built from, apart from appended entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RowsKey {
+    layout_revision: u64,
+    view: OutputView,
+    width: usize,
+}
+
+/// Every visual row of the output pane, built once per width and log
+/// layout. Lines appended at the end of the log are wrapped and added on
+/// their own; anything else (resize, folds, filters, highlighting, block
+/// updates) rebuilds the rows. Rendering then only copies the visible window.
+/// Lines are highlighted before wrapping, so colors carry over wrapped rows.
+#[derive(Debug, Default)]
+pub struct OutputRows {
+    key: Option<RowsKey>,
+    /// `OutputLog::revision` the rows reflect
+    revision: u64,
+    /// Sequence number of the first entry not covered yet
+    end_seq: u64,
+    rows: Vec<Line<'static>>,
+    /// Rows of each block header, for jumping and highlighting
+    headers: Vec<(RequestId, Range<usize>)>,
+    /// Fence state after the last row, for appending
+    highlighter: Option<Highlighter>,
+}
+
+impl OutputRows {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Rows `offset..offset + height`, clipped to the content
+    pub fn window(&self, offset: usize, height: usize) -> &[Line<'static>] {
+        let start = offset.min(self.rows.len());
+        let end = offset.saturating_add(height).min(self.rows.len());
+        &self.rows[start..end]
+    }
+
+    /// Block headers with the rows they occupy, in display order
+    pub fn headers(&self) -> &[(RequestId, Range<usize>)] {
+        &self.headers
+    }
+
+    pub fn header_rows(&self, request: &RequestId) -> Option<Range<usize>> {
+        self.headers.iter().find(|(id, _)| id == request).map(|(_, rows)| rows.clone())
+    }
+
+    /// Brings the rows up to date with the log; cheap when nothing changed
+    /// or when lines were only appended
+    pub fn update(&mut self, log: &OutputLog, view: &OutputView, width: usize) {
+        let key = RowsKey { layout_revision: log.layout_revision(), view: view.clone(), width: width.max(1) };
+        if self.key.as_ref() == Some(&key) && self.revision == log.revision() {
+            return;
+        }
+
+        let appended = log.entries_since(self.end_seq);
+        // A block left out by the kind filter needs its header once it has a shown line
+        let headed = |entry: &OutputEntry| {
+            entry.request.as_ref().is_none_or(|id| log.block(id).is_none() || self.header_rows(id).is_some())
+        };
+        let extend = !view.grouped && appended.iter().filter(|entry| view.shows(entry.kind)).all(headed);
+        if self.key.as_ref() == Some(&key) && extend {
+            for entry in appended.iter().filter(|entry| view.shows(entry.kind)) {
+                self.rows.extend(wrap(entry_line(entry, self.highlighter.as_mut()), key.width));
+            }
+        } else {
+            self.rows.clear();
+            self.headers.clear();
+            self.highlighter = view.highlight.map(Highlighter::new);
+            for line in log.view(view) {
+                let start = self.rows.len();
+                self.rows.extend(wrap(styled(&line, self.highlighter.as_mut()), key.width));
+                if let ViewLine::Block(block, _) = line {
+                    self.headers.push((block.request.clone(), start..self.rows.len()));
+                }
+            }
+        }
+
+        self.key = Some(key);
+        self.revision = log.revision();
+        self.end_seq = log.end_seq();
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Styling
+// ═══════════════════════════════════════════════════════════════
+
+/// Pure function: EntryKind → Style
+pub fn entry_style(kind: EntryKind) -> Style {
+    match kind {
+        EntryKind::Command => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        EntryKind::Error => Style::default().fg(Color::Red),
+        EntryKind::Debug => Style::default().fg(Color::DarkGray),
+        EntryKind::Notification => Style::default().fg(Color::Cyan),
+        EntryKind::Result | EntryKind::System => Style::default(),
+    }
+}
+
+/// Pure function: BlockStatus → header Style
+pub fn block_style(status: BlockStatus) -> Style {
+    let color = match status {
+        BlockStatus::Running => Color::LightBlue,
+        BlockStatus::Done => Color::Green,
+        BlockStatus::Failed | BlockStatus::TimedOut => Color::Red,
+        BlockStatus::Cancelled => Color::DarkGray,
+    };
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
+}
+
+fn entry_line(entry: &OutputEntry, highlighter: Option<&mut Highlighter>) -> Line<'static> {
+    match highlighter.and_then(|h| h.spans(entry)) {
+        Some(spans) => Line::from(spans),
+        None => Line::from(Span::styled(entry.text.clone(), entry_style(entry.kind))),
+    }
+}
+
+fn styled(line: &ViewLine<'_>, mut highlighter: Option<&mut Highlighter>) -> Line<'static> {
+    // a header ends whatever result was being highlighted
+    if let (Some(highlighter), ViewLine::Header(..) | ViewLine::Block(..)) = (highlighter.as_deref_mut(), line) {
+        highlighter.reset();
+    }
+    match line {
+        ViewLine::Header(kind, _) => Line::from(Span::styled(line.text(), entry_style(*kind).add_modifier(Modifier::BOLD))),
+        ViewLine::Block(block, _) => Line::from(Span::styled(line.text(), block_style(block.status))),
+        ViewLine::Entry(entry) => entry_line(entry, highlighter),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Wrapping
+// ═══════════════════════════════════════════════════════════════
+
+/// Pure function: Line × width → rows of at most `width` columns. Breaks
+/// at any character, keeps span styles and always yields at least one row.
+pub fn wrap(line: Line<'static>, width: usize) -> Vec<Line<'static>> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    let mut row: Vec<Span<'static>> = Vec::new();
+    let mut used = 0;
+
+    for span in line.spans {
+        let mut piece = String::new();
+        for c in span.content.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w > width && used > 0 {
+                if !piece.is_empty() {
+                    row.push(Span::styled(std::mem::take(&mut piece), span.style));
+                }
+                rows.push(Line::from(std::mem::take(&mut row)));
+                used = 0;
+            }
+            piece.push(c);
+            used += w;
+        }
+        if !piece.is_empty() {
+            row.push(Span::styled(piece, span.style));
+        }
+    }
+    rows.push(Line::from(row));
+    rows
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Tests
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::highlight::Theme;
+
+    fn texts(rows: &[Line<'static>]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    #[test]
+    fn test_wrap_keeps_styles_and_widths() {
+        let line = Line::from(vec![
+            Span::styled("abcd", Style::default().fg(Color::Red)),
+            Span::raw("efg"),
+        ]);
+        let rows = wrap(line, 3);
+        assert_eq!(texts(&rows), vec!["abc", "def", "g"]);
+        assert_eq!(rows[1].spans[0].style.fg, Some(Color::Red));
+        assert_eq!(rows[1].spans[1].style.fg, None);
+
+        assert_eq!(texts(&wrap(Line::from(""), 10)), vec![""]);
+        // a wide character never straddles two rows
+        assert_eq!(texts(&wrap(Line::from("ab✅"), 3)), vec!["ab", "✅"]);
+    }
+
+    #[test]
+    fn test_rows_append_and_rebuild() {
+        let view = OutputView::default();
+        let mut rows = OutputRows::new();
+        let log = OutputLog::new().with_message("hello world".into());
+        rows.update(&log, &view, 5);
+        assert_eq!(texts(rows.window(0, 10)), vec!["hello", " worl", "d"]);
+
+        let log = log.with_message("x".into());
+        rows.update(&log, &view, 5);
+        assert_eq!(rows.len(), 4);
+
+        rows.update(&log, &view, 20);
+        assert_eq!(texts(rows.window(0, 10)), vec!["hello world", "x"]);
+
+        let id = RequestId::Number(2);
+        let log = log
+            .with_block(id.clone(), "echo".into())
+            .with_entry(OutputEntry::new(EntryKind::Result, "r").with_request(id.clone()));
+        rows.update(&log, &view, 40);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows.header_rows(&id), Some(2..3));
+        assert_eq!(texts(rows.window(3, 5)), vec!["r"]);
+    }
+
+    #[test]
+    fn test_highlighting_survives_wrapping() {
+        let palette = Theme::Dark.palette();
+        let view = OutputView { highlight: Some(Theme::Dark), ..OutputView::default() };
+        let mut rows = OutputRows::new();
+        let log = OutputLog::new()
+            .with_entry(OutputEntry::new(EntryKind::Result, "📋 Tool result 1:"))
+            .with_entry(OutputEntry::new(EntryKind::Result, r#"  "name": "a long value","#));
+        rows.update(&log, &view, 12);
+        assert_eq!(texts(rows.window(2, 5)), vec!["  \"name\": \"a", " long value\"", ","]);
+        assert_eq!(rows.window(3, 1)[0].spans[0].style, palette.string);
+
+        // toggling highlighting off rebuilds the cached rows plain
+        let plain = OutputView::default();
+        rows.update(&log, &plain, 12);
+        assert!(rows.window(3, 1)[0].spans.iter().all(|span| span.style == Style::default()));
+    }
+
+    #[test]
+    fn test_large_log_is_appended_incrementally() {
+        let view = OutputView::default();
+        let mut rows = OutputRows::new();
+        let mut log = OutputLog::with_limit(200_000);
+        for i in 0..100_000 {
+            log = log.with_message(format!("line {}", i));
+        }
+        rows.update(&log, &view, 80);
+        assert_eq!(rows.len(), 100_000);
+
+        let log = log.with_message("tail".into());
+        rows.update(&log, &view, 80);
+        assert_eq!(texts(rows.window(100_000, 5)), vec!["tail"]);
+    }
+
+    #[test]
+    fn test_large_block_is_appended_incrementally() {
+        let view = OutputView::default();
+        let mut rows = OutputRows::new();
+        let id = RequestId::Number(1);
+        let line = |text: String| OutputEntry::new(EntryKind::Result, text).with_request(id.clone());
+        let mut log = OutputLog::with_limit(200_000).with_block(id.clone(), "dump".into());
+        for i in 0..100_000 {
+            log = log.with_entry(line(format!("line {}", i)));
+        }
+        rows.update(&log, &view, 80);
+        assert_eq!(rows.len(), 100_001);
+
+        let layout = log.layout_revision();
+        let log = log.with_entry(line("tail".into()));
+        assert_eq!(log.layout_revision(), layout);
+        rows.update(&log, &view, 80);
+        assert_eq!(texts(rows.window(100_001, 5)), vec!["tail"]);
+
+        // once a plain line follows the block, its lines go back in above it
+        let log = log.with_message("plain".into()).with_entry(line("late".into()));
+        assert_ne!(log.layout_revision(), layout);
+        rows.update(&log, &view, 80);
+        assert_eq!(texts(rows.window(100_002, 5)), vec!["late", "plain"]);
+    }
+}
@@ -0,0 +1,108 @@
+// ============================================================================
+// src/watch.rs - Config file watching for hot-reload
+// ============================================================================
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::ffi::OsString;
+use std::fmt;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Quiet period after the last change before a reload, so editors that
+/// write in several steps are reloaded once, with the finished file
+pub const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches the directory of a config file (editors and our own atomic
+/// save replace the file, which a watch on the file itself would lose)
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+    changes: mpsc::Receiver<()>,
+    last_change: Option<Instant>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path) -> notify::Result<Self> {
+        let name: OsString = path
+            .file_name()
+            .ok_or_else(|| notify::Error::generic("config path has no file name"))?
+            .to_owned();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => Path::new(".").to_path_buf(),
+        };
+
+        let (tx, changes) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            let touches_config = event.paths.iter().any(|p| p.file_name() == Some(name.as_os_str()));
+            if touches_config && !matches!(event.kind, EventKind::Access(_)) {
+                let _ = tx.send(());
+            }
+        })?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self { _watcher: watcher, changes, last_change: None })
+    }
+
+    /// True once per burst of changes, when the file has been quiet for
+    /// [`DEBOUNCE`]
+    pub fn poll(&mut self) -> bool {
+        self.poll_at(Instant::now())
+    }
+
+    fn poll_at(&mut self, now: Instant) -> bool {
+        while self.changes.try_recv().is_ok() {
+            self.last_change = Some(now);
+        }
+        match self.last_change {
+            Some(at) if now.duration_since(at) >= DEBOUNCE => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for ConfigWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigWatcher").field("last_change", &self.last_change).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_change_is_reported_once_after_debounce() {
+        let dir = TempDir::new("watch");
+        let path = dir.join("config.toml");
+        let mut watcher = ConfigWatcher::new(&path).unwrap();
+
+        fs::write(dir.join("other.toml"), "x = 1").unwrap();
+        fs::write(&path, "mcp_servers = []").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !watcher.poll() {
+            assert!(Instant::now() < deadline, "no change reported");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(!watcher.poll());
+    }
+
+    #[test]
+    fn test_other_files_are_ignored() {
+        let dir = TempDir::new("watch");
+        let mut watcher = ConfigWatcher::new(&dir.join("config.toml")).unwrap();
+
+        fs::write(dir.join("other.toml"), "x = 1").unwrap();
+        std::thread::sleep(DEBOUNCE * 2);
+        assert!(!watcher.poll_at(Instant::now() + DEBOUNCE));
+    }
+}
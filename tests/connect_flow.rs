@@ -0,0 +1,474 @@
+// ============================================================================
+// tests/connect_flow.rs - End-to-end: keys → App → McpClient → mock server
+// ============================================================================
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use mcp_client::app::App;
+use mcp_client::config::{Config, McpServerConfig, Profile};
+use mcp_client::event::Event;
+use mcp_client::inspector::TrafficDirection;
+use mcp_client::mock::{self, Fixture, MockHandle};
+use mcp_client::mode::Mode;
+use mcp_client::state::EntryKind;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Demo fixture without the scheduled log notification, so output is stable
+async fn start_mock() -> MockHandle {
+    let fixture = Fixture { notifications: Vec::new(), ..Fixture::demo() };
+    mock::spawn(fixture).await.expect("mock server starts")
+}
+
+fn app_for(name: &str, url: String) -> App {
+    App::new(Config {
+        mcp_servers: vec![McpServerConfig { name: name.to_string(), url, ..Default::default() }],
+        ..Default::default()
+    })
+}
+
+async fn press(app: App, code: KeyCode) -> App {
+    let key = KeyEvent::new(code, KeyModifiers::NONE);
+    app.handle_event(Event::Key(key)).await.expect("key handled")
+}
+
+/// Types `:<text><Enter>` in NORMAL mode
+async fn command(mut app: App, text: &str) -> App {
+    app = press(app, KeyCode::Char(':')).await;
+    assert_eq!(app.mode(), Mode::Command);
+    for c in text.chars() {
+        app = press(app, KeyCode::Char(c)).await;
+    }
+    press(app, KeyCode::Enter).await
+}
+
+/// Feeds ticks (each drains the queued MCP events) until `done` holds
+async fn pump_until(mut app: App, what: &str, done: impl Fn(&App) -> bool) -> App {
+    let deadline = Instant::now() + TIMEOUT;
+    while !done(&app) {
+        if Instant::now() > deadline {
+            panic!(
+                "timed out waiting for {}\nstatus: {}\noutput:\n{}",
+                what,
+                app.status(),
+                app.output().iter().map(|entry| entry.text.as_str()).collect::<Vec<_>>().join("\n")
+            );
+        }
+        app = app.handle_event(Event::Tick).await.expect("tick handled");
+        sleep(Duration::from_millis(2)).await;
+    }
+    app
+}
+
+fn has_line(app: &App, needle: &str) -> bool {
+    app.output().iter().any(|entry| entry.text.contains(needle))
+}
+
+async fn connected(mock: &MockHandle) -> App {
+    let app = command(app_for("e2e-mock", mock.sse_url()), "mcp connect e2e-mock").await;
+    assert_eq!(app.status(), "Connecting to e2e-mock...");
+    pump_until(app, "tools to load", |app| app.status() == "Loaded 5 tools").await
+}
+
+#[tokio::test]
+async fn test_connect_initializes_and_loads_tools() {
+    let mock = start_mock().await;
+    let app = connected(&mock).await;
+
+    assert!(has_line(&app, "✅ MCP session initialized"));
+    assert!(has_line(&app, "✅ Stored 5 tools in App state"));
+    assert!(has_line(&app, "  • echo: Echo the given text back"));
+    assert!(has_line(&app, "Total: 5 tools available"));
+
+    let methods = mock.received_methods().await;
+    assert_eq!(methods, vec!["initialize", "tools/list"]);
+    let initialize = &mock.received().await[0];
+    assert_eq!(initialize["params"]["clientInfo"]["name"], "mcp-client");
+    assert_eq!(initialize["params"]["protocolVersion"], "2024-11-05");
+
+    // Both round trips are visible in the inspector with latencies
+    let traffic = app.inspector().filtered();
+    let summary: Vec<(TrafficDirection, Option<&str>)> =
+        traffic.iter().map(|e| (e.direction, e.method.as_deref())).collect();
+    assert_eq!(
+        summary,
+        vec![
+            (TrafficDirection::Outgoing, Some("initialize")),
+            (TrafficDirection::Incoming, Some("initialize")),
+            (TrafficDirection::Outgoing, Some("tools/list")),
+            (TrafficDirection::Incoming, Some("tools/list")),
+        ]
+    );
+    assert!(traffic[1].latency.is_some());
+    assert!(traffic[3].latency.is_some());
+}
+
+#[tokio::test]
+async fn test_one_tick_drains_all_queued_events() {
+    let mock = start_mock().await;
+    let mut app = command(app_for("e2e-mock", mock.sse_url()), "mcp connect e2e-mock").await;
+    assert!(app.take_redraw());
+
+    // Handshake, tool list and their log lines all queue up meanwhile
+    let deadline = Instant::now() + TIMEOUT;
+    while mock.received_methods().await.len() < 2 && Instant::now() < deadline {
+        sleep(Duration::from_millis(10)).await;
+    }
+    sleep(Duration::from_millis(200)).await;
+    let mut app = app.handle_event(Event::Tick).await.unwrap();
+    assert_eq!(app.status(), "Loaded 5 tools");
+    assert!(app.take_redraw());
+
+    // Nothing new: no redraw needed
+    let mut app = app.handle_event(Event::Tick).await.unwrap();
+    assert!(!app.take_redraw());
+}
+
+#[tokio::test]
+async fn test_run_tool_shows_result() {
+    let mock = start_mock().await;
+    let app = connected(&mock).await;
+
+    let app = command(app, "mcp run echo hello-e2e").await;
+    assert_eq!(app.status(), "Calling tool 'echo'...");
+    assert!(has_line(&app, r#"🔧 Calling 'echo' with: {"text":"hello-e2e"}"#));
+
+    let app = pump_until(app, "tool result", |app| {
+        app.output().iter().any(|entry| entry.text == "hello-e2e")
+    })
+    .await;
+    assert!(has_line(&app, "📋 Tool result 1:"));
+
+    let result = app.output().iter().find(|entry| entry.text == "hello-e2e").unwrap();
+    assert_eq!(result.kind, EntryKind::Result);
+    assert_eq!(result.server.as_deref(), Some("e2e-mock"));
+    assert!(result.request.is_some());
+    let echo = app.output().iter().find(|entry| entry.text == ":mcp run echo hello-e2e").unwrap();
+    assert_eq!(echo.kind, EntryKind::Command);
+
+    let call = mock.received().await.into_iter().find(|m| m["method"] == "tools/call").unwrap();
+    assert_eq!(call["params"]["name"], "echo");
+    assert_eq!(call["params"]["arguments"]["text"], "hello-e2e");
+
+    let inspector = app.inspector().clone().with_filter(Some("tools/call".into()));
+    assert_eq!(inspector.filtered().len(), 2);
+}
+
+#[tokio::test]
+async fn test_results_are_grouped_in_foldable_blocks() {
+    let mock = start_mock().await;
+    let app = connected(&mock).await;
+
+    let app = command(app, "mcp run echo first").await;
+    let app = pump_until(app, "first result", |app| has_line(app, "first")).await;
+    let app = command(app, "mcp run echo second").await;
+    let app = pump_until(app, "second block done", |app| {
+        app.output_lines().iter().any(|line| line.text().contains(r#"echo {"text":"second"} · ✅"#))
+    })
+    .await;
+    let second = app.current_block().cloned().expect("newest block is current");
+
+    let shown = |app: &App| app.output_lines().iter().map(|line| line.text()).collect::<Vec<_>>();
+    let lines = shown(&app);
+    let header = lines.iter().position(|line| line.starts_with(&format!("▾ #{} echo", second))).unwrap();
+    assert!(lines[header + 1..].iter().any(|line| line == "second"));
+
+    let app = press(press(app, KeyCode::Char('z')).await, KeyCode::Char('a')).await;
+    let lines = shown(&app);
+    assert!(lines.iter().any(|line| line.starts_with(&format!("▸ #{} echo", second))));
+    assert!(!lines.iter().any(|line| line == "second"));
+    assert!(lines.iter().any(|line| line == "first"));
+
+    let app = press(press(app, KeyCode::Char('[')).await, KeyCode::Char('[')).await;
+    assert_ne!(app.current_block(), Some(&second));
+    assert_eq!(app.status(), format!("Block #{}", app.current_block().unwrap()));
+    let app = press(press(app, KeyCode::Char(']')).await, KeyCode::Char(']')).await;
+    assert_eq!(app.current_block(), Some(&second));
+
+    let app = press(press(app, KeyCode::Char('z')).await, KeyCode::Char('o')).await;
+    assert!(shown(&app).iter().any(|line| line == "second"));
+}
+
+#[tokio::test]
+async fn test_tool_calls_are_tracked_in_flight() {
+    let mock = start_mock().await;
+    let app = connected(&mock).await;
+
+    // The command returns before the slow tool answers
+    let app = command(app, "mcp run slow_search R 5").await;
+    assert_eq!(app.in_flight().len(), 1);
+    assert_eq!(app.in_flight()[0].label, "slow_search");
+    assert_eq!(app.in_flight()[0].server.as_deref(), Some("e2e-mock"));
+
+    let app = command(app, r#"rpc resources/read {"uri": "file:///board/README.md"}"#).await;
+    assert_eq!(app.in_flight().len(), 2);
+
+    let app = pump_until(app, "both responses", |app| app.in_flight().is_empty()).await;
+    assert!(has_line(&app, "# Demo board"));
+    assert!(has_line(&app, "matching R"));
+}
+
+#[tokio::test]
+async fn test_expired_request_is_cancelled() {
+    let mock = start_mock().await;
+    let app = App::new(Config {
+        mcp_servers: vec![McpServerConfig {
+            name: "e2e-mock".into(),
+            url: mock.sse_url(),
+            request_timeout: Some(1),
+            ..Default::default()
+        }],
+        ..Default::default()
+    });
+    let app = command(app, "mcp connect e2e-mock").await;
+    let app = pump_until(app, "tools to load", |app| app.status() == "Loaded 5 tools").await;
+
+    let app = command(app, "mcp run slow_search R 5").await;
+    let app = command(app, "mcp pending").await;
+    assert!(has_line(&app, "⏳ 1 request(s) in flight:"));
+    assert!(app.output().iter().any(|l| l.text.contains("slow_search") && l.text.contains("e2e-mock") && l.text.contains("(timeout 1 s)")));
+
+    let app = pump_until(app, "timeout", |app| has_line(app, "⏱️  slow_search")).await;
+    assert!(app.in_flight().is_empty());
+    assert!(has_line(&app, "timed out after 1 s - cancelled"));
+
+    // The late response is dropped, and the server was told
+    let app = pump_until(app, "late response", |app| has_line(app, "Ignoring late response")).await;
+    assert!(!has_line(&app, "matching R"));
+    let cancel = mock
+        .received()
+        .await
+        .into_iter()
+        .find(|m| m["method"] == "notifications/cancelled")
+        .expect("cancellation sent");
+    let call = mock.received().await.into_iter().find(|m| m["method"] == "tools/call").unwrap();
+    assert_eq!(cancel["params"]["requestId"], call["id"]);
+    assert_eq!(cancel["params"]["reason"], "timed out after 1 s");
+}
+
+#[tokio::test]
+async fn test_json_tool_result_is_pretty_printed() {
+    let mock = start_mock().await;
+    let app = connected(&mock).await;
+
+    let app = command(app, "mcp run get_board_info").await;
+    let app = pump_until(app, "board info", |app| has_line(app, "\"layers\": 4")).await;
+    assert!(has_line(&app, "\"components\": 3"));
+
+    let app = command(app, "tree").await;
+    assert_eq!(app.status(), "Result 1 as a tree: {3 keys}");
+    let app = press(app, KeyCode::Char('j')).await;
+    let mut app = press(app, KeyCode::Char('Y')).await;
+    assert_eq!(app.take_clipboard().as_deref(), Some("$.components"));
+    let mut app = press(app, KeyCode::Char('y')).await;
+    assert_eq!(app.take_clipboard().as_deref(), Some("3"));
+    assert_eq!(app.tree().unwrap().rows().len(), 4);
+    let app = press(app, KeyCode::Char('q')).await;
+    assert!(app.tree().is_none());
+}
+
+#[tokio::test]
+async fn test_query_selects_from_result() {
+    let mock = start_mock().await;
+    let app = connected(&mock).await;
+
+    let app = command(app, "query .layers").await;
+    assert_eq!(app.status(), "No results yet");
+
+    let app = command(app, "mcp run get_board_info").await;
+    let app = pump_until(app, "board info", |app| has_line(app, "\"layers\": 4")).await;
+    let app = command(app, "query 1 .layers, .nets | select(. > 5)").await;
+    assert_eq!(app.status(), "Query on result 1: 1 match");
+    let last: Vec<&str> = app.output().iter().rev().take(2).map(|entry| entry.text.as_str()).collect();
+    assert_eq!(last, vec!["12", "🔎 Query on result 1: 1 match"]);
+
+    let app = command(app, "query $.layers[*]").await;
+    assert!(has_line(&app, "❌ Query error: cannot iterate over number"));
+    assert!(app.status().starts_with("Error:"));
+}
+
+#[tokio::test]
+async fn test_tool_error_is_reported() {
+    let mock = start_mock().await;
+    let app = connected(&mock).await;
+
+    let app = command(app, "mcp run explode").await;
+    let app = pump_until(app, "error output", |app| has_line(app, "The tool exploded")).await;
+    assert!(has_line(&app, "❌ [MCP Error] RPC error"));
+
+    let app = command(app, "output filter error").await;
+    let shown: Vec<String> = app.output_lines().iter().map(|line| line.text()).collect();
+    assert!(shown.iter().any(|line| line.contains("The tool exploded")));
+    assert!(!shown.iter().any(|line| line.starts_with(":mcp")));
+}
+
+#[tokio::test]
+async fn test_missing_argument_is_caught_before_sending() {
+    let mock = start_mock().await;
+    let app = connected(&mock).await;
+
+    let app = command(app, "mcp run echo").await;
+    assert!(has_line(&app, "❌ Argument error: Missing required parameter: text"));
+    assert!(app.status().starts_with("Error:"));
+    assert!(!mock.received_methods().await.iter().any(|m| m == "tools/call"));
+}
+
+#[tokio::test]
+async fn test_raw_rpc_round_trip() {
+    let mock = start_mock().await;
+    let app = connected(&mock).await;
+
+    let app = command(app, r#"rpc resources/read {"uri": "file:///board/README.md"}"#).await;
+    let app = pump_until(app, "rpc response", |app| has_line(app, "← resources/read #")).await;
+    assert!(has_line(&app, "# Demo board"));
+    assert!(app.status().starts_with("resources/read: ok"));
+    assert!(has_line(&app, "- result 1"));
+
+    let app = command(app, "result 2").await;
+    assert!(app.pager().is_none());
+    assert_eq!(app.status(), "Error: no result 2 (results 1-1 so far)");
+
+    let app = command(app, "result").await;
+    let pager = app.pager().expect("pager open");
+    assert!(pager.title().starts_with("Result 1 · resources/read"));
+    assert_eq!(pager.len(), app.results().last().unwrap().lines().len());
+    assert!(app.results().last().unwrap().lines().iter().any(|line| line.contains("# Demo board")));
+    let app = press(app, KeyCode::Char('q')).await;
+    assert!(app.pager().is_none());
+}
+
+#[tokio::test]
+async fn test_unreachable_server() {
+    // Bind and drop to get a port nobody listens on
+    let addr = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap()
+    };
+    let app = command(app_for("e2e-down", format!("http://{}/sse", addr)), "mcp connect e2e-down").await;
+    let app = pump_until(app, "connect error", |app| has_line(app, "❌ [MCP Error] Connect error")).await;
+    assert!(app.inspector().is_empty());
+}
+
+#[tokio::test]
+async fn test_startup_commands_wait_for_connection() {
+    let mock = start_mock().await;
+    let app = app_for("e2e-mock", mock.sse_url())
+        .with_startup_commands(vec!["mcp connect e2e-mock".into(), "mcp run echo from-startup".into()])
+        .run_startup_commands()
+        .await
+        .unwrap();
+
+    // The tool call is deferred until the tools are loaded
+    assert!(!has_line(&app, ":mcp run echo from-startup"));
+    let app = pump_until(app, "startup tool result", |app| {
+        app.output().iter().any(|entry| entry.text == "from-startup")
+    })
+    .await;
+    assert!(has_line(&app, ":mcp run echo from-startup"));
+    assert_eq!(mock.received_methods().await, vec!["initialize", "tools/list", "tools/call"]);
+}
+
+#[tokio::test]
+async fn test_added_server_is_saved_and_connectable() {
+    let mock = start_mock().await;
+    let path = std::env::temp_dir().join(format!("mcp-client-e2e-{}.toml", uuid::Uuid::new_v4()));
+    let app = App::new(Config::load(&path).unwrap());
+    assert!(has_line(&app, "No MCP servers configured"));
+
+    let app = command(app, &format!("mcp add live {}", mock.sse_url())).await;
+    assert!(has_line(&app, "➕ Added server 'live' (saved to"));
+    assert!(!has_line(&app, "is a stdio server"));
+    assert_eq!(Config::from_file(&path).unwrap().server_names(), vec!["live"]);
+
+    // stdio entries are saved, with a warning that they cannot be connected
+    let app = command(app, "mcp add local npx some-server").await;
+    assert!(has_line(&app, "⚠️  'local' is a stdio server"));
+    let app = command(app, "mcp remove local").await;
+
+    // Editing without an endpoint reopens the command line, prefilled
+    let app = command(app, "mcp edit live").await;
+    assert_eq!(app.mode(), Mode::Command);
+    assert_eq!(app.command_buffer(), format!("mcp edit live {}", mock.sse_url()));
+    let app = press(app, KeyCode::Enter).await;
+    assert!(has_line(&app, "✏️  Updated server 'live'"));
+
+    let app = command(app, "mcp connect live").await;
+    let app = pump_until(app, "tools to load", |app| app.status() == "Loaded 5 tools").await;
+
+    let app = command(app, "mcp remove live").await;
+    assert!(has_line(&app, "➖ Removed server 'live'"));
+    assert!(Config::from_file(&path).unwrap().mcp_servers.is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_config_changes_are_hot_reloaded() {
+    let mock = start_mock().await;
+    let path = std::env::temp_dir().join(format!("mcp-client-e2e-{}.toml", uuid::Uuid::new_v4()));
+    let live = format!("[[mcp_servers]]\nname = \"live\"\nurl = \"{}\"\n", mock.sse_url());
+    std::fs::write(&path, &live).unwrap();
+
+    let app = App::new(Config::from_file(&path).unwrap()).with_config_watch();
+    let app = command(app, "mcp connect live").await;
+    let app = pump_until(app, "tools to load", |app| app.status() == "Loaded 5 tools").await;
+
+    // A new server and a changed setting on the connected one
+    let extra = "[[mcp_servers]]\nname = \"extra\"\ncommand = \"mcp-extra\"\n";
+    std::fs::write(&path, format!("{}env = {{ DEBUG = \"1\" }}\n\n{}", live, extra)).unwrap();
+    let app = pump_until(app, "reload", |app| has_line(app, "🔄 Config reloaded: +extra, ~live")).await;
+    assert!(has_line(&app, "'live' changed - :mcp reconnect"));
+
+    // A broken file keeps the running config
+    std::fs::write(&path, "[[mcp_servers]\n").unwrap();
+    let app = pump_until(app, "reload error", |app| has_line(app, "❌ Config reload failed")).await;
+    let app = command(app, "mcp connect").await;
+    assert_eq!(app.server_selection().unwrap().servers(), ["live", "extra"]);
+    let app = press(app, KeyCode::Esc).await;
+
+    let app = command(app, "mcp reconnect").await;
+    assert_eq!(app.status(), "Connecting to live...");
+    pump_until(app, "tools after reconnect", |app| app.status() == "Loaded 5 tools").await;
+    assert_eq!(mock.received_methods().await.iter().filter(|m| *m == "initialize").count(), 2);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_production_profile_confirms_destructive_tools() {
+    let mock = start_mock().await;
+    let config = Config {
+        mcp_servers: vec![McpServerConfig { name: "e2e-mock".into(), url: mock.sse_url(), ..Default::default() }],
+        profiles: [
+            ("dev".to_string(), Profile::default()),
+            ("prod".to_string(), Profile { production: true, ..Default::default() }),
+        ]
+        .into(),
+        ..Default::default()
+    };
+    let app = command(App::new(config), "profile prod").await;
+    assert!(has_line(&app, "🌐 Profile 'prod' (production)"));
+    assert_eq!(app.profile(), Some("prod"));
+
+    let app = command(app, "mcp connect e2e-mock").await;
+    let app = pump_until(app, "tools to load", |app| app.status() == "Loaded 5 tools").await;
+
+    // Read-only tools run without asking
+    let app = command(app, "mcp run echo safe").await;
+    assert!(!app.is_confirming());
+    let app = pump_until(app, "echo result", |app| app.output().iter().any(|entry| entry.text == "safe")).await;
+
+    let app = command(app, "mcp run soft_fail").await;
+    assert!(app.is_confirming());
+    assert!(has_line(&app, "'soft_fail' may modify data and profile 'prod' is production"));
+    let app = press(app, KeyCode::Char('n')).await;
+    assert!(has_line(&app, "🚫 Cancelled 'soft_fail'"));
+    assert_eq!(mock.received_methods().await.iter().filter(|m| *m == "tools/call").count(), 1);
+
+    // Raw tools/call requests are held back too
+    let app = command(app, r#"rpc tools/call {"name": "soft_fail"}"#).await;
+    assert!(app.is_confirming());
+    let app = press(app, KeyCode::Char('y')).await;
+    pump_until(app, "confirmed call", |app| has_line(app, "Board file is locked")).await;
+    let call = mock.received().await.into_iter().rev().find(|m| m["method"] == "tools/call").unwrap();
+    assert_eq!(call["params"]["name"], "soft_fail");
+}
@@ -0,0 +1,97 @@
+// ============================================================================
+// tests/headless.rs - Headless subcommands against the mock server
+// ============================================================================
+
+use mcp_client::config::{Config, McpServerConfig, Profile};
+use mcp_client::headless::{exit, result_exit_code, HeadlessCommand, HeadlessError};
+use mcp_client::mock::{self, Fixture, MockHandle};
+
+async fn setup() -> (MockHandle, Config) {
+    let mock = mock::spawn(Fixture { notifications: Vec::new(), ..Fixture::demo() }).await.unwrap();
+    let config = Config {
+        mcp_servers: vec![McpServerConfig { name: "ci".into(), url: mock.sse_url(), ..Default::default() }],
+        ..Default::default()
+    };
+    (mock, config)
+}
+
+fn command(args: &[&str]) -> HeadlessCommand {
+    let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    HeadlessCommand::parse(&args).unwrap().expect("a subcommand")
+}
+
+#[tokio::test]
+async fn test_list_tools_skips_auto_load() {
+    let (mock, config) = setup().await;
+    let result = command(&["list-tools", "--server", "ci"]).run(&config).await.unwrap();
+    assert_eq!(result["tools"].as_array().unwrap().len(), 5);
+    assert_eq!(mock.received_methods().await, vec!["initialize", "tools/list"]);
+}
+
+#[tokio::test]
+async fn test_call_with_json_and_positional_args() {
+    let (mock, config) = setup().await;
+
+    let result = command(&["call", "--server", "ci", "--tool", "echo", "--args", r#"{"text": "json"}"#])
+        .run(&config)
+        .await
+        .unwrap();
+    assert_eq!(result["content"][0]["text"], "json");
+    assert_eq!(result_exit_code(&result), exit::OK);
+
+    let result = command(&["call", "--server", "ci", "--tool", "slow_search", "--args", "R 5"])
+        .run(&config)
+        .await
+        .unwrap();
+    assert!(result["content"][0]["text"].as_str().unwrap().contains("matching R"));
+    let call = mock.received().await.into_iter().rev().find(|m| m["method"] == "tools/call").unwrap();
+    assert_eq!(call["params"]["arguments"], serde_json::json!({"pattern": "R", "limit": 5}));
+}
+
+#[tokio::test]
+async fn test_failures_map_to_exit_codes() {
+    let (_mock, config) = setup().await;
+
+    let result = command(&["call", "--server", "ci", "--tool", "soft_fail"]).run(&config).await.unwrap();
+    assert_eq!(result_exit_code(&result), exit::FAILED);
+
+    let err = command(&["call", "--server", "ci", "--tool", "explode"]).run(&config).await.unwrap_err();
+    assert!(matches!(&err, HeadlessError::Rpc(e) if e.code == -32603));
+    assert_eq!(err.exit_code(), exit::FAILED);
+
+    let err = command(&["read-resource", "--server", "ci", "--uri", "file:///nope"]).run(&config).await.unwrap_err();
+    assert_eq!(err.exit_code(), exit::FAILED);
+
+    let err = command(&["list-tools", "--server", "other"]).run(&config).await.unwrap_err();
+    assert_eq!(err.exit_code(), exit::USAGE);
+
+    let err = command(&["call", "--server", "ci", "--tool", "slow_search", "--args", "{\"pattern\": \"U\"}", "--timeout", "1"])
+        .run(&config)
+        .await
+        .unwrap_err();
+    assert_eq!(err.exit_code(), exit::TIMEOUT);
+}
+
+#[tokio::test]
+async fn test_production_profile_needs_yes_for_destructive_tools() {
+    let (mock, config) = setup().await;
+    let profile = Profile { production: true, ..Default::default() };
+    let config = Config { profiles: [("prod".to_string(), profile)].into(), ..config }
+        .with_profile(Some("prod"))
+        .unwrap();
+
+    let err = command(&["call", "--server", "ci", "--tool", "soft_fail"]).run(&config).await.unwrap_err();
+    assert!(matches!(&err, HeadlessError::Unconfirmed(tool) if tool == "soft_fail"));
+    assert_eq!(err.exit_code(), exit::USAGE);
+    assert!(!mock.received_methods().await.contains(&"tools/call".to_string()));
+
+    // Read-only tools run without it
+    let result = command(&["call", "--server", "ci", "--tool", "echo", "--args", r#"{"text": "hi"}"#])
+        .run(&config)
+        .await
+        .unwrap();
+    assert_eq!(result["content"][0]["text"], "hi");
+
+    let result = command(&["call", "--server", "ci", "--tool", "soft_fail", "--yes"]).run(&config).await.unwrap();
+    assert_eq!(result_exit_code(&result), exit::FAILED);
+}